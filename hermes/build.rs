@@ -59,6 +59,18 @@ fn main() {
         .output()
         .expect("failed to execute process");
 
+    // Generate the gRPC service and message types for the price service API. tonic-build shells
+    // out to `protoc`, so unless one is given in PROTOC use the binary vendored for this host
+    // rather than requiring it on every machine and build image.
+    if env::var_os("PROTOC").is_none() {
+        env::set_var(
+            "PROTOC",
+            protoc_bin_vendored::protoc_bin_path().expect("no vendored protoc for this host"),
+        );
+    }
+    tonic_build::compile_protos("proto/price_service.proto")
+        .expect("failed to compile protobuf definitions");
+
     // Build the Go library.
     let mut cmd = Command::new("go");
     cmd.arg("build")
@@ -1,7 +1,10 @@
 use {
     libp2p::Multiaddr,
     std::{
-        net::SocketAddr,
+        net::{
+            IpAddr,
+            SocketAddr,
+        },
         path::PathBuf,
     },
     structopt::StructOpt,
@@ -45,6 +48,55 @@ pub enum Options {
         #[structopt(long, default_value = "127.0.0.1:33999")]
         rpc_addr: SocketAddr,
 
+        /// The address to bind the gRPC server to.
+        #[structopt(long, default_value = "127.0.0.1:33998")]
+        grpc_addr: SocketAddr,
+
+        /// The address to serve Prometheus metrics on. Metrics are kept off the public RPC
+        /// address, so they are neither exposed to clients nor subject to their rate limits.
+        #[structopt(long, default_value = "127.0.0.1:33997")]
+        metrics_addr: SocketAddr,
+
+        /// REST requests and gRPC calls allowed per second from each IP address.
+        ///
+        /// The IP address is the one the connection comes from. Behind a reverse proxy or load
+        /// balancer that is the proxy's, so every client shares one budget unless the proxy is
+        /// listed in `--trusted-proxies`.
+        #[structopt(long, default_value = "10", env = "REST_RATE_LIMIT")]
+        rest_rate_limit: u32,
+
+        /// Websocket messages allowed per second from each IP address.
+        #[structopt(long, default_value = "5", env = "WS_RATE_LIMIT")]
+        ws_rate_limit: u32,
+
+        /// API keys clients can send in the `X-Api-Key` header to be rate limited by key instead
+        /// of by IP address (separated by comma).
+        #[structopt(long, use_delimiter = true, env = "API_KEYS")]
+        api_keys: Vec<String>,
+
+        /// How many times the per IP rate limits each API key is allowed.
+        #[structopt(long, default_value = "10", env = "API_KEY_RATE_LIMIT_MULTIPLIER")]
+        api_key_rate_limit_multiplier: u32,
+
+        /// IP addresses of reverse proxies whose `X-Forwarded-For` header is trusted (separated by
+        /// comma). Connections from these are rate limited by the client address the proxies
+        /// forwarded instead. Off by default, as any client can set the header itself.
+        #[structopt(long, use_delimiter = true, env = "TRUSTED_PROXIES")]
+        trusted_proxies: Vec<IpAddr>,
+
+        /// A Path to a directory to persist price updates in. When not set, updates are only kept
+        /// in memory.
+        #[structopt(long, env = "STORAGE_PATH")]
+        storage_path: Option<PathBuf>,
+
+        /// How long to keep persisted price updates for, in seconds.
+        #[structopt(long, default_value = "604800", env = "STORAGE_RETENTION")]
+        storage_retention: u64,
+
+        /// How often to remove expired price updates from storage, in seconds.
+        #[structopt(long, default_value = "3600", env = "STORAGE_COMPACTION_INTERVAL")]
+        storage_compaction_interval: u64,
+
         /// Multiaddress to bind Pyth P2P server to.
         #[structopt(long, default_value = "/ip4/127.0.0.1/tcp/34000")]
         p2p_addr: Multiaddr,
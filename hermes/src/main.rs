@@ -6,6 +6,7 @@ use {
     futures::{
         channel::mpsc::Receiver,
         SinkExt,
+        StreamExt,
     },
     std::time::Duration,
     structopt::StructOpt,
@@ -17,6 +18,7 @@ use {
 
 mod config;
 mod macros;
+mod metrics;
 mod network;
 mod store;
 
@@ -43,7 +45,7 @@ fn handle_message(_observation: network::p2p::Observation) -> Result<()> {
 }
 
 /// Initialize the Application. This can be invoked either by real main, or by the Geyser plugin.
-async fn init(_update_channel: Receiver<AccountUpdate>) -> Result<()> {
+async fn init(mut update_channel: Receiver<AccountUpdate>) -> Result<()> {
     log::info!("Initializing PythNet...");
 
     // Parse the command line arguments with StructOpt, will exit automatically on `--help` or
@@ -56,11 +58,28 @@ async fn init(_update_channel: Receiver<AccountUpdate>) -> Result<()> {
             wh_bootstrap_addrs,
             wh_listen_addrs,
             rpc_addr,
+            grpc_addr,
+            metrics_addr,
+            rest_rate_limit,
+            ws_rate_limit,
+            api_keys,
+            api_key_rate_limit_multiplier,
+            trusted_proxies,
+            storage_path,
+            storage_retention,
+            storage_compaction_interval,
             p2p_addr,
             p2p_peer: _,
         } => {
             log::info!("Starting PythNet...");
 
+            // Account updates are not processed yet, drain the channel so they are counted.
+            spawn(async move {
+                while update_channel.next().await.is_some() {
+                    metrics::ACCOUNT_UPDATES_RECEIVED.inc();
+                }
+            });
+
             // Spawn the P2P layer.
             log::info!("Starting P2P server on {}", p2p_addr);
             network::p2p::spawn(
@@ -73,9 +92,39 @@ async fn init(_update_channel: Receiver<AccountUpdate>) -> Result<()> {
 
             // Spawn the RPC server.
             log::info!("Starting RPC server on {}", rpc_addr);
+            log::info!("Starting gRPC server on {}", grpc_addr);
+            log::info!("Serving metrics on {}", metrics_addr);
 
             // TODO: Add max size to the config
-            network::rpc::spawn(rpc_addr.to_string(), Store::new_with_local_cache(1000)).await?;
+            let store = match storage_path {
+                Some(storage_path) => {
+                    log::info!("Persisting price updates to {}", storage_path.display());
+                    Store::new_with_persistent_storage(
+                        1000,
+                        &storage_path,
+                        Duration::from_secs(storage_retention),
+                        Duration::from_secs(storage_compaction_interval),
+                    )?
+                }
+                None => Store::new_with_local_cache(1000),
+            };
+
+            let rate_limits = network::rpc::rate_limit::RateLimits::new(
+                rest_rate_limit,
+                ws_rate_limit,
+                api_keys,
+                api_key_rate_limit_multiplier,
+                trusted_proxies,
+            );
+
+            network::rpc::spawn(
+                rpc_addr.to_string(),
+                grpc_addr.to_string(),
+                metrics_addr.to_string(),
+                store,
+                rate_limits,
+            )
+            .await?;
 
             // Wait on Ctrl+C similar to main.
             tokio::signal::ctrl_c().await?;
@@ -0,0 +1,175 @@
+//! Prometheus metrics for the service, served on `/metrics` from their own address.
+
+use {
+    anyhow::Result,
+    axum::{
+        extract::MatchedPath,
+        http::Request,
+        middleware::Next,
+        response::Response,
+        routing::get,
+        Router,
+    },
+    lazy_static::lazy_static,
+    prometheus::{
+        register_histogram_vec,
+        register_int_counter,
+        register_int_counter_vec,
+        register_int_gauge,
+        Encoder,
+        HistogramVec,
+        IntCounter,
+        IntCounterVec,
+        IntGauge,
+        TextEncoder,
+    },
+    std::time::Instant,
+    tonic::{
+        codegen::{
+            http,
+            BoxFuture,
+            Context,
+            Poll,
+            Service,
+        },
+        server::NamedService,
+    },
+};
+
+lazy_static! {
+    /// VAAs received from the Wormhole network.
+    pub static ref VAAS_RECEIVED: IntCounter =
+        register_int_counter!("hermes_vaas_received_total", "VAAs received from Wormhole").unwrap();
+
+    /// VAAs that could not be parsed or stored.
+    pub static ref VAAS_FAILED: IntCounter =
+        register_int_counter!("hermes_vaas_failed_total", "VAAs that failed to process").unwrap();
+
+    /// Price feed updates stored from received VAAs.
+    pub static ref PRICE_UPDATES: IntCounter =
+        register_int_counter!("hermes_price_updates_total", "Price feed updates stored").unwrap();
+
+    /// Account updates received from PythNet.
+    pub static ref ACCOUNT_UPDATES_RECEIVED: IntCounter = register_int_counter!(
+        "hermes_account_updates_received_total",
+        "Account updates received from PythNet"
+    )
+    .unwrap();
+
+    /// Currently open websocket connections.
+    pub static ref WS_CONNECTIONS: IntGauge =
+        register_int_gauge!("hermes_ws_connections", "Open websocket connections").unwrap();
+
+    /// Storage lookups, labelled by whether the cache held the requested update.
+    pub static ref CACHE_LOOKUPS: IntCounterVec = register_int_counter_vec!(
+        "hermes_cache_lookups_total",
+        "Price update cache lookups",
+        &["result"]
+    )
+    .unwrap();
+
+    /// Latency of RPC requests by endpoint and response status.
+    pub static ref REQUEST_DURATION: HistogramVec = register_histogram_vec!(
+        "hermes_request_duration_seconds",
+        "RPC request latency",
+        &["endpoint", "status"]
+    )
+    .unwrap();
+}
+
+/// Middleware recording the latency of every request to a matched route.
+pub async fn track_request<B>(request: Request<B>, next: Next<B>) -> Response {
+    let endpoint = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_owned())
+        .unwrap_or_default();
+
+    let start = Instant::now();
+    let response = next.run(request).await;
+
+    REQUEST_DURATION
+        .with_label_values(&[&endpoint, response.status().as_str()])
+        .observe(start.elapsed().as_secs_f64());
+
+    response
+}
+
+/// Wraps a gRPC service to record the latency of its calls in `REQUEST_DURATION`, the gRPC
+/// counterpart of `track_request`. The endpoint is the method path and the status the gRPC status
+/// code.
+///
+/// Calls are timed until the response headers, the start of the stream for streaming calls. The
+/// status of a successful response is only sent in its trailers, so calls without a status in
+/// their headers are recorded as OK.
+#[derive(Clone)]
+pub struct TrackGrpc<S>(pub S);
+
+/// gRPC status of calls to methods the service doesn't implement.
+const GRPC_UNIMPLEMENTED: &str = "12";
+
+impl<S: NamedService> NamedService for TrackGrpc<S> {
+    const NAME: &'static str = S::NAME;
+}
+
+impl<S, B, ResBody> Service<http::Request<B>> for TrackGrpc<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<ResBody>>,
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+    ResBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.0.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let endpoint = request.uri().path().to_owned();
+        let start = Instant::now();
+        let response = self.0.call(request);
+
+        Box::pin(async move {
+            let response = response.await?;
+            let status = response
+                .headers()
+                .get("grpc-status")
+                .and_then(|status| status.to_str().ok())
+                .unwrap_or("0")
+                .to_owned();
+
+            // Calls to unknown methods share one label, so clients can't create new series.
+            let endpoint = match status.as_str() {
+                GRPC_UNIMPLEMENTED => "",
+                _ => &endpoint,
+            };
+            REQUEST_DURATION
+                .with_label_values(&[endpoint, &status])
+                .observe(start.elapsed().as_secs_f64());
+
+            Ok(response)
+        })
+    }
+}
+
+/// Serves `/metrics` on `metrics_addr` until the server is shut down.
+pub async fn spawn(metrics_addr: String) -> Result<()> {
+    let app = Router::new().route("/metrics", get(|| async { render() }));
+    axum::Server::bind(&metrics_addr.parse()?)
+        .serve(app.into_make_service())
+        .await?;
+
+    Ok(())
+}
+
+/// Render every registered metric in the Prometheus text format.
+pub fn render() -> String {
+    let mut buffer = Vec::new();
+    if let Err(e) = TextEncoder::new().encode(&prometheus::gather(), &mut buffer) {
+        log::error!("Failed to encode metrics: {:?}", e);
+    }
+    String::from_utf8(buffer).unwrap_or_default()
+}
@@ -1,6 +1,10 @@
 use {
-    self::ws::dispatch_updates,
+    self::{
+        rate_limit::RateLimits,
+        ws::dispatch_updates,
+    },
     crate::{
+        metrics,
         network::p2p::OBSERVATIONS,
         store::{
             Store,
@@ -9,27 +13,39 @@ use {
     },
     anyhow::Result,
     axum::{
-        routing::get,
+        middleware,
+        routing::{
+            get,
+            post,
+        },
         Router,
     },
-    std::sync::Arc,
+    std::{
+        net::SocketAddr,
+        sync::Arc,
+    },
 };
 
+mod grpc;
+pub mod rate_limit;
 mod rest;
+mod sse;
 mod types;
 mod ws;
 
 #[derive(Clone)]
 pub struct State {
-    pub store: Store,
-    pub ws:    Arc<ws::WsState>,
+    pub store:       Store,
+    pub ws:          Arc<ws::WsState>,
+    pub rate_limits: Arc<RateLimits>,
 }
 
 impl State {
-    pub fn new(store: Store) -> Self {
+    pub fn new(store: Store, rate_limits: RateLimits) -> Self {
         Self {
             store,
             ws: Arc::new(ws::WsState::new()),
+            rate_limits: Arc::new(rate_limits),
         }
     }
 }
@@ -38,41 +54,79 @@ impl State {
 ///
 /// Currently this is based on Axum due to the simplicity and strong ecosyjtem support for the
 /// packages they are based on (tokio & hyper).
-pub async fn spawn(rpc_addr: String, store: Store) -> Result<()> {
-    let state = State::new(store);
+pub async fn spawn(
+    rpc_addr: String,
+    grpc_addr: String,
+    metrics_addr: String,
+    store: Store,
+    rate_limits: RateLimits,
+) -> Result<()> {
+    let state = State::new(store, rate_limits);
+
+    let rate_limits = state.rate_limits.clone();
+    tokio::spawn(async move { rate_limits.run_pruning().await });
+
+    // Serve the gRPC API alongside the REST one, sharing the store and subscriber dispatch.
+    let grpc_state = state.clone();
+    tokio::spawn(async move {
+        if let Err(e) = grpc::spawn(grpc_addr, grpc_state).await {
+            log::error!("gRPC server failed: {:?}", e);
+        }
+    });
+
+    tokio::spawn(async move {
+        if let Err(e) = metrics::spawn(metrics_addr).await {
+            log::error!("Metrics server failed: {:?}", e);
+        }
+    });
 
     // Initialize Axum Router. Note the type here is a `Router<State>` due to the use of the
     // `with_state` method which replaces `Body` with `State` in the type signature.
+    //
+    // Route layers only wrap the routes added before them, so `/live` is added after the rate
+    // limit for liveness probes to never be throttled, but before the request metrics.
     let app = Router::new();
     let app = app
         .route("/", get(rest::index))
-        .route("/live", get(rest::live))
         .route("/ws", get(ws::ws_route_handler))
         .route("/api/latest_price_feeds", get(rest::latest_price_feeds))
         .route("/api/latest_vaas", get(rest::latest_vaas))
         .route("/api/get_vaa", get(rest::get_vaa))
         .route("/api/get_vaa_ccip", get(rest::get_vaa_ccip))
         .route("/api/price_feed_ids", get(rest::price_feed_ids))
+        .route("/v2/updates/price/latest", post(rest::latest_updates))
+        .route("/v2/updates/price/stream", get(sse::price_stream))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            rate_limit::limit_rest,
+        ))
+        .route("/live", get(rest::live))
+        .route_layer(middleware::from_fn(metrics::track_request))
         .with_state(state.clone());
 
     // Listen in the background for new VAA's from the Wormhole RPC.
     tokio::spawn(async move {
         loop {
             if let Ok(observation) = OBSERVATIONS.1.lock().unwrap().recv() {
+                metrics::VAAS_RECEIVED.inc();
                 match state.store.store_update(Update::Vaa(observation)) {
                     Ok(updated_feed_ids) => {
+                        metrics::PRICE_UPDATES.inc_by(updated_feed_ids.len() as u64);
                         tokio::spawn(dispatch_updates(updated_feed_ids, state.clone()));
                     }
-                    Err(e) => log::error!("Failed to process VAA: {:?}", e),
+                    Err(e) => {
+                        metrics::VAAS_FAILED.inc();
+                        log::error!("Failed to process VAA: {:?}", e);
+                    }
                 }
             }
         }
     });
 
     // Binds the axum's server to the configured address and port. This is a blocking call and will
-    // not return until the server is shutdown.
+    // not return until the server is shutdown. The peer address is kept for rate limiting.
     axum::Server::bind(&rpc_addr.parse()?)
-        .serve(app.into_make_service())
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
         .await?;
 
     Ok(())
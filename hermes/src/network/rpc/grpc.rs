@@ -0,0 +1,357 @@
+//! gRPC counterpart to the REST and websocket APIs, giving backend integrators typed clients and
+//! HTTP/2 multiplexing. The protobuf definitions live in `proto/price_service.proto`.
+
+use {
+    self::proto::{
+        price_service_server::{
+            PriceService,
+            PriceServiceServer,
+        },
+        GetLatestPriceFeedsRequest,
+        GetLatestPriceFeedsResponse,
+        GetPriceFeedByTimeRequest,
+        PriceFeed,
+        SubscribePriceFeedsRequest,
+    },
+    super::{
+        rate_limit::RateLimits,
+        ws::LastSent,
+    },
+    crate::{
+        metrics::TrackGrpc,
+        store::{
+            proof::batch_vaa::PriceInfo,
+            RequestTime,
+        },
+    },
+    anyhow::Result,
+    futures::{
+        Stream,
+        StreamExt,
+    },
+    pyth_sdk::PriceIdentifier,
+    std::{
+        collections::HashSet,
+        pin::Pin,
+        sync::Arc,
+    },
+    tokio::sync::mpsc,
+    tonic::{
+        service::Interceptor,
+        Request,
+        Response,
+        Status,
+    },
+};
+
+mod proto {
+    tonic::include_proto!("hermes");
+}
+
+impl From<pyth_sdk::Price> for proto::Price {
+    fn from(price: pyth_sdk::Price) -> Self {
+        Self {
+            price:        price.price,
+            conf:         price.conf,
+            expo:         price.expo,
+            publish_time: price.publish_time,
+        }
+    }
+}
+
+impl PriceFeed {
+    pub fn from_price_info(price_info: PriceInfo, verbose: bool, binary: bool) -> Self {
+        Self {
+            id:        price_info.price_feed.id.to_bytes().to_vec(),
+            price:     Some(price_info.price_feed.get_price_unchecked().into()),
+            ema_price: Some(price_info.price_feed.get_ema_price_unchecked().into()),
+            metadata:  verbose.then_some(proto::PriceFeedMetadata {
+                emitter_chain:              price_info.emitter_chain.into(),
+                attestation_time:           price_info.attestation_time,
+                sequence_number:            price_info.sequence_number,
+                price_service_receive_time: price_info.receive_time,
+            }),
+            vaa:       binary.then_some(price_info.vaa_bytes),
+        }
+    }
+}
+
+fn parse_price_id(id: &[u8]) -> Option<PriceIdentifier> {
+    id.try_into().ok().map(PriceIdentifier::new)
+}
+
+fn parse_price_ids(ids: &[Vec<u8>]) -> Option<Vec<PriceIdentifier>> {
+    ids.iter().map(|id| parse_price_id(id)).collect()
+}
+
+fn invalid_price_id() -> Status {
+    Status::invalid_argument("Price feed ids must be 32 bytes")
+}
+
+pub struct GrpcService {
+    state: super::State,
+}
+
+#[tonic::async_trait]
+impl PriceService for GrpcService {
+    async fn get_latest_price_feeds(
+        &self,
+        request: Request<GetLatestPriceFeedsRequest>,
+    ) -> Result<Response<GetLatestPriceFeedsResponse>, Status> {
+        let request = request.into_inner();
+        let price_ids = parse_price_ids(&request.ids).ok_or_else(invalid_price_id)?;
+        let price_feeds_with_update_data = self
+            .state
+            .store
+            .get_price_feeds_with_update_data(price_ids, RequestTime::Latest)
+            .map_err(|_| Status::not_found("Update data not found"))?;
+
+        Ok(Response::new(GetLatestPriceFeedsResponse {
+            price_feeds: price_feeds_with_update_data
+                .batch_vaa
+                .price_infos
+                .into_values()
+                .map(|price_info| {
+                    PriceFeed::from_price_info(price_info, request.verbose, request.binary)
+                })
+                .collect(),
+        }))
+    }
+
+    async fn get_price_feed_by_time(
+        &self,
+        request: Request<GetPriceFeedByTimeRequest>,
+    ) -> Result<Response<PriceFeed>, Status> {
+        let request = request.into_inner();
+        let price_id = parse_price_id(&request.id).ok_or_else(invalid_price_id)?;
+        let price_info = self
+            .state
+            .store
+            .get_price_feeds_with_update_data(
+                vec![price_id],
+                RequestTime::FirstAfter(request.publish_time),
+            )
+            .map_err(|_| Status::not_found("Update data not found"))?
+            .batch_vaa
+            .price_infos
+            .remove(&price_id)
+            .ok_or_else(|| Status::not_found("Update data not found"))?;
+
+        Ok(Response::new(PriceFeed::from_price_info(
+            price_info,
+            request.verbose,
+            request.binary,
+        )))
+    }
+
+    type SubscribePriceFeedsStream = Pin<Box<dyn Stream<Item = Result<PriceFeed, Status>> + Send>>;
+
+    async fn subscribe_price_feeds(
+        &self,
+        request: Request<SubscribePriceFeedsRequest>,
+    ) -> Result<Response<Self::SubscribePriceFeedsStream>, Status> {
+        let request = request.into_inner();
+        let price_ids: HashSet<PriceIdentifier> = parse_price_ids(&request.ids)
+            .ok_or_else(invalid_price_id)?
+            .into_iter()
+            .collect();
+
+        let (id, rx) = self.state.ws.subscribe();
+        log::debug!("New gRPC subscription, assigning id: {}", id);
+
+        let store = self.state.store.clone();
+        let mut last_sent = LastSent::default();
+        let updates = subscription_stream(rx, move |update_feed_ids| {
+            let mut price_feeds = Vec::new();
+            for price_id in update_feed_ids {
+                if !price_ids.contains(&price_id) {
+                    continue;
+                }
+
+                let price_info = match store
+                    .get_price_feeds_with_update_data(vec![price_id], RequestTime::Latest)
+                    .map(|mut data| data.batch_vaa.price_infos.remove(&price_id))
+                {
+                    Ok(Some(price_info)) => price_info,
+                    Ok(None) => continue,
+                    Err(e) => {
+                        log::error!("gRPC subscriber {}: Error fetching update: {}", id, e);
+                        continue;
+                    }
+                };
+
+                if !last_sent.record(price_id, price_info.publish_time) {
+                    continue;
+                }
+
+                price_feeds.push(Ok(PriceFeed::from_price_info(
+                    price_info,
+                    request.verbose,
+                    request.binary,
+                )));
+            }
+            price_feeds
+        });
+
+        Ok(Response::new(Box::pin(updates)))
+    }
+}
+
+/// Turn the feed ids announced by the dispatcher into a stream of the items `f` builds from them.
+fn subscription_stream<T, F>(
+    rx: mpsc::Receiver<Vec<PriceIdentifier>>,
+    f: F,
+) -> impl Stream<Item = T> + Send
+where
+    T: Send,
+    F: FnMut(Vec<PriceIdentifier>) -> Vec<T> + Send,
+{
+    futures::stream::unfold((rx, f), |(mut rx, mut f)| async move {
+        let update_feed_ids = rx.recv().await?;
+        Some((futures::stream::iter(f(update_feed_ids)), (rx, f)))
+    })
+    .flatten()
+}
+
+/// Rejects calls from clients that have exceeded their budget. Clients are identified like REST
+/// clients, by their API key or the address behind any trusted proxies, and every call, including
+/// a subscription, takes one request from their REST budget.
+#[derive(Clone)]
+struct RateLimit(Arc<RateLimits>);
+
+impl Interceptor for RateLimit {
+    fn call(&mut self, request: Request<()>) -> Result<Request<()>, Status> {
+        let peer = request
+            .remote_addr()
+            .ok_or_else(|| Status::internal("Unknown peer address"))?;
+        let client = self
+            .0
+            .client(peer.ip(), &request.metadata().clone().into_headers());
+        if !self.0.rest.check(&client) {
+            return Err(Status::resource_exhausted("Rate limit exceeded"));
+        }
+        Ok(request)
+    }
+}
+
+/// Serves the gRPC API on `grpc_addr` until the server is shut down.
+pub async fn spawn(grpc_addr: String, state: super::State) -> Result<()> {
+    let rate_limit = RateLimit(state.rate_limits.clone());
+    let service = PriceServiceServer::with_interceptor(GrpcService { state }, rate_limit);
+
+    tonic::transport::Server::builder()
+        .add_service(TrackGrpc(service))
+        .serve(grpc_addr.parse()?)
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::network::rpc::types::{
+            RpcPriceFeed,
+            RpcPriceFeedMetadata,
+        },
+        base64::{
+            engine::general_purpose::STANDARD as base64_standard_engine,
+            Engine as _,
+        },
+    };
+
+    // The proto is written by hand, so this checks it against the JSON types field by field. Every
+    // struct is destructured without `..`, adding, removing or renaming a field on either side
+    // stops this from compiling until the other side and this test are updated to match.
+    #[test]
+    fn test_proto_matches_json_types() {
+        let price = |price| pyth_sdk::Price {
+            price,
+            conf: 2,
+            expo: -8,
+            publish_time: 1_700_000_000,
+        };
+        let price_info = PriceInfo {
+            price_feed:       pyth_sdk::PriceFeed::new(
+                PriceIdentifier::new([1; 32]),
+                price(100),
+                price(99),
+            ),
+            vaa_bytes:        vec![1, 2, 3],
+            publish_time:     1_700_000_000,
+            emitter_chain:    26,
+            attestation_time: 1_700_000_001,
+            receive_time:     1_700_000_002,
+            sequence_number:  7,
+        };
+
+        let RpcPriceFeed {
+            id,
+            price: json_price,
+            ema_price: json_ema_price,
+            metadata,
+            vaa,
+        } = RpcPriceFeed::from_price_info(price_info.clone(), true, true);
+        let PriceFeed {
+            id: proto_id,
+            price: proto_price,
+            ema_price: proto_ema_price,
+            metadata: proto_metadata,
+            vaa: proto_vaa,
+        } = PriceFeed::from_price_info(price_info, true, true);
+
+        assert_eq!(id.to_bytes().to_vec(), proto_id);
+        for (json, proto) in [(json_price, proto_price), (json_ema_price, proto_ema_price)] {
+            let pyth_sdk::Price {
+                price,
+                conf,
+                expo,
+                publish_time,
+            } = json;
+            let proto::Price {
+                price: proto_price,
+                conf: proto_conf,
+                expo: proto_expo,
+                publish_time: proto_publish_time,
+            } = proto.unwrap();
+            assert_eq!(
+                (price, conf, expo, publish_time),
+                (proto_price, proto_conf, proto_expo, proto_publish_time)
+            );
+        }
+
+        let RpcPriceFeedMetadata {
+            emitter_chain,
+            attestation_time,
+            sequence_number,
+            price_service_receive_time,
+        } = metadata.unwrap();
+        let proto::PriceFeedMetadata {
+            emitter_chain: proto_emitter_chain,
+            attestation_time: proto_attestation_time,
+            sequence_number: proto_sequence_number,
+            price_service_receive_time: proto_price_service_receive_time,
+        } = proto_metadata.unwrap();
+        assert_eq!(
+            (
+                u32::from(emitter_chain),
+                attestation_time,
+                sequence_number,
+                price_service_receive_time
+            ),
+            (
+                proto_emitter_chain,
+                proto_attestation_time,
+                proto_sequence_number,
+                proto_price_service_receive_time
+            )
+        );
+
+        // Binary fields are base64 in JSON and raw bytes in the proto.
+        assert_eq!(
+            base64_standard_engine.decode(vaa.unwrap()).unwrap(),
+            proto_vaa.unwrap()
+        );
+    }
+}
@@ -0,0 +1,276 @@
+use {
+    axum::{
+        extract::{
+            ConnectInfo,
+            State,
+        },
+        http::{
+            HeaderMap,
+            Request,
+            StatusCode,
+        },
+        middleware::Next,
+        response::{
+            IntoResponse,
+            Response,
+        },
+    },
+    dashmap::DashMap,
+    std::{
+        collections::HashSet,
+        net::{
+            IpAddr,
+            SocketAddr,
+        },
+        time::{
+            Duration,
+            Instant,
+        },
+    },
+};
+
+/// Header clients send their API key in.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Header reverse proxies append the address of the client they forward for to.
+pub const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+
+/// How often idle buckets are dropped so the limiter doesn't grow with every client ever seen.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The identity a client is rate limited by: its API key if it sent a known one, otherwise the IP
+/// address it connected from.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Client {
+    Ip(IpAddr),
+    ApiKey(String),
+}
+
+struct TokenBucket {
+    tokens:      f64,
+    last_refill: Instant,
+}
+
+/// A token bucket per client. Buckets hold up to one second's worth of tokens, refilled at the
+/// client's rate, and every request or message takes one.
+pub struct RateLimiter {
+    ip_rate:      f64,
+    api_key_rate: f64,
+    buckets:      DashMap<Client, TokenBucket>,
+}
+
+impl RateLimiter {
+    pub fn new(ip_rate: u32, api_key_rate: u32) -> Self {
+        Self {
+            ip_rate:      ip_rate.into(),
+            api_key_rate: api_key_rate.into(),
+            buckets:      DashMap::new(),
+        }
+    }
+
+    fn rate(&self, client: &Client) -> f64 {
+        match client {
+            Client::Ip(_) => self.ip_rate,
+            Client::ApiKey(_) => self.api_key_rate,
+        }
+    }
+
+    /// Take a token from the client's bucket, returning whether one was available.
+    pub fn check(&self, client: &Client) -> bool {
+        self.check_at(client, Instant::now())
+    }
+
+    fn check_at(&self, client: &Client, now: Instant) -> bool {
+        let rate = self.rate(client);
+        let mut bucket = self
+            .buckets
+            .entry(client.clone())
+            .or_insert_with(|| TokenBucket {
+                tokens:      rate,
+                last_refill: now,
+            });
+
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(rate);
+        bucket.last_refill = now;
+
+        if bucket.tokens < 1.0 {
+            return false;
+        }
+        bucket.tokens -= 1.0;
+        true
+    }
+
+    /// Drop the buckets of clients idle long enough to have refilled completely, they are
+    /// indistinguishable from a new bucket.
+    fn prune(&self) {
+        let now = Instant::now();
+        self.buckets
+            .retain(|_, bucket| now.duration_since(bucket.last_refill) < Duration::from_secs(1));
+    }
+}
+
+/// Rate limits applied to RPC clients, with separate budgets for REST requests and websocket
+/// messages. gRPC calls draw from the REST budget.
+pub struct RateLimits {
+    pub rest:        RateLimiter,
+    pub ws:          RateLimiter,
+    api_keys:        HashSet<String>,
+    trusted_proxies: HashSet<IpAddr>,
+}
+
+impl RateLimits {
+    /// Create limits allowing `rest_rate` requests and `ws_rate` websocket messages per second
+    /// from each IP address, and `api_key_multiplier` times as many from each known API key.
+    /// Connections from `trusted_proxies` are limited by the address they forward for.
+    pub fn new(
+        rest_rate: u32,
+        ws_rate: u32,
+        api_keys: Vec<String>,
+        api_key_multiplier: u32,
+        trusted_proxies: Vec<IpAddr>,
+    ) -> Self {
+        Self {
+            rest:            RateLimiter::new(
+                rest_rate,
+                rest_rate.saturating_mul(api_key_multiplier),
+            ),
+            ws:              RateLimiter::new(ws_rate, ws_rate.saturating_mul(api_key_multiplier)),
+            api_keys:        api_keys.into_iter().collect(),
+            trusted_proxies: trusted_proxies.into_iter().collect(),
+        }
+    }
+
+    /// Identify a client by its API key header, falling back to its IP address when the header
+    /// is missing or holds an unknown key. `peer` is the address the connection comes from.
+    pub fn client(&self, peer: IpAddr, headers: &HeaderMap) -> Client {
+        headers
+            .get(API_KEY_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|key| self.api_keys.contains(*key))
+            .map(|key| Client::ApiKey(key.to_owned()))
+            .unwrap_or_else(|| Client::Ip(self.client_ip(peer, headers)))
+    }
+
+    /// The address of the client behind any trusted proxies.
+    ///
+    /// Each proxy appends the address it received the request from to `X-Forwarded-For`, so the
+    /// entries are read from the right and the first one not belonging to a trusted proxy is the
+    /// client. Everything left of it may have been set by the client and is ignored. A request
+    /// from an untrusted peer, or without a usable header, is attributed to the peer.
+    fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.trusted_proxies.contains(&peer) {
+            return peer;
+        }
+
+        let mut client = peer;
+        for value in headers.get_all(FORWARDED_FOR_HEADER).iter().rev() {
+            let value = match value.to_str() {
+                Ok(value) => value,
+                Err(_) => return client,
+            };
+            for entry in value.rsplit(',') {
+                match entry.trim().parse::<IpAddr>() {
+                    Ok(ip) if self.trusted_proxies.contains(&ip) => client = ip,
+                    Ok(ip) => return ip,
+                    Err(_) => return client,
+                }
+            }
+        }
+        client
+    }
+
+    /// Periodically drop idle buckets. This runs until the process exits.
+    pub async fn run_pruning(&self) {
+        loop {
+            tokio::time::sleep(PRUNE_INTERVAL).await;
+            self.rest.prune();
+            self.ws.prune();
+        }
+    }
+}
+
+/// Middleware rejecting REST requests from clients that have exceeded their budget.
+pub async fn limit_rest<B>(
+    State(state): State<super::State>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let client = state.rate_limits.client(addr.ip(), request.headers());
+    if !state.rate_limits.rest.check(&client) {
+        return (StatusCode::TOO_MANY_REQUESTS, "Rate limit exceeded").into_response();
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter() {
+        let limiter = RateLimiter::new(2, 4);
+        let ip = Client::Ip("203.0.113.7".parse().unwrap());
+        let other = Client::Ip("203.0.113.8".parse().unwrap());
+        let api_key = Client::ApiKey("key".to_owned());
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+
+        // A new client starts with a full bucket of one second's worth of requests.
+        assert!(limiter.check_at(&ip, at(0)));
+        assert!(limiter.check_at(&ip, at(0)));
+        assert!(!limiter.check_at(&ip, at(0)));
+        assert!(!limiter.check_at(&ip, at(100)));
+
+        // Other clients have their own buckets, API keys at their own rate.
+        assert!(limiter.check_at(&other, at(100)));
+        assert!((0..4).all(|_| limiter.check_at(&api_key, at(100))));
+        assert!(!limiter.check_at(&api_key, at(100)));
+
+        // Tokens refill at the client's rate, a denied request doesn't take one.
+        assert!(limiter.check_at(&ip, at(500)));
+        assert!(!limiter.check_at(&ip, at(500)));
+        assert!(limiter.check_at(&api_key, at(350)));
+        assert!(!limiter.check_at(&api_key, at(350)));
+
+        // Buckets never hold more than one second's worth, however long the client was idle.
+        assert!(limiter.check_at(&ip, at(60_000)));
+        assert!(limiter.check_at(&ip, at(60_000)));
+        assert!(!limiter.check_at(&ip, at(60_000)));
+    }
+
+    #[test]
+    fn test_client_ip() {
+        let proxy: IpAddr = "10.0.0.1".parse().unwrap();
+        let client: IpAddr = "203.0.113.7".parse().unwrap();
+        let spoofed: IpAddr = "198.51.100.1".parse().unwrap();
+        let limits = RateLimits::new(1, 1, vec![], 1, vec![proxy, "10.0.0.2".parse().unwrap()]);
+        let headers = |values: &[&str]| {
+            let mut headers = HeaderMap::new();
+            for value in values {
+                headers.append(FORWARDED_FOR_HEADER, value.parse().unwrap());
+            }
+            headers
+        };
+
+        // Untrusted peers are limited by their own address whatever they send.
+        assert_eq!(limits.client_ip(client, &headers(&["1.2.3.4"])), client);
+
+        // Behind trusted proxies the rightmost untrusted entry is the client, anything before
+        // it may have been written by the client.
+        let forwarded = headers(&["198.51.100.1, 203.0.113.7, 10.0.0.2"]);
+        assert_eq!(limits.client_ip(proxy, &forwarded), client);
+        let forwarded = headers(&["198.51.100.1", "203.0.113.7"]);
+        assert_eq!(limits.client_ip(proxy, &forwarded), client);
+        assert_ne!(limits.client_ip(proxy, &forwarded), spoofed);
+
+        // Without a usable header the request is attributed to the last trusted hop.
+        assert_eq!(limits.client_ip(proxy, &HeaderMap::new()), proxy);
+        assert_eq!(limits.client_ip(proxy, &headers(&["unknown"])), proxy);
+        assert_eq!(
+            limits.client_ip(proxy, &headers(&["garbage, 10.0.0.2"])),
+            "10.0.0.2".parse::<IpAddr>().unwrap()
+        );
+    }
+}
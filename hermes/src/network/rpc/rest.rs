@@ -31,6 +31,7 @@ use {
 pub enum RestError {
     UpdateDataNotFound,
     CcipUpdateDataNotFound,
+    TooManyPriceIds,
 }
 
 impl IntoResponse for RestError {
@@ -49,6 +50,11 @@ impl IntoResponse for RestError {
 
                 (StatusCode::BAD_GATEWAY, "CCIP update data not found").into_response()
             }
+            RestError::TooManyPriceIds => (
+                StatusCode::BAD_REQUEST,
+                format!("At most {MAX_LATEST_UPDATES_IDS} price feed ids can be requested"),
+            )
+                .into_response(),
         }
     }
 }
@@ -116,6 +122,70 @@ pub async fn latest_price_feeds(
     ))
 }
 
+/// The most price feed ids a single `latest_updates` request may ask for.
+const MAX_LATEST_UPDATES_IDS: usize = 1000;
+
+#[derive(Debug, serde::Deserialize)]
+pub struct LatestUpdatesRequest {
+    ids:     Vec<PriceIdInput>,
+    #[serde(default)]
+    parsed:  bool,
+    #[serde(default)]
+    verbose: bool,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct LatestUpdatesResponse {
+    /// The update data covering every requested feed, each VAA represented in base64 once.
+    ///
+    /// This stays a list rather than one merged payload: every entry is a separately signed batch
+    /// VAA, and target chain contracts take update data as exactly such a list of VAAs (for
+    /// example `updatePriceFeeds(bytes[])` on EVM). There is no format they accept that holds
+    /// several VAAs in one payload, so clients pass this list on unchanged.
+    update_data: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    price_feeds: Option<Vec<RpcPriceFeed>>,
+}
+
+/// Returns the latest update data for many price feeds at once.
+///
+/// The ids are sent in the body, avoiding URL length limits for requests covering hundreds of
+/// feeds, and the VAAs covering them are returned once each instead of once per feed. Duplicate
+/// ids are dropped before checking them against `MAX_LATEST_UPDATES_IDS`.
+pub async fn latest_updates(
+    State(state): State<super::State>,
+    Json(request): Json<LatestUpdatesRequest>,
+) -> Result<Json<LatestUpdatesResponse>, RestError> {
+    let mut price_ids: Vec<PriceIdentifier> = request.ids.into_iter().map(|id| id.into()).collect();
+    price_ids.sort();
+    price_ids.dedup();
+    if price_ids.len() > MAX_LATEST_UPDATES_IDS {
+        return Err(RestError::TooManyPriceIds);
+    }
+
+    let price_feeds_with_update_data = state
+        .store
+        .get_price_feeds_with_update_data(price_ids, RequestTime::Latest)
+        .map_err(|_| RestError::UpdateDataNotFound)?;
+
+    Ok(Json(LatestUpdatesResponse {
+        update_data: price_feeds_with_update_data
+            .batch_vaa
+            .update_data
+            .iter()
+            .map(|vaa_bytes| base64_standard_engine.encode(vaa_bytes))
+            .collect(),
+        price_feeds: request.parsed.then(|| {
+            price_feeds_with_update_data
+                .batch_vaa
+                .price_infos
+                .into_values()
+                .map(|price_info| RpcPriceFeed::from_price_info(price_info, request.verbose, false))
+                .collect()
+        }),
+    }))
+}
+
 #[derive(Debug, serde::Deserialize)]
 pub struct GetVaaQueryParams {
     id:           PriceIdInput,
@@ -213,5 +283,111 @@ pub async fn index() -> impl IntoResponse {
         "/api/latest_vaas?ids[]=<price_feed_id>&ids[]=<price_feed_id_2>&...",
         "/api/get_vaa?id=<price_feed_id>&publish_time=<publish_time_in_unix_timestamp>",
         "/api/get_vaa_ccip?data=<0x<price_feed_id_32_bytes>+<publish_time_unix_timestamp_be_8_bytes>>",
+        "POST /v2/updates/price/latest {\"ids\": [<price_feed_id>, <price_feed_id_2>, ...](, \"parsed\": true)(, \"verbose\": true)}",
+        "/v2/updates/price/stream?ids[]=<price_feed_id>&ids[]=<price_feed_id_2>&..(&parsed=false)(&verbose=true)(&binary=true)(&allow_unordered=true)",
     ])
 }
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::{
+            network::rpc::rate_limit::RateLimits,
+            store::{
+                proof::batch_vaa::PriceInfo,
+                storage::{
+                    Key,
+                    StorageData,
+                },
+                Store,
+            },
+        },
+    };
+
+    fn price_id(i: u16) -> PriceIdentifier {
+        let mut id = [0; 32];
+        id[..2].copy_from_slice(&i.to_be_bytes());
+        PriceIdentifier::new(id)
+    }
+
+    async fn latest_updates_for(
+        state: &super::super::State,
+        ids: &[PriceIdentifier],
+    ) -> Result<LatestUpdatesResponse, RestError> {
+        let request = serde_json::json!({
+            "ids": ids.iter().map(|id| id.to_hex()).collect::<Vec<_>>(),
+            "parsed": true,
+        });
+        latest_updates(
+            State(state.clone()),
+            Json(serde_json::from_value(request).unwrap()),
+        )
+        .await
+        .map(|Json(response)| response)
+    }
+
+    #[tokio::test]
+    async fn test_latest_updates() {
+        // Feeds 0 and 1 are attested in the same VAA, feed 2 in another.
+        let store = Store::new_with_local_cache(10);
+        for (i, vaa_bytes) in [(0, vec![1]), (1, vec![1]), (2, vec![2])] {
+            let price_info = PriceInfo {
+                price_feed: pyth_sdk::PriceFeed::new(
+                    price_id(i),
+                    Default::default(),
+                    Default::default(),
+                ),
+                vaa_bytes,
+                publish_time: 1_700_000_000,
+                ..Default::default()
+            };
+            store
+                .state
+                .insert(
+                    Key::BatchVaa(price_id(i)),
+                    1_700_000_000,
+                    StorageData::BatchVaa(price_info),
+                )
+                .unwrap();
+        }
+        let state = super::super::State::new(store, RateLimits::new(1, 1, vec![], 1, vec![]));
+
+        // Duplicate ids are dropped and every VAA is returned once.
+        let ids = [
+            price_id(0),
+            price_id(1),
+            price_id(0),
+            price_id(2),
+            price_id(2),
+        ];
+        let response = latest_updates_for(&state, &ids).await.ok().unwrap();
+        let mut update_data = response.update_data;
+        update_data.sort();
+        assert_eq!(
+            update_data,
+            vec![
+                base64_standard_engine.encode([1]),
+                base64_standard_engine.encode([2])
+            ]
+        );
+        assert_eq!(response.price_feeds.unwrap().len(), 3);
+
+        // The limit counts distinct ids, so repeating an id any number of times is accepted.
+        let repeated = vec![price_id(0); MAX_LATEST_UPDATES_IDS + 1];
+        let response = latest_updates_for(&state, &repeated).await.ok().unwrap();
+        assert_eq!(response.update_data.len(), 1);
+
+        // Too many distinct ids are rejected before the store is queried.
+        let distinct: Vec<PriceIdentifier> =
+            (0..=MAX_LATEST_UPDATES_IDS as u16).map(price_id).collect();
+        assert!(matches!(
+            latest_updates_for(&state, &distinct).await,
+            Err(RestError::TooManyPriceIds)
+        ));
+        assert!(matches!(
+            latest_updates_for(&state, &distinct[..MAX_LATEST_UPDATES_IDS]).await,
+            Err(RestError::UpdateDataNotFound)
+        ));
+    }
+}
@@ -0,0 +1,265 @@
+use {
+    super::{
+        types::{
+            PriceIdInput,
+            RpcPriceFeed,
+        },
+        ws::{
+            LastSent,
+            SubscriberId,
+        },
+    },
+    crate::store::{
+        RequestTime,
+        Store,
+        UnixTimestamp,
+    },
+    anyhow::Result,
+    axum::{
+        extract::State,
+        http::HeaderMap,
+        response::sse::{
+            Event,
+            KeepAlive,
+            Sse,
+        },
+    },
+    axum_extra::extract::Query,
+    base64::{
+        engine::general_purpose::STANDARD as base64_standard_engine,
+        Engine as _,
+    },
+    futures::Stream,
+    pyth_sdk::PriceIdentifier,
+    std::{
+        collections::{
+            HashSet,
+            VecDeque,
+        },
+        convert::Infallible,
+        time::Duration,
+    },
+};
+
+/// Interval between the heartbeat comments that keep idle connections open through proxies.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Debug, serde::Deserialize)]
+pub struct PriceStreamQueryParams {
+    ids:             Vec<PriceIdInput>,
+    #[serde(default = "default_parsed")]
+    parsed:          bool,
+    #[serde(default)]
+    verbose:         bool,
+    #[serde(default)]
+    binary:          bool,
+    /// Forward every stored update instead of only those newer than the last one sent for the
+    /// feed.
+    #[serde(default)]
+    allow_unordered: bool,
+}
+
+fn default_parsed() -> bool {
+    true
+}
+
+#[derive(Debug, serde::Serialize)]
+struct PriceStreamUpdate {
+    id:     PriceIdentifier,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parsed: Option<RpcPriceFeed>,
+    /// Vaa binary represented in base64.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    binary: Option<String>,
+}
+
+/// Streams price updates as Server-Sent Events, mirroring the websocket subscription for clients
+/// behind proxies that can't keep websocket connections open.
+///
+/// Every event id lists the publish time of the last update sent for each requested feed, in the
+/// order of the `ids` parameter and empty for feeds not sent yet, such as `1700000000,,1700000003`.
+/// Browsers send the last id they saw in the `Last-Event-ID` header when reconnecting, and the
+/// client is then caught up with every requested feed that has been published since its own last
+/// update. An id that doesn't match the requested feeds catches up every feed.
+pub async fn price_stream(
+    State(state): State<super::State>,
+    headers: HeaderMap,
+    Query(params): Query<PriceStreamQueryParams>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let (id, rx) = state.ws.subscribe();
+    log::debug!("New SSE connection, assigning id: {}", id);
+
+    let price_ids: Vec<PriceIdentifier> = params.ids.into_iter().map(|id| id.into()).collect();
+    let mut subscriber = SseSubscriber {
+        id,
+        store: state.store.clone(),
+        price_ids: price_ids.iter().cloned().collect(),
+        event_id_order: price_ids,
+        parsed: params.parsed,
+        verbose: params.verbose,
+        binary: params.binary,
+        allow_unordered: params.allow_unordered,
+        last_sent: LastSent::default(),
+        pending: VecDeque::new(),
+    };
+
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok());
+    if let Some(last_event_id) = last_event_id {
+        subscriber.catch_up(last_event_id);
+    }
+
+    let events = futures::stream::unfold((subscriber, rx), |(mut subscriber, mut rx)| async move {
+        loop {
+            if let Some(event) = subscriber.pending.pop_front() {
+                return Some((Ok(event), (subscriber, rx)));
+            }
+            let update_feed_ids = rx.recv().await?;
+            subscriber.queue_updates(update_feed_ids);
+        }
+    });
+
+    Sse::new(events).keep_alive(
+        KeepAlive::new()
+            .interval(HEARTBEAT_INTERVAL)
+            .text("heartbeat"),
+    )
+}
+
+/// The state of a single SSE connection, turning the feed ids announced by the store into
+/// events for the client.
+struct SseSubscriber {
+    id:              SubscriberId,
+    store:           Store,
+    price_ids:       HashSet<PriceIdentifier>,
+    /// The requested feeds in the order their publish times appear in event ids.
+    event_id_order:  Vec<PriceIdentifier>,
+    parsed:          bool,
+    verbose:         bool,
+    binary:          bool,
+    allow_unordered: bool,
+    last_sent:       LastSent,
+    pending:         VecDeque<Event>,
+}
+
+impl SseSubscriber {
+    /// Queue the latest update of every feed published after its time in `last_event_id`.
+    fn catch_up(&mut self, last_event_id: &str) {
+        match parse_event_id(last_event_id, self.event_id_order.len()) {
+            Some(publish_times) => {
+                for (price_id, publish_time) in self.event_id_order.iter().zip(publish_times) {
+                    if let Some(publish_time) = publish_time {
+                        self.last_sent.record(*price_id, publish_time);
+                    }
+                }
+            }
+            None => log::debug!(
+                "SSE subscriber {}: Catching up every feed, unknown last event id {}",
+                self.id,
+                last_event_id
+            ),
+        }
+
+        for price_id in self.event_id_order.clone() {
+            self.queue_update(price_id, true);
+        }
+    }
+
+    /// The event id for the current state of the stream, see `price_stream`.
+    fn event_id(&self) -> String {
+        self.event_id_order
+            .iter()
+            .map(|price_id| {
+                self.last_sent
+                    .get(price_id)
+                    .map(|publish_time| publish_time.to_string())
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    fn queue_updates(&mut self, update_feed_ids: Vec<PriceIdentifier>) {
+        for price_id in update_feed_ids {
+            if self.price_ids.contains(&price_id) {
+                self.queue_update(price_id, !self.allow_unordered);
+            }
+        }
+    }
+
+    fn queue_update(&mut self, price_id: PriceIdentifier, ordered: bool) {
+        match self.next_event(price_id, ordered) {
+            Ok(Some(event)) => self.pending.push_back(event),
+            Ok(None) => {}
+            Err(e) => log::error!("SSE subscriber {}: Error building update: {}", self.id, e),
+        }
+    }
+
+    fn next_event(&mut self, price_id: PriceIdentifier, ordered: bool) -> Result<Option<Event>> {
+        let price_info = self
+            .store
+            .get_price_feeds_with_update_data(vec![price_id], RequestTime::Latest)?
+            .batch_vaa
+            .price_infos
+            .remove(&price_id)
+            .ok_or_else(|| anyhow::anyhow!("Price feed {} not found.", price_id))?;
+
+        let publish_time = price_info.publish_time;
+        if !self.last_sent.record(price_id, publish_time) && ordered {
+            return Ok(None);
+        }
+
+        let update = PriceStreamUpdate {
+            id:     price_id,
+            binary: self
+                .binary
+                .then(|| base64_standard_engine.encode(&price_info.vaa_bytes)),
+            parsed: self
+                .parsed
+                .then(|| RpcPriceFeed::from_price_info(price_info, self.verbose, false)),
+        };
+
+        Ok(Some(
+            Event::default()
+                .id(self.event_id())
+                .event("price_update")
+                .json_data(update)?,
+        ))
+    }
+}
+
+/// Parse the publish times of an event id for `feeds` requested feeds, or `None` if it was not
+/// produced for the same number of feeds.
+fn parse_event_id(event_id: &str, feeds: usize) -> Option<Vec<Option<UnixTimestamp>>> {
+    let publish_times = event_id
+        .split(',')
+        .map(|publish_time| match publish_time {
+            "" => Some(None),
+            publish_time => publish_time.parse().ok().map(Some),
+        })
+        .collect::<Option<Vec<_>>>()?;
+    (publish_times.len() == feeds).then_some(publish_times)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_event_id() {
+        assert_eq!(
+            parse_event_id("1700000000,,1700000003", 3),
+            Some(vec![Some(1_700_000_000), None, Some(1_700_000_003)])
+        );
+        assert_eq!(
+            parse_event_id("1700000000", 1),
+            Some(vec![Some(1_700_000_000)])
+        );
+        assert_eq!(parse_event_id("", 1), Some(vec![None]));
+
+        // Ids from a stream of other feeds, or not produced by this server, are not used.
+        assert_eq!(parse_event_id("1700000000,1700000003", 3), None);
+        assert_eq!(parse_event_id("1700000000,abc", 2), None);
+    }
+}
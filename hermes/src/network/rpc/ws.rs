@@ -1,9 +1,21 @@
 use {
-    super::types::{
-        PriceIdInput,
-        RpcPriceFeed,
+    super::{
+        rate_limit::{
+            Client,
+            RateLimits,
+        },
+        types::{
+            PriceIdInput,
+            RpcPriceFeed,
+        },
+    },
+    crate::{
+        metrics,
+        store::{
+            Store,
+            UnixTimestamp,
+        },
     },
-    crate::store::Store,
     anyhow::Result,
     axum::{
         extract::{
@@ -12,8 +24,10 @@ use {
                 WebSocket,
                 WebSocketUpgrade,
             },
+            ConnectInfo,
             State,
         },
+        http::HeaderMap,
         response::IntoResponse,
     },
     dashmap::DashMap,
@@ -32,40 +46,63 @@ use {
         Serialize,
     },
     std::{
-        collections::HashMap,
-        sync::atomic::{
-            AtomicUsize,
-            Ordering,
+        collections::{
+            HashMap,
+            HashSet,
+        },
+        net::SocketAddr,
+        sync::{
+            atomic::{
+                AtomicUsize,
+                Ordering,
+            },
+            Arc,
+        },
+        time::Duration,
+    },
+    tokio::{
+        sync::mpsc,
+        time::{
+            Instant,
+            Interval,
+            MissedTickBehavior,
         },
     },
-    tokio::sync::mpsc,
 };
 
+/// How often throttled feeds are checked for updates that are due to be sent.
+const THROTTLE_TICK: Duration = Duration::from_millis(50);
+
 
 pub async fn ws_route_handler(
     ws: WebSocketUpgrade,
     State(state): State<super::State>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
-    ws.on_upgrade(|socket| websocket_handler(socket, state))
+    let client = state.rate_limits.client(addr.ip(), &headers);
+    ws.on_upgrade(|socket| websocket_handler(socket, state, client))
 }
 
-async fn websocket_handler(stream: WebSocket, state: super::State) {
-    let ws_state = state.ws.clone();
-    let id = ws_state.subscriber_counter.fetch_add(1, Ordering::SeqCst);
-
+async fn websocket_handler(stream: WebSocket, state: super::State, client: Client) {
+    let (id, rx) = state.ws.subscribe();
     let (sender, receiver) = stream.split();
 
-    // TODO: Use a configured value for the buffer size or make it const static
-    // TODO: Use redis stream to source the updates instead of a channel
-    let (tx, rx) = mpsc::channel::<Vec<PriceIdentifier>>(1000);
-
-    ws_state.subscribers.insert(id, tx);
-
     log::debug!("New websocket connection, assigning id: {}", id);
 
-    let mut subscriber = Subscriber::new(id, state.store.clone(), rx, receiver, sender);
-
+    let mut subscriber = Subscriber::new(
+        id,
+        state.store.clone(),
+        client,
+        state.rate_limits.clone(),
+        rx,
+        receiver,
+        sender,
+    );
+
+    metrics::WS_CONNECTIONS.inc();
     subscriber.run().await;
+    metrics::WS_CONNECTIONS.dec();
 }
 
 pub type SubscriberId = usize;
@@ -76,28 +113,45 @@ pub struct Subscriber {
     id:                      SubscriberId,
     closed:                  bool,
     store:                   Store,
+    client:                  Client,
+    rate_limits:             Arc<RateLimits>,
     update_rx:               mpsc::Receiver<Vec<PriceIdentifier>>,
     receiver:                SplitStream<WebSocket>,
     sender:                  SplitSink<WebSocket, Message>,
     price_feeds_with_config: HashMap<PriceIdentifier, PriceFeedClientConfig>,
+    last_sent:               LastSent,
+    /// When the last update of each feed was sent, for throttling.
+    sent_at:                 HashMap<PriceIdentifier, Instant>,
+    throttled:               HashSet<PriceIdentifier>,
+    throttle_interval:       Interval,
 }
 
 impl Subscriber {
     pub fn new(
         id: SubscriberId,
         store: Store,
+        client: Client,
+        rate_limits: Arc<RateLimits>,
         update_rx: mpsc::Receiver<Vec<PriceIdentifier>>,
         receiver: SplitStream<WebSocket>,
         sender: SplitSink<WebSocket, Message>,
     ) -> Self {
+        let mut throttle_interval = tokio::time::interval(THROTTLE_TICK);
+        throttle_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
         Self {
             id,
             closed: false,
             store,
+            client,
+            rate_limits,
             update_rx,
             receiver,
             sender,
             price_feeds_with_config: HashMap::new(),
+            last_sent: LastSent::default(),
+            sent_at: HashMap::new(),
+            throttled: HashSet::new(),
+            throttle_interval,
         }
     }
 
@@ -128,6 +182,9 @@ impl Subscriber {
                     Some(message_or_err) => self.handle_client_message(message_or_err?).await?
                 }
             },
+            _ = self.throttle_interval.tick(), if !self.throttled.is_empty() => {
+                self.handle_throttled_updates().await?;
+            },
         }
 
         Ok(())
@@ -138,34 +195,91 @@ impl Subscriber {
         price_feed_ids: Vec<PriceIdentifier>,
     ) -> Result<()> {
         for price_feed_id in price_feed_ids {
-            if let Some(config) = self.price_feeds_with_config.get(&price_feed_id) {
-                let price_feeds_with_update_data = self.store.get_price_feeds_with_update_data(
-                    vec![price_feed_id],
-                    crate::store::RequestTime::Latest,
-                )?;
-                let price_info = price_feeds_with_update_data
-                    .batch_vaa
-                    .price_infos
-                    .get(&price_feed_id)
-                    .ok_or_else(|| {
-                        anyhow::anyhow!("Price feed {} not found.", price_feed_id.to_string())
-                    })?
-                    .clone();
-                let price_feed =
-                    RpcPriceFeed::from_price_info(price_info, config.verbose, config.binary);
-                // Feed does not flush the message and will allow us
-                // to send multiple messages in a single flush.
-                self.sender
-                    .feed(Message::Text(serde_json::to_string(
-                        &ServerMessage::PriceUpdate { price_feed },
-                    )?))
-                    .await?;
+            if let Some(config) = self.price_feeds_with_config.get(&price_feed_id).cloned() {
+                // Updates inside the client's minimum interval are held back, the latest one is
+                // sent once the interval has elapsed.
+                if self.is_throttled(&price_feed_id, &config) {
+                    self.throttled.insert(price_feed_id);
+                    continue;
+                }
+                self.feed_price_update(price_feed_id, &config).await?;
             }
         }
         self.sender.flush().await?;
         Ok(())
     }
 
+    async fn handle_throttled_updates(&mut self) -> Result<()> {
+        let due: Vec<PriceIdentifier> = self
+            .throttled
+            .iter()
+            .filter(|price_feed_id| {
+                self.price_feeds_with_config
+                    .get(price_feed_id)
+                    .map_or(true, |config| !self.is_throttled(price_feed_id, config))
+            })
+            .cloned()
+            .collect();
+
+        for price_feed_id in due {
+            self.throttled.remove(&price_feed_id);
+            if let Some(config) = self.price_feeds_with_config.get(&price_feed_id).cloned() {
+                self.feed_price_update(price_feed_id, &config).await?;
+            }
+        }
+        self.sender.flush().await?;
+        Ok(())
+    }
+
+    fn is_throttled(
+        &self,
+        price_feed_id: &PriceIdentifier,
+        config: &PriceFeedClientConfig,
+    ) -> bool {
+        match (config.min_interval, self.sent_at.get(price_feed_id)) {
+            (Some(min_interval), Some(sent_at)) => sent_at.elapsed() < min_interval,
+            _ => false,
+        }
+    }
+
+    /// Queue the latest update of a feed for the client without flushing it.
+    async fn feed_price_update(
+        &mut self,
+        price_feed_id: PriceIdentifier,
+        config: &PriceFeedClientConfig,
+    ) -> Result<()> {
+        let price_feeds_with_update_data = self.store.get_price_feeds_with_update_data(
+            vec![price_feed_id],
+            crate::store::RequestTime::Latest,
+        )?;
+        let price_info = price_feeds_with_update_data
+            .batch_vaa
+            .price_infos
+            .get(&price_feed_id)
+            .ok_or_else(|| anyhow::anyhow!("Price feed {} not found.", price_feed_id))?
+            .clone();
+
+        // Unless the client allows it, never send an update that isn't newer than the last one.
+        if !self
+            .last_sent
+            .record(price_feed_id, price_info.publish_time)
+            && !config.allow_out_of_order
+        {
+            return Ok(());
+        }
+        self.sent_at.insert(price_feed_id, Instant::now());
+
+        let price_feed = RpcPriceFeed::from_price_info(price_info, config.verbose, config.binary);
+        // Feed does not flush the message and will allow us
+        // to send multiple messages in a single flush.
+        self.sender
+            .feed(Message::Text(serde_json::to_string(
+                &ServerMessage::PriceUpdate { price_feed },
+            )?))
+            .await?;
+        Ok(())
+    }
+
     async fn handle_client_message(&mut self, message: Message) -> Result<()> {
         if let Message::Close(_) = message {
             log::debug!("Subscriber {} closed connection", self.id);
@@ -181,6 +295,18 @@ impl Subscriber {
             }
         };
 
+        if !self.rate_limits.ws.check(&self.client) {
+            self.sender
+                .send(
+                    serde_json::to_string(&ServerMessage::Response(ServerResponseMessage::Err {
+                        error: "Rate limit exceeded".to_string(),
+                    }))?
+                    .into(),
+                )
+                .await?;
+            return Ok(());
+        }
+
         match maybe_client_message {
             Err(e) => {
                 self.sender
@@ -199,17 +325,29 @@ impl Subscriber {
                 ids,
                 verbose,
                 binary,
+                min_interval_ms,
+                allow_out_of_order,
             }) => {
                 for id in ids {
                     let price_id: PriceIdentifier = id.into();
-                    self.price_feeds_with_config
-                        .insert(price_id, PriceFeedClientConfig { verbose, binary });
+                    self.price_feeds_with_config.insert(
+                        price_id,
+                        PriceFeedClientConfig {
+                            verbose,
+                            binary,
+                            min_interval: min_interval_ms.map(Duration::from_millis),
+                            allow_out_of_order,
+                        },
+                    );
                 }
             }
             Ok(ClientMessage::Unsubscribe { ids }) => {
                 for id in ids {
                     let price_id: PriceIdentifier = id.into();
                     self.price_feeds_with_config.remove(&price_id);
+                    self.last_sent.remove(&price_id);
+                    self.sent_at.remove(&price_id);
+                    self.throttled.remove(&price_id);
                 }
             }
         }
@@ -254,8 +392,10 @@ pub async fn dispatch_updates(update_feed_ids: Vec<PriceIdentifier>, state: supe
 
 #[derive(Clone)]
 pub struct PriceFeedClientConfig {
-    verbose: bool,
-    binary:  bool,
+    verbose:            bool,
+    binary:             bool,
+    min_interval:       Option<Duration>,
+    allow_out_of_order: bool,
 }
 
 pub struct WsState {
@@ -270,6 +410,48 @@ impl WsState {
             subscribers:        DashMap::new(),
         }
     }
+
+    /// Register a subscriber with `dispatch_updates`, returning its id and the receiver the ids
+    /// of updated feeds arrive on. Websocket, SSE and gRPC subscribers are all fed this way. The
+    /// dispatcher drops the sender once the receiver is gone with the client's connection.
+    pub fn subscribe(&self) -> (SubscriberId, mpsc::Receiver<Vec<PriceIdentifier>>) {
+        // TODO: Use a configured value for the buffer size or make it const static
+        // TODO: Use redis stream to source the updates instead of a channel
+        let (tx, rx) = mpsc::channel(1000);
+        let id = self.subscriber_counter.fetch_add(1, Ordering::SeqCst);
+        self.subscribers.insert(id, tx);
+        (id, rx)
+    }
+}
+
+/// The publish time of the last update sent to a subscriber for each feed, so that streams only
+/// forward updates newer than the ones the client already has.
+#[derive(Default)]
+pub struct LastSent(HashMap<PriceIdentifier, UnixTimestamp>);
+
+impl LastSent {
+    /// Record an update of the feed published at `publish_time`, returning whether it is newer
+    /// than the last one sent. Older updates are not recorded.
+    pub fn record(&mut self, price_id: PriceIdentifier, publish_time: UnixTimestamp) -> bool {
+        let is_newer = self
+            .0
+            .get(&price_id)
+            .map_or(true, |last_sent| publish_time > *last_sent);
+        if is_newer {
+            self.0.insert(price_id, publish_time);
+        }
+        is_newer
+    }
+
+    /// The publish time of the last update sent for the feed.
+    pub fn get(&self, price_id: &PriceIdentifier) -> Option<UnixTimestamp> {
+        self.0.get(price_id).copied()
+    }
+
+    /// Forget the feed, so that its next update is sent whatever its publish time.
+    pub fn remove(&mut self, price_id: &PriceIdentifier) {
+        self.0.remove(price_id);
+    }
 }
 
 
@@ -278,11 +460,18 @@ impl WsState {
 enum ClientMessage {
     #[serde(rename = "subscribe")]
     Subscribe {
-        ids:     Vec<PriceIdInput>,
+        ids:                Vec<PriceIdInput>,
+        #[serde(default)]
+        verbose:            bool,
+        #[serde(default)]
+        binary:             bool,
+        /// Send at most one update per feed in this many milliseconds. Updates in between are
+        /// skipped, except for the latest which is sent when the interval has elapsed.
         #[serde(default)]
-        verbose: bool,
+        min_interval_ms:    Option<u64>,
+        /// Send every update, including those not newer than the last one sent for the feed.
         #[serde(default)]
-        binary:  bool,
+        allow_out_of_order: bool,
     },
     #[serde(rename = "unsubscribe")]
     Unsubscribe { ids: Vec<PriceIdInput> },
@@ -5,7 +5,11 @@ use {
     },
     anyhow::Result,
     pyth_sdk::PriceIdentifier,
-    std::sync::Arc,
+    std::{
+        path::Path,
+        sync::Arc,
+        time::Duration,
+    },
 };
 
 pub mod proof;
@@ -43,6 +47,22 @@ impl Store {
         }
     }
 
+    /// Create a store that persists updates to disk at `path`, keeping them for `retention` and
+    /// pruning expired ones every `compaction_interval`.
+    pub fn new_with_persistent_storage(
+        max_size_per_key: usize,
+        path: &Path,
+        retention: Duration,
+        compaction_interval: Duration,
+    ) -> Result<Self> {
+        let storage =
+            storage::persistent::PersistentStorage::open(path, max_size_per_key, retention)?;
+        tokio::spawn(storage.clone().run_compaction(compaction_interval));
+        Ok(Self {
+            state: Arc::new(Box::new(storage)),
+        })
+    }
+
     /// Stores the update data in the store and returns the price identifiers for which
     /// price feeds were updated.
     pub fn store_update(&self, update: Update) -> Result<Vec<PriceIdentifier>> {
@@ -36,7 +36,7 @@ use {
 };
 
 // TODO: We need to add more metadata to this struct.
-#[derive(Clone, Default, PartialEq, Debug)]
+#[derive(Clone, Default, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
 pub struct PriceInfo {
     pub price_feed:       PriceFeed,
     pub vaa_bytes:        Vec<u8>,
@@ -9,8 +9,9 @@ use {
 };
 
 pub mod local_cache;
+pub mod persistent;
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
 pub enum StorageData {
     BatchVaa(PriceInfo),
 }
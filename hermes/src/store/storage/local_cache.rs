@@ -6,6 +6,7 @@ use {
         StorageData,
         UnixTimestamp,
     },
+    crate::metrics,
     anyhow::Result,
     dashmap::DashMap,
     std::{
@@ -74,6 +75,7 @@ impl Storage for LocalCache {
                         // not sure that the first element is the closest one.
                         if let Some(oldest_record) = key_cache.front() {
                             if time < oldest_record.time {
+                                metrics::CACHE_LOOKUPS.with_label_values(&["miss"]).inc();
                                 return Ok(None);
                             }
                         }
@@ -94,9 +96,14 @@ impl Storage for LocalCache {
                     }
                 };
 
+                let result = if record.is_some() { "hit" } else { "miss" };
+                metrics::CACHE_LOOKUPS.with_label_values(&[result]).inc();
                 Ok(record.map(|record| record.value))
             }
-            None => Ok(None),
+            None => {
+                metrics::CACHE_LOOKUPS.with_label_values(&["miss"]).inc();
+                Ok(None)
+            }
         }
     }
 
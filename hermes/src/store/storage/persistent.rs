@@ -0,0 +1,286 @@
+use {
+    super::{
+        super::RequestTime,
+        local_cache::LocalCache,
+        Key,
+        Storage,
+        StorageData,
+        UnixTimestamp,
+    },
+    anyhow::{
+        anyhow,
+        Result,
+    },
+    pyth_sdk::PriceIdentifier,
+    std::{
+        path::Path,
+        time::{
+            Duration,
+            SystemTime,
+            UNIX_EPOCH,
+        },
+    },
+};
+
+/// A `Storage` that persists every update to disk, so historical queries can reach back further
+/// than the in-memory cache window.
+///
+/// Records are kept in a sled database keyed by the storage key followed by the big endian time,
+/// so that the records of a key are contiguous and sorted by time. A `LocalCache` sits in front of
+/// the database to serve recent updates without touching the disk. Records older than the
+/// retention period are removed by `run_compaction`.
+#[derive(Clone)]
+pub struct PersistentStorage {
+    cache:     LocalCache,
+    records:   sled::Tree,
+    keys:      sled::Tree,
+    retention: Duration,
+}
+
+impl PersistentStorage {
+    pub fn open(path: &Path, max_size_per_key: usize, retention: Duration) -> Result<Self> {
+        let db = sled::open(path)?;
+        Ok(Self {
+            cache: LocalCache::new(max_size_per_key),
+            records: db.open_tree("records")?,
+            keys: db.open_tree("keys")?,
+            retention,
+        })
+    }
+
+    /// Remove every record older than the retention period, along with keys left without records.
+    pub fn prune(&self) -> Result<usize> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        self.prune_before(now.saturating_sub(self.retention.as_secs()))
+    }
+
+    fn prune_before(&self, cutoff: UnixTimestamp) -> Result<usize> {
+        let mut removed = 0;
+        for prefix in self.keys.iter().keys() {
+            let prefix = prefix?;
+            for record in self
+                .records
+                .range(record_key(&prefix, 0)..record_key(&prefix, cutoff))
+                .keys()
+            {
+                self.records.remove(record?)?;
+                removed += 1;
+            }
+
+            // An insert writes its record before its key, so checking again after removing the
+            // key restores it if a record was inserted in between.
+            if self.records.scan_prefix(&prefix).next().is_none() {
+                self.keys.remove(&prefix)?;
+                if self.records.scan_prefix(&prefix).next().is_some() {
+                    self.keys.insert(&prefix, &[])?;
+                }
+            }
+        }
+
+        self.records.flush()?;
+        self.keys.flush()?;
+        Ok(removed)
+    }
+
+    /// Periodically prune expired records. This runs until the process exits.
+    pub async fn run_compaction(self, interval: Duration) {
+        loop {
+            tokio::time::sleep(interval).await;
+            match self.prune() {
+                Ok(removed) => log::debug!("Pruned {} expired records from storage", removed),
+                Err(e) => log::error!("Failed to prune storage: {:?}", e),
+            }
+        }
+    }
+
+    fn get_persisted(&self, key: &Key, request_time: RequestTime) -> Result<Option<StorageData>> {
+        let prefix = key_prefix(key);
+        let record = match request_time {
+            RequestTime::Latest => self.records.scan_prefix(&prefix).next_back(),
+            RequestTime::FirstAfter(time) => {
+                // As with the cache, a time before the oldest record can't be answered as we
+                // don't know whether an earlier update closer to it has been pruned.
+                let oldest = self.records.scan_prefix(&prefix).next().transpose()?;
+                match oldest {
+                    Some((oldest_key, _)) if time < record_time(&oldest_key)? => return Ok(None),
+                    _ => {}
+                }
+                self.records
+                    .range(record_key(&prefix, time)..=record_key(&prefix, UnixTimestamp::MAX))
+                    .next()
+            }
+        };
+
+        record
+            .transpose()?
+            .map(|(_, value)| serde_cbor::from_slice(&value).map_err(Into::into))
+            .transpose()
+    }
+}
+
+impl Storage for PersistentStorage {
+    fn insert(&self, key: Key, time: UnixTimestamp, value: StorageData) -> Result<()> {
+        let prefix = key_prefix(&key);
+        self.records
+            .insert(record_key(&prefix, time), serde_cbor::to_vec(&value)?)?;
+        self.keys.insert(prefix, &[])?;
+        self.cache.insert(key, time, value)
+    }
+
+    fn get(&self, key: Key, request_time: RequestTime) -> Result<Option<StorageData>> {
+        if let Some(value) = self.cache.get(key.clone(), request_time.clone())? {
+            return Ok(Some(value));
+        }
+        self.get_persisted(&key, request_time)
+    }
+
+    fn keys(&self) -> Vec<Key> {
+        self.keys
+            .iter()
+            .keys()
+            .filter_map(|prefix| match prefix {
+                Ok(prefix) => key_from_prefix(&prefix),
+                Err(e) => {
+                    log::error!("Failed to read storage key: {:?}", e);
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+/// Tag byte identifying the variant of a `Key` in the database.
+const BATCH_VAA_KEY: u8 = 0;
+
+fn key_prefix(key: &Key) -> Vec<u8> {
+    match key {
+        Key::BatchVaa(price_id) => [&[BATCH_VAA_KEY][..], &price_id.to_bytes()].concat(),
+    }
+}
+
+fn key_from_prefix(prefix: &[u8]) -> Option<Key> {
+    match prefix.split_first() {
+        Some((&BATCH_VAA_KEY, id)) => {
+            Some(Key::BatchVaa(PriceIdentifier::new(id.try_into().ok()?)))
+        }
+        _ => None,
+    }
+}
+
+fn record_key(prefix: &[u8], time: UnixTimestamp) -> Vec<u8> {
+    [prefix, &time.to_be_bytes()].concat()
+}
+
+fn record_time(record_key: &[u8]) -> Result<UnixTimestamp> {
+    let time = record_key
+        .len()
+        .checked_sub(8)
+        .and_then(|offset| record_key[offset..].try_into().ok())
+        .ok_or_else(|| anyhow!("Malformed storage record key"))?;
+    Ok(UnixTimestamp::from_be_bytes(time))
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::store::proof::batch_vaa::PriceInfo,
+    };
+
+    fn open() -> (tempfile::TempDir, PersistentStorage) {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = PersistentStorage::open(dir.path(), 10, Duration::from_secs(100)).unwrap();
+        (dir, storage)
+    }
+
+    fn key(id: u8) -> Key {
+        Key::BatchVaa(PriceIdentifier::new([id; 32]))
+    }
+
+    fn data(publish_time: UnixTimestamp) -> StorageData {
+        StorageData::BatchVaa(PriceInfo {
+            publish_time,
+            ..Default::default()
+        })
+    }
+
+    fn persisted(
+        storage: &PersistentStorage,
+        id: u8,
+        request_time: RequestTime,
+    ) -> Option<UnixTimestamp> {
+        match storage.get_persisted(&key(id), request_time).unwrap()? {
+            StorageData::BatchVaa(price_info) => Some(price_info.publish_time),
+        }
+    }
+
+    #[test]
+    fn test_record_key() {
+        let prefix = key_prefix(&key(7));
+        assert_eq!(prefix, [&[BATCH_VAA_KEY][..], &[7; 32]].concat());
+        assert_eq!(key_from_prefix(&prefix), Some(key(7)));
+        assert_eq!(key_from_prefix(&[9, 7]), None);
+
+        // Times are big endian so that records sort by time within their key.
+        let record = record_key(&prefix, 0x0102);
+        assert_eq!(&record[33..], &[0, 0, 0, 0, 0, 0, 1, 2]);
+        assert!(record_key(&prefix, 0xff) < record_key(&prefix, 0x100));
+        assert_eq!(record_time(&record).unwrap(), 0x0102);
+        assert!(record_time(&[1, 2]).is_err());
+    }
+
+    #[test]
+    fn test_get_persisted() {
+        let (_dir, storage) = open();
+        for time in [255, 256, 1000] {
+            storage.insert(key(1), time, data(time)).unwrap();
+        }
+        storage.insert(key(2), 300, data(300)).unwrap();
+
+        assert_eq!(persisted(&storage, 1, RequestTime::Latest), Some(1000));
+        assert_eq!(persisted(&storage, 2, RequestTime::Latest), Some(300));
+        assert_eq!(persisted(&storage, 3, RequestTime::Latest), None);
+
+        // The first record at or after the requested time, within the key's own records.
+        assert_eq!(
+            persisted(&storage, 1, RequestTime::FirstAfter(255)),
+            Some(255)
+        );
+        assert_eq!(
+            persisted(&storage, 1, RequestTime::FirstAfter(257)),
+            Some(1000)
+        );
+        assert_eq!(persisted(&storage, 1, RequestTime::FirstAfter(1001)), None);
+
+        // Times before the oldest record can't be answered.
+        assert_eq!(persisted(&storage, 1, RequestTime::FirstAfter(254)), None);
+
+        let mut keys = storage.keys();
+        keys.sort_by_key(|Key::BatchVaa(id)| id.to_bytes());
+        assert_eq!(keys, [key(1), key(2)]);
+    }
+
+    #[test]
+    fn test_prune() {
+        let (_dir, storage) = open();
+        for time in [100, 200, 300] {
+            storage.insert(key(1), time, data(time)).unwrap();
+        }
+        storage.insert(key(2), 100, data(100)).unwrap();
+
+        assert_eq!(storage.prune_before(200).unwrap(), 2);
+        assert_eq!(
+            persisted(&storage, 1, RequestTime::FirstAfter(200)),
+            Some(200)
+        );
+        assert_eq!(persisted(&storage, 1, RequestTime::FirstAfter(100)), None);
+
+        // Keys whose records have all expired are dropped.
+        assert_eq!(persisted(&storage, 2, RequestTime::Latest), None);
+        assert_eq!(storage.keys(), [key(1)]);
+
+        assert_eq!(storage.prune_before(200).unwrap(), 0);
+        assert_eq!(storage.prune().unwrap(), 2);
+        assert!(storage.keys().is_empty());
+    }
+}
@@ -0,0 +1,112 @@
+#[cfg(feature = "blake3")]
+use pythnet_sdk::hashers::blake3::Blake3;
+#[cfg(feature = "poseidon")]
+use pythnet_sdk::hashers::poseidon::Poseidon;
+use {
+    criterion::{
+        black_box,
+        criterion_group,
+        criterion_main,
+        BenchmarkId,
+        Criterion,
+        Throughput,
+    },
+    pythnet_sdk::{
+        accumulators::merkle::{
+            hash::{
+                hash_leaf,
+                hash_leaves,
+            },
+            MerkleAccumulator,
+        },
+        hashers::{
+            keccak256::Keccak256,
+            keccak256_160::Keccak160,
+            keccak512::Keccak512,
+            prime::PrimeHasher,
+            sha256::Sha256,
+            sha3_256::Sha3_256,
+            Hasher,
+        },
+    },
+};
+
+const SIZES: [usize; 3] = [100, 10_000, 100_000];
+const MESSAGE_SIZES: [usize; 3] = [32, 256, 4096];
+
+fn items(size: usize) -> Vec<[u8; 8]> {
+    (0..size).map(|i| i.to_be_bytes()).collect()
+}
+
+fn bench_hashv_with<H: Hasher>(c: &mut Criterion, name: &str) {
+    let mut group = c.benchmark_group(format!("hashv_{name}"));
+    for size in MESSAGE_SIZES {
+        let message = vec![0xab; size];
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &message, |b, message| {
+            b.iter(|| black_box(H::hashv(&[message])))
+        });
+    }
+    group.finish();
+}
+
+fn bench_build_with<H: Hasher>(c: &mut Criterion, name: &str) {
+    let mut group = c.benchmark_group(format!("merkle_build_{name}"));
+    for size in SIZES {
+        let items = items(size);
+        let items: Vec<&[u8]> = items.iter().map(|i| i.as_ref()).collect();
+        group.bench_with_input(BenchmarkId::from_parameter(size), &items, |b, items| {
+            b.iter(|| black_box(MerkleAccumulator::<H>::new(items).unwrap()))
+        });
+    }
+    group.finish();
+}
+
+// Raw throughput and the cost of building the same tree with every hasher, to compare digests
+// when choosing one for a new target chain. Feature gated hashers are only measured when their
+// feature is enabled.
+fn bench_hashers(c: &mut Criterion) {
+    fn bench<H: Hasher>(c: &mut Criterion, name: &str) {
+        bench_hashv_with::<H>(c, name);
+        bench_build_with::<H>(c, name);
+    }
+
+    bench::<Keccak256>(c, "keccak256");
+    bench::<Keccak160>(c, "keccak160");
+    bench::<Keccak512>(c, "keccak512");
+    bench::<Sha256>(c, "sha256");
+    bench::<Sha3_256>(c, "sha3_256");
+    bench::<PrimeHasher>(c, "prime");
+    #[cfg(feature = "blake3")]
+    bench::<Blake3>(c, "blake3");
+    #[cfg(feature = "poseidon")]
+    bench::<Poseidon>(c, "poseidon");
+}
+
+// Hashing the leaves of a slot worth of price messages, run with and without the `simd` feature
+// to compare the Keccak256 backends. `one_by_one` hashes each leaf on its own, `batched` lets the
+// hasher hash several at once, which with `simd` runs the AVX2 or NEON path if the CPU has it.
+fn bench_leaf_hashing(c: &mut Criterion) {
+    const MESSAGES: usize = 100_000;
+    let messages: Vec<Vec<u8>> = (0..MESSAGES)
+        .map(|i| [i.to_be_bytes().as_ref(), &[0u8; 77]].concat())
+        .collect();
+    let messages: Vec<&[u8]> = messages.iter().map(|m| m.as_slice()).collect();
+
+    let mut group = c.benchmark_group("leaf_hashing_keccak256");
+    group.throughput(Throughput::Elements(MESSAGES as u64));
+    group.bench_function(BenchmarkId::new("one_by_one", MESSAGES), |b| {
+        b.iter(|| {
+            for message in &messages {
+                black_box(hash_leaf::<Keccak256>(message));
+            }
+        })
+    });
+    group.bench_function(BenchmarkId::new("batched", MESSAGES), |b| {
+        b.iter(|| black_box(hash_leaves::<Keccak256>(&messages)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_hashers, bench_leaf_hashing);
+criterion_main!(benches);
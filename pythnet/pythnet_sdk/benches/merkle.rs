@@ -0,0 +1,75 @@
+use {
+    criterion::{
+        black_box,
+        criterion_group,
+        criterion_main,
+        BenchmarkId,
+        Criterion,
+    },
+    pythnet_sdk::{
+        accumulators::{
+            merkle::MerkleAccumulator,
+            Accumulator,
+        },
+        hashers::keccak256::Keccak256,
+    },
+};
+
+const SIZES: [usize; 3] = [100, 10_000, 100_000];
+
+fn items(size: usize) -> Vec<[u8; 8]> {
+    (0..size).map(|i| i.to_be_bytes()).collect()
+}
+
+// Building the tree happens once per slot on the validator, with the `parallel` feature enabled
+// this measures the rayon based construction.
+fn bench_build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("merkle_build");
+    for size in SIZES {
+        let items = items(size);
+        let items: Vec<&[u8]> = items.iter().map(|i| i.as_ref()).collect();
+        group.bench_with_input(BenchmarkId::from_parameter(size), &items, |b, items| {
+            b.iter(|| black_box(MerkleAccumulator::<Keccak256>::new(items).unwrap()))
+        });
+    }
+    group.finish();
+}
+
+// Proving every item in the tree is the workload of generating proofs for a full slot of price
+// messages, which used to be quadratic in the number of items due to the linear leaf lookup.
+fn bench_prove(c: &mut Criterion) {
+    let mut group = c.benchmark_group("merkle_prove_all");
+    for size in SIZES {
+        let items = items(size);
+        let items: Vec<&[u8]> = items.iter().map(|i| i.as_ref()).collect();
+        let accumulator = MerkleAccumulator::<Keccak256>::new(&items).unwrap();
+        group.bench_with_input(BenchmarkId::from_parameter(size), &items, |b, items| {
+            b.iter(|| {
+                for item in items {
+                    black_box(accumulator.prove(item).unwrap());
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+// Checking a single proof is what a target chain pays per price update, it grows with the depth
+// of the tree only.
+fn bench_check(c: &mut Criterion) {
+    let mut group = c.benchmark_group("merkle_check");
+    for size in SIZES {
+        let items = items(size);
+        let items: Vec<&[u8]> = items.iter().map(|i| i.as_ref()).collect();
+        let accumulator = MerkleAccumulator::<Keccak256>::new(&items).unwrap();
+        let item = items[size - 1];
+        let proof = accumulator.prove(item).unwrap();
+        group.bench_with_input(BenchmarkId::from_parameter(size), &proof, |b, proof| {
+            b.iter(|| assert!(accumulator.check(black_box(proof.clone()), item)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_build, bench_prove, bench_check);
+criterion_main!(benches);
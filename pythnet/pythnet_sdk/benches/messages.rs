@@ -0,0 +1,81 @@
+use {
+    borsh::{
+        BorshDeserialize,
+        BorshSerialize,
+    },
+    criterion::{
+        black_box,
+        criterion_group,
+        criterion_main,
+        BenchmarkId,
+        Criterion,
+        Throughput,
+    },
+    pythnet_sdk::messages::{
+        FeedId,
+        FundingRateMessage,
+        MarketStatus,
+        MarketStatusMessage,
+        Message,
+        TwapMessage,
+    },
+};
+
+const BATCH_SIZES: [usize; 3] = [500, 1000, 2000];
+
+// A slot worth of messages, mostly TWAPs with a funding rate and market status for every tenth
+// feed, encoded back to back as they are read out of accumulator leaves.
+fn batch(size: usize) -> Vec<u8> {
+    (0..size)
+        .map(|i| {
+            let mut feed_id = [0u8; 32];
+            feed_id[..8].copy_from_slice(&(i / 3).to_be_bytes());
+            let feed_id = FeedId(feed_id);
+            let message = match i % 10 {
+                0 => Message::FundingRateMessage(FundingRateMessage {
+                    feed_id,
+                    rate: i as i64,
+                    rate_expo: -8,
+                    interval: 3600,
+                    publish_time: 1_700_000_000,
+                }),
+                1 => Message::MarketStatusMessage(MarketStatusMessage {
+                    feed_id,
+                    status: MarketStatus::Open,
+                    publish_time: 1_700_000_000,
+                }),
+                _ => Message::TwapMessage(TwapMessage {
+                    feed_id,
+                    cumulative_price: i as i128 * 1_000_000,
+                    cumulative_conf: i as u128 * 1_000,
+                    num_down_slots: 0,
+                    publish_slot: 200_000_000,
+                    publish_time: 1_700_000_000,
+                    prev_publish_time: 1_699_999_999,
+                }),
+            };
+            message.try_to_vec().unwrap()
+        })
+        .collect::<Vec<_>>()
+        .concat()
+}
+
+fn bench_decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode_messages");
+    for size in BATCH_SIZES {
+        let bytes = batch(size);
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &bytes, |b, bytes| {
+            b.iter(|| {
+                let mut buf = bytes.as_slice();
+                while !buf.is_empty() {
+                    black_box(Message::deserialize(&mut buf).unwrap());
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_decode);
+criterion_main!(benches);
@@ -0,0 +1,185 @@
+// Write the seed corpus of the fuzz targets to `fuzz/seeds/<target>`, one file per input.
+//
+// Seeds are the payloads of the wire conformance vectors, valid and invalid, along with encodings
+// of the proof and message types that have no vectors. Run from the crate root with
+// `cargo run --example generate_fuzz_seeds` after changing any of them, and check in the result.
+
+use {
+    borsh::BorshSerialize,
+    pythnet_sdk::{
+        accumulators::{
+            merkle::{
+                AggregatedProof,
+                MerkleAccumulator,
+                SortedMerkleAccumulator,
+            },
+            Accumulator,
+        },
+        hashers::keccak256::Keccak256,
+        messages::{
+            FeedId,
+            FeedMetadataMessage,
+            FundingRateMessage,
+            MarketStatus,
+            MarketStatusMessage,
+            Message,
+            PriceFeedMessage,
+            PublisherStakeCap,
+            PublisherStakeCapsMessage,
+            TwapMessage,
+        },
+        wire::{
+            conformance::{
+                Kind,
+                INVALID,
+                VALID,
+            },
+            v1::{
+                frame_updates,
+                Proof,
+            },
+        },
+    },
+    std::path::Path,
+};
+
+fn write(target: &str, name: &str, bytes: &[u8]) {
+    let dir = Path::new("fuzz/seeds").join(target);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join(name.replace(", ", " ").replace(" ", "_")), bytes).unwrap();
+}
+
+fn main() {
+    let vectors = VALID
+        .iter()
+        .map(|vector| (vector.name, vector.kind, vector.payload))
+        .chain(
+            INVALID
+                .iter()
+                .map(|vector| (vector.name, vector.kind, vector.payload)),
+        );
+
+    let mut updates = vec![];
+    for (name, kind, payload) in vectors {
+        let payload = hex::decode(payload).unwrap();
+        let targets: &[&str] = match kind {
+            Kind::WormholeMerkleRoot => &["wormhole_merkle_root", "wormhole_payload"],
+            Kind::WormholeMerkleRoots => &["wormhole_payload"],
+            Kind::CompressedAccumulatorUpdateData => &["compressed_accumulator_update"],
+            Kind::GovernanceInstruction => &["governance_instruction"],
+        };
+        for target in targets {
+            write(target, name, &payload);
+        }
+        updates.push(payload);
+    }
+    write(
+        "update_data_stream",
+        "conformance payloads",
+        &frame_updates(&updates).unwrap(),
+    );
+    write(
+        "update_data_stream",
+        "empty",
+        &frame_updates(&[] as &[&[u8]]).unwrap(),
+    );
+
+    let items: Vec<&[u8]> = vec![b"a", b"c", b"e", b"g", b"i"];
+    let accumulator = MerkleAccumulator::<Keccak256>::new(&items).unwrap();
+    let sorted = SortedMerkleAccumulator::<Keccak256>::new(&items).unwrap();
+    let path = accumulator.prove(b"c").unwrap();
+    let multi = accumulator.prove_many(&[b"a", b"g"]).unwrap();
+    let aggregated = AggregatedProof::new(vec![multi.clone(), multi.clone()]);
+    let absence = sorted.prove_absence(b"d").unwrap();
+
+    write("merkle_path", "path", &path.try_to_vec().unwrap());
+    write("merkle_proofs", "multiproof", &multi.try_to_vec().unwrap());
+    write(
+        "merkle_proofs",
+        "aggregated proof",
+        &aggregated.try_to_vec().unwrap(),
+    );
+    write(
+        "merkle_proofs",
+        "absence proof",
+        &absence.try_to_vec().unwrap(),
+    );
+    for (name, proof) in [
+        ("merkle path", Proof::MerklePath(path)),
+        ("multiproof", Proof::MerkleMultiProof(multi)),
+        ("aggregated proof", Proof::AggregatedProof(aggregated)),
+        ("absence proof", Proof::AbsenceProof(absence)),
+        (
+            "unknown",
+            Proof::Unknown {
+                tag:   200,
+                bytes: vec![1, 2, 3],
+            },
+        ),
+    ] {
+        write("proof", name, &proof.try_to_vec().unwrap());
+    }
+
+    let feed_id = FeedId([1; 32]);
+    let messages = [
+        Message::PriceFeedMessage(PriceFeedMessage {
+            feed_id,
+            price: -2,
+            conf: 3,
+            exponent: -8,
+            publish_time: 1_700_000_060,
+            prev_publish_time: 1_700_000_000,
+            ema_price: -4,
+            ema_conf: 5,
+        }),
+        Message::TwapMessage(TwapMessage {
+            feed_id,
+            cumulative_price: -2,
+            cumulative_conf: 3,
+            num_down_slots: 4,
+            publish_slot: 5,
+            publish_time: 1_700_000_060,
+            prev_publish_time: 1_700_000_000,
+        }),
+        Message::PublisherStakeCapsMessage(PublisherStakeCapsMessage {
+            publish_time: 1_700_000_000,
+            caps:         vec![PublisherStakeCap {
+                publisher: [2; 32],
+                cap:       100,
+            }],
+        }),
+        Message::MarketStatusMessage(MarketStatusMessage {
+            feed_id,
+            status: MarketStatus::Halted,
+            publish_time: 1_700_000_000,
+        }),
+        Message::FundingRateMessage(FundingRateMessage {
+            feed_id,
+            rate: -125,
+            rate_expo: -6,
+            interval: 3600,
+            publish_time: 1_700_000_000,
+        }),
+        Message::FeedMetadataMessage(FeedMetadataMessage {
+            feed_id,
+            symbol: "Crypto.BTC/USD".to_string(),
+            asset_type: "Crypto".to_string(),
+            quote_currency: "USD".to_string(),
+            publish_time: 1_700_000_000,
+        }),
+    ];
+    for (i, message) in messages.iter().enumerate() {
+        write(
+            "message",
+            &format!("message {i}"),
+            &message.try_to_vec().unwrap(),
+        );
+    }
+    for (i, chunk) in messages[5].to_chunks(32).unwrap().iter().enumerate() {
+        write(
+            "message",
+            &format!("chunk {i}"),
+            &chunk.try_to_vec().unwrap(),
+        );
+    }
+}
@@ -0,0 +1,24 @@
+#![no_main]
+
+use {
+    borsh::{
+        BorshDeserialize,
+        BorshSerialize,
+    },
+    libfuzzer_sys::fuzz_target,
+    pythnet_sdk::wire::v1::{
+        CompressedAccumulatorUpdateData,
+        MAX_DECOMPRESSED_UPDATE_SIZE,
+    },
+};
+
+// Parsing must round-trip, and decompressing must stay within its size limit whatever the input.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(update) = CompressedAccumulatorUpdateData::try_from_slice(data) {
+        assert_eq!(update.try_to_vec().unwrap(), data);
+    }
+
+    if let Ok(update) = CompressedAccumulatorUpdateData::decompress_update(data) {
+        assert!(update.len() <= data.len().max(MAX_DECOMPRESSED_UPDATE_SIZE));
+    }
+});
@@ -0,0 +1,22 @@
+#![no_main]
+
+use {
+    borsh::{
+        BorshDeserialize,
+        BorshSerialize,
+    },
+    libfuzzer_sys::fuzz_target,
+    pythnet_sdk::wire::{
+        governance::GovernanceInstruction,
+        SerializedSize,
+    },
+};
+
+// Every contract executes the instruction it parses, so an instruction must have exactly one
+// encoding.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(instruction) = GovernanceInstruction::try_from_slice(data) {
+        assert_eq!(instruction.try_to_vec().unwrap(), data);
+        assert_eq!(instruction.serialized_size(), data.len());
+    }
+});
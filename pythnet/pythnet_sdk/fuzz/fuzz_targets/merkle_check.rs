@@ -0,0 +1,35 @@
+#![no_main]
+
+use {
+    libfuzzer_sys::fuzz_target,
+    pythnet_sdk::{
+        accumulators::{
+            merkle::{
+                MerkleAccumulator,
+                MerklePath,
+            },
+            Accumulator,
+        },
+        hashers::keccak256::Keccak256,
+    },
+};
+
+// Every item of a tree must be provable, and an arbitrary path must never prove an item that is
+// not in the tree.
+fuzz_target!(|input: (Vec<Vec<u8>>, Vec<[u8; 32]>, Vec<u8>)| {
+    let (items, path, item) = input;
+    let items: Vec<&[u8]> = items.iter().take(256).map(|i| i.as_slice()).collect();
+    let accumulator = match MerkleAccumulator::<Keccak256>::new(&items) {
+        Ok(accumulator) => accumulator,
+        Err(_) => return,
+    };
+
+    for item in &items {
+        let proof = accumulator.prove(item).unwrap();
+        assert!(accumulator.check(proof, item));
+    }
+
+    if accumulator.check(MerklePath::new(path), &item) {
+        assert!(items.contains(&item.as_slice()));
+    }
+});
@@ -0,0 +1,25 @@
+#![no_main]
+
+use {
+    borsh::BorshDeserialize,
+    libfuzzer_sys::fuzz_target,
+    pythnet_sdk::{
+        accumulators::merkle::{
+            MerklePath,
+            MAX_PATH_LENGTH,
+        },
+        hashers::keccak256::Keccak256,
+    },
+};
+
+// Paths are read from untrusted update payloads, so neither encoding may panic or accept more than
+// MAX_PATH_LENGTH hashes whatever the input.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(path) = MerklePath::<Keccak256>::try_from_slice(data) {
+        assert!(path.hashes().len() <= MAX_PATH_LENGTH);
+    }
+
+    if let Ok(path) = serde_json::from_slice::<MerklePath<Keccak256>>(data) {
+        assert!(path.hashes().len() <= MAX_PATH_LENGTH);
+    }
+});
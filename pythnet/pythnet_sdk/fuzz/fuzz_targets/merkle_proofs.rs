@@ -0,0 +1,37 @@
+#![no_main]
+
+use {
+    borsh::{
+        BorshDeserialize,
+        BorshSerialize,
+    },
+    libfuzzer_sys::fuzz_target,
+    pythnet_sdk::{
+        accumulators::merkle::{
+            AbsenceProof,
+            AggregatedProof,
+            MerkleMultiProof,
+        },
+        hashers::keccak256::Keccak256,
+        wire::SerializedSize,
+    },
+};
+
+// The counts in multiproofs are bounded before anything is allocated, so no input may exhaust
+// memory, and a proof that parses must serialize back to the same bytes, of the expected size.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(proof) = MerkleMultiProof::<Keccak256>::try_from_slice(data) {
+        assert_eq!(proof.try_to_vec().unwrap(), data);
+        assert_eq!(proof.serialized_size(), data.len());
+    }
+
+    if let Ok(proof) = AggregatedProof::<Keccak256>::try_from_slice(data) {
+        assert_eq!(proof.try_to_vec().unwrap(), data);
+        assert_eq!(proof.serialized_size(), data.len());
+    }
+
+    if let Ok(proof) = AbsenceProof::<Keccak256>::try_from_slice(data) {
+        assert_eq!(proof.try_to_vec().unwrap(), data);
+        assert_eq!(proof.serialized_size(), data.len());
+    }
+});
@@ -0,0 +1,25 @@
+#![no_main]
+
+use {
+    borsh::{
+        BorshDeserialize,
+        BorshSerialize,
+    },
+    libfuzzer_sys::fuzz_target,
+    pythnet_sdk::messages::{
+        Message,
+        MessageChunk,
+    },
+};
+
+// Leaves are committed to by their bytes, so a message or chunk that parses must serialize back
+// to exactly the leaf it was read from.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(message) = Message::try_from_slice(data) {
+        assert_eq!(message.try_to_vec().unwrap(), data);
+    }
+
+    if let Ok(chunk) = MessageChunk::try_from_slice(data) {
+        assert_eq!(chunk.try_to_vec().unwrap(), data);
+    }
+});
@@ -0,0 +1,25 @@
+#![no_main]
+
+use {
+    borsh::{
+        BorshDeserialize,
+        BorshSerialize,
+    },
+    libfuzzer_sys::fuzz_target,
+    pythnet_sdk::{
+        hashers::keccak256::Keccak256,
+        wire::{
+            v1::Proof,
+            SerializedSize,
+        },
+    },
+};
+
+// Proofs of known and unknown types alike must serialize back to the bytes they were parsed
+// from, so that relayers forward them unchanged.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(proof) = Proof::<Keccak256>::try_from_slice(data) {
+        assert_eq!(proof.try_to_vec().unwrap(), data);
+        assert_eq!(proof.serialized_size(), data.len());
+    }
+});
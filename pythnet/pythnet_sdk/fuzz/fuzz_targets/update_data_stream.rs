@@ -0,0 +1,22 @@
+#![no_main]
+
+use {
+    libfuzzer_sys::fuzz_target,
+    pythnet_sdk::wire::v1::{
+        frame_updates,
+        UpdateDataStream,
+    },
+};
+
+// A blob whose frames all parse must be rebuilt exactly by framing the parsed updates again, and
+// a malformed frame must end the stream rather than loop or panic.
+fuzz_target!(|data: &[u8]| {
+    let stream = match UpdateDataStream::new(data) {
+        Ok(stream) => stream,
+        Err(_) => return,
+    };
+
+    if let Ok(updates) = stream.collect::<std::io::Result<Vec<&[u8]>>>() {
+        assert_eq!(frame_updates(&updates).unwrap(), data);
+    }
+});
@@ -0,0 +1,21 @@
+#![no_main]
+
+use {
+    borsh::{
+        BorshDeserialize,
+        BorshSerialize,
+    },
+    libfuzzer_sys::fuzz_target,
+    pythnet_sdk::{
+        hashers::keccak256::Keccak256,
+        wire::v1::WormholeMerkleRoot,
+    },
+};
+
+// Anything that parses must serialize back to exactly the same bytes, otherwise two different
+// payloads could be accepted for the same root.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(root) = WormholeMerkleRoot::<Keccak256>::try_from_slice(data) {
+        assert_eq!(root.try_to_vec().unwrap(), data);
+    }
+});
@@ -0,0 +1,33 @@
+#![no_main]
+
+use {
+    borsh::{
+        BorshDeserialize,
+        BorshSerialize,
+    },
+    libfuzzer_sys::fuzz_target,
+    pythnet_sdk::{
+        hashers::keccak256::Keccak256,
+        wire::{
+            v1::{
+                WormholeMerkleRoots,
+                WormholePayload,
+            },
+            SerializedSize,
+        },
+    },
+};
+
+// Both payloads carrying roots must serialize back to exactly the bytes they were parsed from,
+// whether parsed as their own type or through `WormholePayload`.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(roots) = WormholeMerkleRoots::<Keccak256>::try_from_slice(data) {
+        assert_eq!(roots.try_to_vec().unwrap(), data);
+        assert_eq!(roots.serialized_size(), data.len());
+    }
+
+    if let Ok(payload) = WormholePayload::<Keccak256>::try_from_slice(data) {
+        assert_eq!(payload.try_to_vec().unwrap(), data);
+        assert_eq!(payload.serialized_size(), data.len());
+    }
+});
@@ -4,8 +4,59 @@
 //! several different accumulators. This library can be used for interacting with PythNet state
 //! proofs for account content.
 
+pub mod fixed;
 pub mod merkle;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+pub mod mmr;
+#[cfg(feature = "std")]
 pub mod mul;
+pub mod pruned;
+pub mod solana;
+
+/// Errors returned by accumulators when building them or proving an item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccumulatorError {
+    /// An accumulator cannot be built from an empty set of items.
+    EmptyInput,
+    /// The item is not a member of the accumulator.
+    ItemNotFound,
+    /// The set has more items than the accumulator can hold.
+    TooManyItems,
+    /// The item at index `duplicate` is the same as the earlier item at index `first`.
+    DuplicateItem { first: usize, duplicate: usize },
+    /// An iterator yielded a different number of items than its reported length.
+    LengthMismatch,
+    /// The accumulator only holds its root, such as one deserialized without its nodes, so it
+    /// cannot be modified.
+    MissingNodes,
+}
+
+impl core::fmt::Display for AccumulatorError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            AccumulatorError::EmptyInput => write!(f, "Cannot build an accumulator from no items"),
+            AccumulatorError::ItemNotFound => write!(f, "Item is not a member of the accumulator"),
+            AccumulatorError::TooManyItems => write!(f, "Too many items for the accumulator"),
+            AccumulatorError::DuplicateItem { first, duplicate } => {
+                write!(f, "Item {duplicate} is a duplicate of item {first}")
+            }
+            AccumulatorError::LengthMismatch => {
+                write!(
+                    f,
+                    "Iterator yielded a different number of items than its length"
+                )
+            }
+            AccumulatorError::MissingNodes => {
+                write!(f, "Accumulator holds no nodes to modify")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AccumulatorError {
+}
 
 /// The Accumulator trait defines the interface for an accumulator.
 ///
@@ -21,11 +72,11 @@ where
     type Proof;
 
     /// Prove an item is a member of the accumulator.
-    fn prove(&'a self, item: &[u8]) -> Option<Self::Proof>;
+    fn prove(&'a self, item: &[u8]) -> Result<Self::Proof, AccumulatorError>;
 
     /// Verify an item is a member of the accumulator.
     fn check(&'a self, proof: Self::Proof, item: &[u8]) -> bool;
 
     /// Create an accumulator from a set of items.
-    fn from_set(items: impl Iterator<Item = &'a [u8]>) -> Option<Self>;
+    fn from_set(items: impl Iterator<Item = &'a [u8]>) -> Result<Self, AccumulatorError>;
 }
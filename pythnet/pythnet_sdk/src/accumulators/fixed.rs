@@ -0,0 +1,181 @@
+//! A fixed depth MerkleTree based Accumulator that does not allocate.
+
+use crate::{
+    accumulators::{
+        merkle::hash::{
+            hash_leaf,
+            hash_node,
+            hash_null,
+        },
+        AccumulatorError,
+    },
+    hashers::{
+        keccak256::Keccak256,
+        Hasher,
+    },
+};
+
+/// A FixedMerkleAccumulator maintains the root of a Merkle Tree of exactly `DEPTH` levels.
+///
+/// Only the left-most filled subtree of every level is kept, in an array of `DEPTH` hashes, so
+/// appending an item costs `O(DEPTH)` hashes and no heap allocation. The tree holds at most
+/// `2^DEPTH` items and unused leaves are NULL, so once it holds more than `2^(DEPTH - 1)` items
+/// its root is the same as that of a MerkleAccumulator over the same items and proofs generated
+/// by the MerkleAccumulator can be checked here.
+///
+/// As the items themselves are not stored this type cannot generate proofs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixedMerkleAccumulator<H: Hasher = Keccak256, const DEPTH: usize = 16> {
+    pub root: H::Hash,
+    len:      u64,
+    branch:   [H::Hash; DEPTH],
+}
+
+impl<H: Hasher, const DEPTH: usize> Default for FixedMerkleAccumulator<H, DEPTH> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<H: Hasher, const DEPTH: usize> FixedMerkleAccumulator<H, DEPTH> {
+    // Evaluated when the type is instantiated, so an unsupported depth fails to compile.
+    const DEPTH_IN_RANGE: () = assert!(DEPTH < u64::BITS as usize, "DEPTH must be below 64");
+
+    /// Number of items the tree can hold.
+    pub const CAPACITY: u64 = 1 << DEPTH;
+
+    /// Create an empty tree.
+    pub fn new() -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::DEPTH_IN_RANGE;
+        let mut accumulator = Self {
+            root:   Default::default(),
+            len:    0,
+            branch: [Default::default(); DEPTH],
+        };
+        accumulator.root = accumulator.compute_root();
+        accumulator
+    }
+
+    /// Create a tree from a set of items, which may hold at most `CAPACITY` items.
+    pub fn from_set<'a>(items: impl Iterator<Item = &'a [u8]>) -> Result<Self, AccumulatorError> {
+        let mut accumulator = Self::new();
+        for item in items {
+            accumulator
+                .append(item)
+                .ok_or(AccumulatorError::TooManyItems)?;
+        }
+        Ok(accumulator)
+    }
+
+    /// Number of items in the tree.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Append an item to the tree, returning the index of its leaf or `None` if the tree is full.
+    pub fn append(&mut self, item: &[u8]) -> Option<u64> {
+        let index = self.len;
+        if index >= Self::CAPACITY {
+            return None;
+        }
+
+        // Carry the new leaf up for as long as it completes a subtree, storing the first subtree
+        // left incomplete as the branch of its level. The last leaf completes every subtree and
+        // so is carried all the way up to the root.
+        let mut node = hash_leaf::<H>(item);
+        let mut full = true;
+        for (height, branch) in self.branch.iter_mut().enumerate() {
+            if index >> height & 1 == 0 {
+                *branch = node;
+                full = false;
+                break;
+            }
+            node = hash_node::<H>(branch, &node);
+        }
+
+        self.len += 1;
+        self.root = if full { node } else { self.compute_root() };
+        Some(index)
+    }
+
+    /// Verify an item is a member of the tree. The proof always has one hash per level.
+    pub fn check(&self, proof: &[H::Hash; DEPTH], item: &[u8]) -> bool {
+        let mut current = hash_leaf::<H>(item);
+        for hash in proof {
+            current = hash_node::<H>(&current, hash);
+        }
+        current == self.root
+    }
+
+    /// Combine the branches with the empty subtrees to the right of the last item. Only valid
+    /// while the tree is not full.
+    fn compute_root(&self) -> H::Hash {
+        let mut node = hash_null::<H>();
+        let mut empty = node;
+        for (height, branch) in self.branch.iter().enumerate() {
+            node = if self.len >> height & 1 == 1 {
+                hash_node::<H>(branch, &node)
+            } else {
+                hash_node::<H>(&node, &empty)
+            };
+            empty = hash_node::<H>(&empty, &empty);
+        }
+        node
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::accumulators::{
+            merkle::MerkleAccumulator,
+            Accumulator,
+        },
+    };
+
+    #[test]
+    fn test_fixed_merkle() {
+        let items: Vec<[u8; 8]> = (0..8usize).map(|i| i.to_be_bytes()).collect();
+        let mut accumulator = FixedMerkleAccumulator::<Keccak256, 3>::new();
+        assert!(accumulator.is_empty());
+
+        // Every size past half the capacity matches the equivalent MerkleAccumulator, including
+        // a full tree.
+        for (i, item) in items.iter().enumerate() {
+            assert_eq!(accumulator.append(item), Some(i as u64));
+            let leaves: Vec<&[u8]> = items[..=i].iter().map(|i| i.as_ref()).collect();
+            let merkle = MerkleAccumulator::<Keccak256>::new(&leaves).unwrap();
+            if i >= 4 {
+                assert_eq!(accumulator.root, merkle.root);
+                for item in &leaves {
+                    let proof = merkle.prove(item).unwrap();
+                    let proof: [_; 3] = proof.hashes().try_into().unwrap();
+                    assert!(accumulator.check(&proof, item));
+                }
+            } else {
+                assert_ne!(accumulator.root, merkle.root);
+            }
+        }
+
+        assert_eq!(
+            accumulator.len(),
+            FixedMerkleAccumulator::<Keccak256, 3>::CAPACITY
+        );
+        assert_eq!(accumulator.append(&[0]), None);
+        assert_eq!(
+            FixedMerkleAccumulator::<Keccak256, 3>::from_set(items.iter().map(|i| i.as_ref())),
+            Ok(accumulator.clone())
+        );
+        assert!(!accumulator.check(&[Default::default(); 3], &items[0]));
+        assert_eq!(
+            FixedMerkleAccumulator::<Keccak256, 2>::from_set(items.iter().map(|i| i.as_ref())),
+            Err(AccumulatorError::TooManyItems)
+        );
+    }
+}
@@ -1,56 +1,123 @@
 //! A MerkleTree based Accumulator.
 
+pub mod hash;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use {
-    crate::{
-        accumulators::Accumulator,
-        hashers::{
-            keccak256::Keccak256,
-            Hasher,
-        },
+    self::hash::{
+        hash_gap,
+        hash_leaf_in_domain,
+        hash_node,
     },
-    borsh::{
-        BorshDeserialize,
-        BorshSerialize,
+    crate::hashers::{
+        keccak256::Keccak256,
+        Hasher,
     },
+    alloc::vec::Vec,
+    core::marker::PhantomData,
     serde::{
+        de::{
+            DeserializeSeed,
+            MapAccess,
+            SeqAccess,
+            Visitor,
+        },
         Deserialize,
+        Deserializer,
         Serialize,
     },
 };
+#[cfg(feature = "std")]
+use {
+    self::hash::{
+        hash_leaf,
+        hash_leaves,
+        hash_null,
+    },
+    crate::accumulators::{
+        Accumulator,
+        AccumulatorError,
+    },
+    std::{
+        borrow::Cow,
+        collections::HashMap,
+    },
+};
+// Proofs are encoded with the `wire` feature alone, so they can be parsed without `std`.
+#[cfg(feature = "wire")]
+use {
+    crate::wire::SerializedSize,
+    borsh::{
+        maybestd::io::{
+            self,
+            Error,
+            ErrorKind::InvalidData,
+            Write,
+        },
+        BorshDeserialize,
+        BorshSerialize,
+    },
+};
+
+/// The hash of a leaf, computed once so it can be checked against many proofs.
+///
+/// Verifiers that check the same item against several proofs, or that receive leaf digests
+/// computed elsewhere in their pipeline, can hash the item up front and use `verify_hashed` and
+/// `check_hashed` instead of re-hashing the item for every check. Being a distinct type keeps leaf
+/// hashes from being mixed up with the interior node hashes of a proof.
+#[derive(Clone, Default, Debug, PartialEq, Eq, Serialize)]
+pub struct LeafHash<H: Hasher>(H::Hash);
+
+impl<H: Hasher> LeafHash<H> {
+    /// Wrap a leaf hash computed elsewhere, which must be `hash::hash_leaf` of the item or
+    /// `hash::hash_leaf_in_domain` for trees with a domain.
+    pub fn new(hash: H::Hash) -> Self {
+        Self(hash)
+    }
 
-// We need to discern between leaf and intermediate nodes to prevent trivial second pre-image
-// attacks. If we did not do this it would be possible for an attacker to intentionally create
-// non-leaf nodes that have the same hash as a leaf node, and then use that to prove the existence
-// of a leaf node that does not exist.
-//
-// See:
-//
-// - https://flawed.net.nz/2018/02/21/attacking-merkle-trees-with-a-second-preimage-attack
-// - https://en.wikipedia.org/wiki/Merkle_tree#Second_preimage_attack
-//
-// NOTE: We use a NULL prefix for leaf nodes to distinguish them from the empty message (""), while
-// there is no path that allows empty messages this is a safety measure to prevent future
-// vulnerabilities being introduced.
-const LEAF_PREFIX: &[u8] = &[0];
-const NODE_PREFIX: &[u8] = &[1];
-const NULL_PREFIX: &[u8] = &[2];
+    pub fn from_item(item: &[u8]) -> Self {
+        Self(hash_leaf_in_domain::<H>(&[], item))
+    }
 
-fn hash_leaf<H: Hasher>(leaf: &[u8]) -> H::Hash {
-    H::hashv(&[LEAF_PREFIX, leaf])
+    pub fn from_item_in_domain(domain: &[u8], item: &[u8]) -> Self {
+        Self(hash_leaf_in_domain::<H>(domain, item))
+    }
+
+    pub fn hash(&self) -> H::Hash {
+        self.0
+    }
 }
 
-fn hash_node<H: Hasher>(l: &H::Hash, r: &H::Hash) -> H::Hash {
-    H::hashv(&[
-        NODE_PREFIX,
-        (if l <= r { l } else { r }).as_ref(),
-        (if l <= r { r } else { l }).as_ref(),
-    ])
+/// Upper bound on the number of hashes in a MerklePath accepted when deserializing. A path has one
+/// hash per level of the tree, so this comfortably covers any tree that can fit in memory while
+/// stopping malicious inputs from forcing huge allocations.
+pub const MAX_PATH_LENGTH: usize = 64;
+
+/// A path had `len` hashes, more than `MAX_PATH_LENGTH`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathTooLong {
+    pub len: usize,
+}
+
+impl core::fmt::Display for PathTooLong {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(
+            f,
+            "Path length {} exceeds maximum of {MAX_PATH_LENGTH}",
+            self.len
+        )
+    }
 }
 
-fn hash_null<H: Hasher>() -> H::Hash {
-    H::hashv(&[NULL_PREFIX])
+#[cfg(feature = "std")]
+impl std::error::Error for PathTooLong {
 }
 
+/// Upper bound on the number of items in a MerkleMultiProof accepted when deserializing. This is
+/// far more messages than fit in any update, larger batches can be split across several proofs.
+pub const MAX_MULTIPROOF_ITEMS: usize = 1 << 16;
+
 #[derive(Clone, Default, Debug, PartialEq, Eq, Serialize)]
 pub struct MerklePath<H: Hasher>(Vec<H::Hash>);
 
@@ -58,123 +125,1642 @@ impl<H: Hasher> MerklePath<H> {
     pub fn new(path: Vec<H::Hash>) -> Self {
         Self(path)
     }
+
+    /// Verify an item is a member of the tree with the given root. Paths longer than
+    /// `MAX_PATH_LENGTH` are rejected without being hashed.
+    pub fn verify(&self, root: &H::Hash, item: &[u8]) -> bool {
+        self.verify_with_max_depth(root, item, MAX_PATH_LENGTH)
+    }
+
+    /// Verify an item is a member of a tree with the given root and at most `max_depth` levels.
+    /// Paths longer than `max_depth` are rejected without being hashed.
+    pub fn verify_with_max_depth(&self, root: &H::Hash, item: &[u8], max_depth: usize) -> bool {
+        self.verify_in_domain_with_max_depth(root, &[], item, max_depth)
+    }
+
+    /// Verify an item is a member of a tree built with `MerkleAccumulator::new_with_domain`.
+    pub fn verify_in_domain(&self, root: &H::Hash, domain: &[u8], item: &[u8]) -> bool {
+        self.verify_in_domain_with_max_depth(root, domain, item, MAX_PATH_LENGTH)
+    }
+
+    fn verify_in_domain_with_max_depth(
+        &self,
+        root: &H::Hash,
+        domain: &[u8],
+        item: &[u8],
+        max_depth: usize,
+    ) -> bool {
+        self.0.len() <= max_depth
+            && self.verify_hashed_with_max_depth(
+                root,
+                &LeafHash::from_item_in_domain(domain, item),
+                max_depth,
+            )
+    }
+
+    /// Verify a leaf hashed ahead of time is a member of the tree with the given root, see
+    /// `LeafHash`.
+    pub fn verify_hashed(&self, root: &H::Hash, leaf: &LeafHash<H>) -> bool {
+        self.verify_hashed_with_max_depth(root, leaf, MAX_PATH_LENGTH)
+    }
+
+    fn verify_hashed_with_max_depth(
+        &self,
+        root: &H::Hash,
+        leaf: &LeafHash<H>,
+        max_depth: usize,
+    ) -> bool {
+        if self.0.len() > max_depth {
+            return false;
+        }
+
+        let mut current = leaf.0;
+        for hash in &self.0 {
+            current = hash_node::<H>(&current, hash);
+        }
+        &current == root
+    }
+
+    /// Sibling hashes of the path, ordered from the leaf up to the root.
+    pub fn hashes(&self) -> &[H::Hash] {
+        &self.0
+    }
+}
+
+// Paths built from untrusted hashes, such as those in a wire payload, share the deserialization
+// limit. Like `<[T; N]>::try_from(Vec<T>)` a rejected Vec is handed back unchanged.
+impl<H: Hasher> TryFrom<Vec<H::Hash>> for MerklePath<H> {
+    type Error = Vec<H::Hash>;
+
+    fn try_from(path: Vec<H::Hash>) -> Result<Self, Self::Error> {
+        if path.len() > MAX_PATH_LENGTH {
+            return Err(path);
+        }
+        Ok(Self(path))
+    }
+}
+
+impl<H: Hasher> From<MerklePath<H>> for Vec<H::Hash> {
+    fn from(path: MerklePath<H>) -> Self {
+        path.0
+    }
+}
+
+#[cfg(feature = "wire")]
+impl<H: Hasher> BorshSerialize for MerklePath<H>
+where
+    H::Hash: BorshSerialize,
+{
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        BorshSerialize::serialize(&self.0, writer)
+    }
+}
+
+#[cfg(feature = "wire")]
+impl<H: Hasher> SerializedSize for MerklePath<H> {
+    fn serialized_size(&self) -> usize {
+        4 + self.0.len() * H::hash_size()
+    }
+}
+
+#[cfg(feature = "wire")]
+impl<H: Hasher> BorshDeserialize for MerklePath<H>
+where
+    H::Hash: BorshDeserialize,
+{
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        let len = <u32 as BorshDeserialize>::deserialize(buf)? as usize;
+        if len > MAX_PATH_LENGTH {
+            return Err(Error::new(
+                InvalidData,
+                format!("Path length {len} exceeds maximum of {MAX_PATH_LENGTH}"),
+            ));
+        }
+
+        let mut path = Vec::with_capacity(len);
+        for _ in 0..len {
+            path.push(<H::Hash as BorshDeserialize>::deserialize(buf)?);
+        }
+        Ok(Self(path))
+    }
+}
+
+// Deserialize the path element by element so that the length bound is enforced before anything
+// is allocated for an oversized input.
+impl<'de, H: Hasher> Deserialize<'de> for MerklePath<H> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct PathVisitor<H>(PhantomData<H>);
+
+        impl<'de, H: Hasher> Visitor<'de> for PathVisitor<H> {
+            type Value = MerklePath<H>;
+
+            fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+                write!(formatter, "at most {MAX_PATH_LENGTH} hashes")
+            }
+
+            fn visit_newtype_struct<D: Deserializer<'de>>(
+                self,
+                deserializer: D,
+            ) -> Result<Self::Value, D::Error> {
+                deserializer.deserialize_seq(self)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut path = Vec::new();
+                while let Some(hash) = seq.next_element()? {
+                    if path.len() == MAX_PATH_LENGTH {
+                        return Err(serde::de::Error::invalid_length(path.len() + 1, &self));
+                    }
+                    path.push(hash);
+                }
+                Ok(MerklePath(path))
+            }
+        }
+
+        deserializer.deserialize_newtype_struct("MerklePath", PathVisitor(PhantomData))
+    }
+}
+
+/// A MerkleRoot verifies proofs against the root of a MerkleAccumulator.
+///
+/// Verifying a proof only requires the root of the tree, so unlike MerkleAccumulator this type
+/// does not store any nodes. This is what on-chain verifiers should use, MerkleAccumulator is only
+/// needed to generate proofs.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleRoot<H: Hasher = Keccak256>(H::Hash);
+
+impl<H: Hasher> MerkleRoot<H> {
+    pub fn new(root: H::Hash) -> Self {
+        Self(root)
+    }
+
+    pub fn hash(&self) -> H::Hash {
+        self.0
+    }
+
+    /// Verify an item is a member of the tree.
+    pub fn check(&self, proof: MerklePath<H>, item: &[u8]) -> bool {
+        proof.verify(&self.0, item)
+    }
+
+    /// Verify a leaf hashed ahead of time is a member of the tree, see `LeafHash`.
+    pub fn check_hashed(&self, proof: MerklePath<H>, leaf: &LeafHash<H>) -> bool {
+        proof.verify_hashed(&self.0, leaf)
+    }
+
+    /// Verify an item is a member of the tree, rejecting proofs longer than `max_depth`. Callers
+    /// that know the size of the tree should use its depth so that compute spent on a proof is
+    /// bounded up front.
+    pub fn check_with_max_depth(
+        &self,
+        proof: MerklePath<H>,
+        item: &[u8],
+        max_depth: usize,
+    ) -> bool {
+        proof.verify_with_max_depth(&self.0, item, max_depth)
+    }
+
+    /// Verify that all `items` are members of the tree. Items must be given in the same order
+    /// they were passed to `prove_many`.
+    pub fn check_many(&self, proof: MerkleMultiProof<H>, items: &[&[u8]]) -> bool {
+        self.check_many_in_domain(proof, &[], items)
+    }
+
+    /// Verify an item is a member of a tree built with `MerkleAccumulator::new_with_domain`.
+    pub fn check_in_domain(&self, proof: MerklePath<H>, domain: &[u8], item: &[u8]) -> bool {
+        proof.verify_in_domain(&self.0, domain, item)
+    }
+
+    /// Verify that all `items` are members of a tree built with
+    /// `MerkleAccumulator::new_with_domain`.
+    pub fn check_many_in_domain(
+        &self,
+        proof: MerkleMultiProof<H>,
+        domain: &[u8],
+        items: &[&[u8]],
+    ) -> bool {
+        let leaves = match multiproof_leaves::<H>(proof.depth, &proof.indices, domain, items) {
+            Some(leaves) => leaves,
+            None => return false,
+        };
+
+        let mut hashes = proof.hashes.into_iter();
+        let root = fold_multiproof::<H>(proof.depth, leaves, |_| hashes.next());
+        hashes.next().is_none() && root == Some(self.0)
+    }
+
+    /// Verify an item is not a member of a tree built by SortedMerkleAccumulator.
+    pub fn check_absence(&self, proof: AbsenceProof<H>, item: &[u8]) -> bool {
+        let low = proof.low.as_deref();
+        let high = proof.high.as_deref();
+        if matches!(low, Some(low) if low >= item) || matches!(high, Some(high) if high <= item) {
+            return false;
+        }
+
+        let mut current = hash_gap::<H>(low, high);
+        for hash in proof.path.0 {
+            current = hash_node::<H>(&current, &hash);
+        }
+        current == self.0
+    }
+}
+
+/// A MerkleMultiProof proves membership of several items against the same root at once.
+///
+/// Rather than carrying one independent path per item, only the sibling hashes that cannot be
+/// computed from the proven items themselves are stored. Items that share a branch of the tree
+/// therefore share the interior hashes of that branch. Hashes are ordered bottom-up by level and
+/// left to right within a level, which is the order a verifier consumes them in.
+///
+/// Layout when Borsh encoded, with every integer big endian like the `wire` types:
+///
+/// ```text
+/// 1 byte:   depth of the tree, at most `MAX_PATH_LENGTH`
+/// 4 bytes:  number of indices, at most `MAX_MULTIPROOF_ITEMS`
+/// 4*N:      leaf indices
+/// 4 bytes:  number of hashes, at most the number of indices times the depth
+/// H*M:      hashes
+/// ```
+#[derive(Clone, Default, Debug, PartialEq, Eq, Serialize)]
+pub struct MerkleMultiProof<H: Hasher> {
+    depth:   u8,
+    indices: Vec<u32>,
+    hashes:  Vec<H::Hash>,
+}
+
+impl<H: Hasher> MerkleMultiProof<H> {
+    pub fn new(depth: u8, indices: Vec<u32>, hashes: Vec<H::Hash>) -> Self {
+        Self {
+            depth,
+            indices,
+            hashes,
+        }
+    }
+
+    /// Leaf indices of the proven items, in the order the items were given to `prove_many`.
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    /// Most hashes a valid proof of `items` items at `depth` can hold: every level needs at most
+    /// one sibling per item.
+    fn max_hashes(depth: u8, items: usize) -> usize {
+        items.saturating_mul(depth.into())
+    }
+}
+
+// A sequence deserialized element by element that fails as soon as it exceeds `max` elements,
+// so an oversized input is rejected before it is allocated.
+struct BoundedSeq<T> {
+    max:      usize,
+    elements: PhantomData<T>,
+}
+
+impl<T> BoundedSeq<T> {
+    fn new(max: usize) -> Self {
+        Self {
+            max,
+            elements: PhantomData,
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> DeserializeSeed<'de> for BoundedSeq<T> {
+    type Value = Vec<T>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for BoundedSeq<T> {
+    type Value = Vec<T>;
+
+    fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(formatter, "at most {} elements", self.max)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut elements = Vec::new();
+        while let Some(element) = seq.next_element()? {
+            if elements.len() == self.max {
+                return Err(serde::de::Error::invalid_length(elements.len() + 1, &self));
+            }
+            elements.push(element);
+        }
+        Ok(elements)
+    }
+}
+
+// Untrusted proofs are bounded the same way as `MerklePath`, no valid proof is deeper than
+// `MAX_PATH_LENGTH`, proves more than `MAX_MULTIPROOF_ITEMS` items or holds more hashes than
+// `max_hashes`. Each bound is enforced while its field is read. The fields are expected in
+// declaration order, which is how `Serialize` writes them.
+impl<'de, H: Hasher> Deserialize<'de> for MerkleMultiProof<H> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        const FIELDS: &[&str] = &["depth", "indices", "hashes"];
+
+        #[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+        #[serde(field_identifier, rename_all = "lowercase")]
+        enum Field {
+            Depth,
+            Indices,
+            Hashes,
+        }
+
+        struct MultiProofVisitor<H>(PhantomData<H>);
+
+        impl<H: Hasher> MultiProofVisitor<H> {
+            fn check_depth<E: serde::de::Error>(depth: u8) -> Result<u8, E> {
+                if usize::from(depth) > MAX_PATH_LENGTH {
+                    return Err(E::custom("multiproof depth exceeds MAX_PATH_LENGTH"));
+                }
+                Ok(depth)
+            }
+
+            fn next_field<'de, A: MapAccess<'de>>(
+                map: &mut A,
+                field: Field,
+            ) -> Result<(), A::Error> {
+                match map.next_key::<Field>()? {
+                    Some(key) if key == field => Ok(()),
+                    Some(_) => Err(serde::de::Error::custom(
+                        "multiproof fields must be in declaration order",
+                    )),
+                    None => Err(serde::de::Error::missing_field(FIELDS[field as usize])),
+                }
+            }
+        }
+
+        impl<'de, H: Hasher> Visitor<'de> for MultiProofVisitor<H> {
+            type Value = MerkleMultiProof<H>;
+
+            fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+                write!(formatter, "struct MerkleMultiProof")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let missing = |i| serde::de::Error::invalid_length(i, &self);
+                let depth = Self::check_depth(seq.next_element()?.ok_or_else(|| missing(0))?)?;
+                let indices = seq
+                    .next_element_seed(BoundedSeq::new(MAX_MULTIPROOF_ITEMS))?
+                    .ok_or_else(|| missing(1))?;
+                let hashes = seq
+                    .next_element_seed(BoundedSeq::new(MerkleMultiProof::<H>::max_hashes(
+                        depth,
+                        indices.len(),
+                    )))?
+                    .ok_or_else(|| missing(2))?;
+                Ok(MerkleMultiProof::new(depth, indices, hashes))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                Self::next_field(&mut map, Field::Depth)?;
+                let depth = Self::check_depth(map.next_value()?)?;
+                Self::next_field(&mut map, Field::Indices)?;
+                let indices = map.next_value_seed(BoundedSeq::new(MAX_MULTIPROOF_ITEMS))?;
+                Self::next_field(&mut map, Field::Hashes)?;
+                let hashes = map.next_value_seed(BoundedSeq::new(
+                    MerkleMultiProof::<H>::max_hashes(depth, indices.len()),
+                ))?;
+                Ok(MerkleMultiProof::new(depth, indices, hashes))
+            }
+        }
+
+        deserializer.deserialize_struct("MerkleMultiProof", FIELDS, MultiProofVisitor(PhantomData))
+    }
+}
+
+#[cfg(feature = "wire")]
+impl<H: Hasher> BorshSerialize for MerkleMultiProof<H>
+where
+    H::Hash: BorshSerialize,
+{
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        BorshSerialize::serialize(&self.depth, writer)?;
+        writer.write_all(&(self.indices.len() as u32).to_be_bytes())?;
+        for index in &self.indices {
+            writer.write_all(&index.to_be_bytes())?;
+        }
+        writer.write_all(&(self.hashes.len() as u32).to_be_bytes())?;
+        for hash in &self.hashes {
+            BorshSerialize::serialize(hash, writer)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "wire")]
+impl<H: Hasher> SerializedSize for MerkleMultiProof<H> {
+    fn serialized_size(&self) -> usize {
+        9 + 4 * self.indices.len() + self.hashes.len() * H::hash_size()
+    }
+}
+
+#[cfg(feature = "wire")]
+impl<H: Hasher> BorshDeserialize for MerkleMultiProof<H>
+where
+    H::Hash: BorshDeserialize,
+{
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        let depth = <u8 as BorshDeserialize>::deserialize(buf)?;
+        if usize::from(depth) > MAX_PATH_LENGTH {
+            return Err(Error::new(
+                InvalidData,
+                format!("Multiproof depth {depth} exceeds maximum of {MAX_PATH_LENGTH}"),
+            ));
+        }
+
+        // Lengths are checked against their bound and the remaining input before allocating so a
+        // forged count can't reserve more memory than the payload could fill.
+        let len = u32::from_be_bytes(BorshDeserialize::deserialize(buf)?) as usize;
+        if len > MAX_MULTIPROOF_ITEMS {
+            return Err(Error::new(
+                InvalidData,
+                format!(
+                    "Multiproof proves {len} items, exceeding maximum of {MAX_MULTIPROOF_ITEMS}"
+                ),
+            ));
+        }
+        if len.saturating_mul(4) > buf.len() {
+            return Err(Error::new(
+                InvalidData,
+                "Unexpected end of multiproof indices",
+            ));
+        }
+        let mut indices = Vec::with_capacity(len);
+        for _ in 0..len {
+            indices.push(u32::from_be_bytes(BorshDeserialize::deserialize(buf)?));
+        }
+
+        let len = u32::from_be_bytes(BorshDeserialize::deserialize(buf)?) as usize;
+        let max_hashes = Self::max_hashes(depth, indices.len());
+        if len > max_hashes {
+            return Err(Error::new(
+                InvalidData,
+                format!("Multiproof holds {len} hashes, at most {max_hashes} can be used"),
+            ));
+        }
+        let mut hashes = Vec::with_capacity(len);
+        for _ in 0..len {
+            hashes.push(<H::Hash as BorshDeserialize>::deserialize(buf)?);
+        }
+
+        Ok(Self::new(depth, indices, hashes))
+    }
+}
+
+/// Fold a set of nodes at depth `depth` up to the root, returning the root hash.
+///
+/// `level` holds `(node id, hash)` pairs sorted by node id. Whenever a node's sibling is not
+/// itself part of `level`, `sibling` is called with the sibling's node id to obtain its hash.
+fn fold_multiproof<H: Hasher>(
+    depth: u8,
+    mut level: Vec<(usize, H::Hash)>,
+    mut sibling: impl FnMut(usize) -> Option<H::Hash>,
+) -> Option<H::Hash> {
+    for _ in 0..depth {
+        let mut next = Vec::with_capacity(level.len());
+        let mut i = 0;
+        while i < level.len() {
+            let (id, hash) = level[i];
+            let other = match level.get(i + 1) {
+                Some((other_id, other)) if *other_id == id ^ 1 => {
+                    i += 1;
+                    *other
+                }
+                _ => sibling(id ^ 1)?,
+            };
+            next.push((id / 2, hash_node::<H>(&hash, &other)));
+            i += 1;
+        }
+        level = next;
+    }
+
+    match level.as_slice() {
+        [(1, root)] => Some(*root),
+        _ => None,
+    }
+}
+
+/// Hash `items` into leaves positioned by `indices`, returning them sorted by node id. Fails if
+/// an index is out of range or the same index is given two different items.
+fn multiproof_leaves<H: Hasher>(
+    depth: u8,
+    indices: &[u32],
+    domain: &[u8],
+    items: &[&[u8]],
+) -> Option<Vec<(usize, H::Hash)>> {
+    if items.is_empty() || items.len() != indices.len() || depth >= usize::BITS as u8 {
+        return None;
+    }
+
+    let capacity = 1usize << depth;
+    let mut leaves = Vec::with_capacity(items.len());
+    for (&index, item) in indices.iter().zip(items) {
+        let index = usize::try_from(index).ok().filter(|i| *i < capacity)?;
+        leaves.push((capacity + index, hash_leaf_in_domain::<H>(domain, item)));
+    }
+
+    leaves.sort_by_key(|(id, _)| *id);
+    for pair in leaves.windows(2) {
+        if pair[0].0 == pair[1].0 && pair[0].1 != pair[1].1 {
+            return None;
+        }
+    }
+    leaves.dedup_by_key(|(id, _)| *id);
+    Some(leaves)
+}
+
+/// An AggregatedProof proves batches of items against several roots at once.
+///
+/// A relayer submitting updates from several slots would otherwise carry an independent path for
+/// every update. Instead the items of each slot are proven with a single MerkleMultiProof, so
+/// updates from the same slot share the interior hashes of their common branches, and every batch
+/// is checked with one call.
+///
+/// When Borsh encoded the proofs are prefixed with their count as a big endian u32, each encoded
+/// as a `MerkleMultiProof`.
+#[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(deserialize = ""))]
+pub struct AggregatedProof<H: Hasher> {
+    proofs: Vec<MerkleMultiProof<H>>,
+}
+
+#[cfg(feature = "wire")]
+impl<H: Hasher> BorshSerialize for AggregatedProof<H>
+where
+    H::Hash: BorshSerialize,
+{
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&(self.proofs.len() as u32).to_be_bytes())?;
+        for proof in &self.proofs {
+            BorshSerialize::serialize(proof, writer)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "wire")]
+impl<H: Hasher> SerializedSize for AggregatedProof<H> {
+    fn serialized_size(&self) -> usize {
+        4 + self
+            .proofs
+            .iter()
+            .map(SerializedSize::serialized_size)
+            .sum::<usize>()
+    }
+}
+
+#[cfg(feature = "wire")]
+impl<H: Hasher> BorshDeserialize for AggregatedProof<H>
+where
+    H::Hash: BorshDeserialize,
+{
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        // Every proof takes at least 9 bytes, the depth and both counts.
+        let len = u32::from_be_bytes(BorshDeserialize::deserialize(buf)?) as usize;
+        if len.saturating_mul(9) > buf.len() {
+            return Err(Error::new(
+                InvalidData,
+                "Unexpected end of aggregated proof",
+            ));
+        }
+        let mut proofs = Vec::with_capacity(len);
+        for _ in 0..len {
+            proofs.push(BorshDeserialize::deserialize(buf)?);
+        }
+        Ok(Self::new(proofs))
+    }
+}
+
+impl<H: Hasher> AggregatedProof<H> {
+    pub fn new(proofs: Vec<MerkleMultiProof<H>>) -> Self {
+        Self { proofs }
+    }
+
+    /// The proof of each batch, in the order the batches were proven.
+    pub fn proofs(&self) -> &[MerkleMultiProof<H>] {
+        &self.proofs
+    }
+
+    /// Verify that every batch of items is a member of the tree with the batch's root. Batches
+    /// must be given in the same order they were proven in, with their items in the same order.
+    pub fn verify(self, batches: &[(H::Hash, &[&[u8]])]) -> bool {
+        self.proofs.len() == batches.len()
+            && self
+                .proofs
+                .into_iter()
+                .zip(batches)
+                .all(|(proof, (root, items))| MerkleRoot::<H>::new(*root).check_many(proof, items))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<H: Hasher> AggregatedProof<H> {
+    /// Prove each batch of items against its accumulator.
+    ///
+    /// Returns `None` if any of the items is not in the accumulator of its batch.
+    pub fn prove(batches: &[(&MerkleAccumulator<H>, &[&[u8]])]) -> Option<Self> {
+        let proofs = batches
+            .iter()
+            .map(|(accumulator, items)| accumulator.prove_many(items))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self::new(proofs))
+    }
+}
+
+/// A MerkleAccumulator maintains a Merkle Tree.
+///
+/// The implementation is based on Solana's Merkle Tree implementation. This structure also stores
+/// the items that are in the tree due to the need to look-up the index of an item in the tree in
+/// order to create a proof.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, BorshSerialize, Serialize, Deserialize, Default)]
+pub struct MerkleAccumulator<H: Hasher = Keccak256> {
+    pub root:  H::Hash,
+    #[serde(skip)]
+    pub nodes: Vec<H::Hash>,
+
+    // Maps leaf hashes to every node holding them, in ascending order, so proofs are found
+    // without scanning the tree. It is not serialized but rebuilt from `nodes` on load, and every
+    // method modifying the tree keeps it up to date, so `nodes` must not be modified directly.
+    #[borsh_skip]
+    #[serde(skip)]
+    index: HashMap<H::Hash, Vec<usize>>,
+
+    // Domain mixed into every leaf, see `new_with_domain`. Like the root alone it is not enough
+    // to rebuild the tree, so it is not serialized and must be restored with `with_domain`.
+    #[borsh_skip]
+    #[serde(skip)]
+    domain: Vec<u8>,
+}
+
+// The index is derived from `nodes` so it does not take part in equality.
+#[cfg(feature = "std")]
+impl<H: Hasher> PartialEq for MerkleAccumulator<H> {
+    fn eq(&self, other: &Self) -> bool {
+        self.root == other.root && self.nodes == other.nodes && self.domain == other.domain
+    }
+}
+
+#[cfg(feature = "std")]
+impl<H: Hasher> Eq for MerkleAccumulator<H> {
+}
+
+#[cfg(feature = "std")]
+impl<H: Hasher> BorshDeserialize for MerkleAccumulator<H>
+where
+    H::Hash: BorshDeserialize,
+{
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        let mut accumulator = Self {
+            root:   BorshDeserialize::deserialize(buf)?,
+            nodes:  BorshDeserialize::deserialize(buf)?,
+            index:  HashMap::new(),
+            domain: Vec::new(),
+        };
+
+        // Either only the root is known, or the nodes form a complete tree under that root. The
+        // nodes are not re-hashed here, `from_snapshot` does that for untrusted trees.
+        let nodes = accumulator.nodes.len();
+        if nodes != 0 && (nodes < 2 || !nodes.is_power_of_two()) {
+            return Err(Error::new(
+                InvalidData,
+                format!("Invalid number of nodes {nodes}"),
+            ));
+        }
+        if nodes != 0 && accumulator.nodes[1] != accumulator.root {
+            return Err(Error::new(InvalidData, "Nodes do not match the root"));
+        }
+
+        accumulator.build_index();
+        Ok(accumulator)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, H: Hasher + 'a> MerkleAccumulator<H> {
+    #[deprecated(note = "use `wire::v1::WormholeMerkleRoot::from_accumulator` instead")]
+    pub fn serialize(&self, storage: u32) -> Vec<u8> {
+        let root = crate::wire::v1::WormholeMerkleRoot::from_accumulator(self, storage);
+        BorshSerialize::try_to_vec(&root).expect("writing to a Vec cannot fail")
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, H: Hasher + 'a> Accumulator<'a> for MerkleAccumulator<H> {
+    type Proof = MerklePath<H>;
+
+    // Items are hashed as they are consumed so only their hashes are held in memory. Callers that
+    // know the number of items up front should use `from_exact_iter` to avoid the extra copy.
+    fn from_set(items: impl Iterator<Item = &'a [u8]>) -> Result<Self, AccumulatorError> {
+        let leaves: Vec<H::Hash> = items.map(|i| hash_leaf::<H>(i)).collect();
+        Self::from_leaves(&leaves)
+    }
+
+    fn prove(&'a self, item: &[u8]) -> Result<Self::Proof, AccumulatorError> {
+        let index = self
+            .find_leaf(&self.hash_item(item).0)
+            .ok_or(AccumulatorError::ItemNotFound)?;
+        Ok(self.find_path(index))
+    }
+
+    // NOTE: This `check` call is intended to be generic accross accumulator implementations, but
+    // for a merkle tree the proof does not use the `self` parameter as the proof is standalone
+    // and doesn't need the original nodes. Normally a merkle API would be something like:
+    //
+    // ```
+    // MerkleTree::check(proof)
+    // ```
+    //
+    // or even:
+    //
+    // ```
+    // proof.verify()
+    // ```
+    //
+    // But to stick to the Accumulator trait we do it via the trait method. Verifiers that only
+    // hold the root should use `MerkleRoot` directly.
+    //
+    // Proofs longer than the depth of the tree can never be valid, so they are rejected before
+    // any hashing is done.
+    fn check(&'a self, proof: Self::Proof, item: &[u8]) -> bool {
+        self.check_with_max_depth(proof, item, self.max_depth())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<H: Hasher> MerkleAccumulator<H> {
+    pub fn new(items: &[&[u8]]) -> Result<Self, AccumulatorError> {
+        // Chunks are large enough for the hasher to batch leaves within each thread.
+        #[cfg(feature = "parallel")]
+        let leaves: Vec<H::Hash> = items
+            .par_chunks(1024)
+            .flat_map_iter(|chunk| hash_leaves::<H>(chunk))
+            .collect();
+        #[cfg(not(feature = "parallel"))]
+        let leaves: Vec<H::Hash> = hash_leaves::<H>(items);
+        Self::from_leaves(&leaves)
+    }
+
+    /// Create an accumulator whose leaves are separated by `domain`, e.g. `b"pyth-price-v2"`.
+    ///
+    /// The domain is mixed into every leaf hash so trees built for different applications on the
+    /// same chain can never share a leaf, and a proof for one can never be replayed against the
+    /// other. Proofs are checked against the root with `MerkleRoot::check_in_domain`. An empty
+    /// domain builds the same tree as `new`.
+    pub fn new_with_domain(items: &[&[u8]], domain: &[u8]) -> Result<Self, AccumulatorError> {
+        let leaves: Vec<H::Hash> = items
+            .iter()
+            .map(|i| hash_leaf_in_domain::<H>(domain, i))
+            .collect();
+        Ok(Self::from_leaves(&leaves)?.with_domain(domain))
+    }
+
+    /// Set the domain of an accumulator whose nodes were already built with it, such as one
+    /// deserialized from a tree created by `new_with_domain`. The nodes are not re-hashed.
+    pub fn with_domain(mut self, domain: &[u8]) -> Self {
+        self.domain = domain.to_vec();
+        self
+    }
+
+    /// Domain mixed into the leaves of the tree, empty unless built with `new_with_domain`.
+    pub fn domain(&self) -> &[u8] {
+        &self.domain
+    }
+
+    /// Create an accumulator, failing if any item appears more than once.
+    ///
+    /// Duplicates shadow each other in a tree built with `new`, as `prove` only finds the first
+    /// copy. Duplicated price messages are almost always a publisher bug so this reports the
+    /// first duplicate found instead.
+    pub fn try_new_unique(items: &[&[u8]]) -> Result<Self, AccumulatorError> {
+        let leaves: Vec<H::Hash> = hash_leaves::<H>(items);
+        let mut seen = HashMap::with_capacity(leaves.len());
+        for (duplicate, leaf) in leaves.iter().enumerate() {
+            if let Some(&first) = seen.get(leaf) {
+                return Err(AccumulatorError::DuplicateItem { first, duplicate });
+            }
+            seen.insert(*leaf, duplicate);
+        }
+
+        Self::from_leaves(&leaves)
+    }
+
+    /// Create an accumulator whose root only depends on the set of items, not their order.
+    ///
+    /// The leaf hashes are sorted by their bytes before the tree is built so independently
+    /// constructed trees over the same items, including from other implementations, produce
+    /// identical roots. Proofs are generated and checked exactly as for a tree built with `new`.
+    pub fn new_canonical(items: &[&[u8]]) -> Result<Self, AccumulatorError> {
+        let mut leaves: Vec<H::Hash> = hash_leaves::<H>(items);
+        leaves.sort_unstable_by(|a, b| a.as_ref().cmp(b.as_ref()));
+        Self::from_leaves(&leaves)
+    }
+
+    /// Build a tree from an iterator of known length, hashing each item straight into the tree.
+    ///
+    /// Unlike `new` the items never have to be collected, so peak memory is the size of the tree
+    /// alone. Fails if the iterator is empty or yields a different number of items than its
+    /// length.
+    pub fn from_exact_iter<'b>(
+        items: impl ExactSizeIterator<Item = &'b [u8]>,
+    ) -> Result<Self, AccumulatorError> {
+        Self::from_exact_leaves(items.map(|i| hash_leaf::<H>(i)))
+    }
+
+    /// Build a tree from already hashed leaves.
+    fn from_leaves(leaves: &[H::Hash]) -> Result<Self, AccumulatorError> {
+        Self::from_exact_leaves(leaves.iter().copied())
+    }
+
+    fn from_exact_leaves(
+        leaves: impl ExactSizeIterator<Item = H::Hash>,
+    ) -> Result<Self, AccumulatorError> {
+        Self::build(Vec::new(), HashMap::new(), leaves)
+    }
+
+    /// Build a tree from an arena's storage, see `MerkleArena`.
+    ///
+    /// As long as the arena holds enough capacity for the tree building does not allocate. Call
+    /// `MerkleArena::reclaim` once the accumulator is no longer needed to reuse its storage for the
+    /// next one.
+    pub fn new_in(arena: &mut MerkleArena<H>, items: &[&[u8]]) -> Result<Self, AccumulatorError> {
+        if items.is_empty() {
+            return Err(AccumulatorError::EmptyInput);
+        }
+
+        let nodes = std::mem::take(&mut arena.nodes);
+        let index = std::mem::take(&mut arena.index);
+        Self::build(nodes, index, items.iter().map(|i| hash_leaf::<H>(i)))
+    }
+
+    /// Build a tree into the given node and index storage, which is cleared first.
+    fn build(
+        mut tree: Vec<H::Hash>,
+        mut index: HashMap<H::Hash, Vec<usize>>,
+        mut leaves: impl ExactSizeIterator<Item = H::Hash>,
+    ) -> Result<Self, AccumulatorError> {
+        let len = leaves.len();
+        if len == 0 {
+            return Err(AccumulatorError::EmptyInput);
+        }
+
+        let depth = len.next_power_of_two().trailing_zeros();
+        tree.clear();
+        tree.resize(1 << (depth + 1), Default::default());
+        index.clear();
+        index.reserve(len);
+
+        // Filling the leaf hashes
+        let null = hash_null::<H>();
+        for (i, node) in tree[(1 << depth)..].iter_mut().enumerate() {
+            *node = match (i < len, leaves.next()) {
+                (true, Some(leaf)) => leaf,
+                (true, None) => return Err(AccumulatorError::LengthMismatch),
+                (false, _) => null,
+            };
+        }
+        if leaves.next().is_some() {
+            return Err(AccumulatorError::LengthMismatch);
+        }
+
+        // Filling the node hashes from bottom to top
+        for k in (1..=depth).rev() {
+            let level = k - 1;
+            let level_num_nodes = 1 << level;
+
+            // Nodes within a level only depend on the level below, so with the `parallel`
+            // feature each level is hashed concurrently.
+            #[cfg(feature = "parallel")]
+            {
+                let (parents, children) = tree.split_at_mut(2 * level_num_nodes);
+                parents[level_num_nodes..]
+                    .par_iter_mut()
+                    .enumerate()
+                    .for_each(|(i, node)| {
+                        *node = hash_node::<H>(&children[2 * i], &children[2 * i + 1]);
+                    });
+            }
+
+            #[cfg(not(feature = "parallel"))]
+            for i in 0..level_num_nodes {
+                let id = (1 << level) + i;
+                tree[id] = hash_node::<H>(&tree[id * 2], &tree[id * 2 + 1]);
+            }
+        }
+
+        let mut accumulator = Self {
+            root: tree[1],
+            nodes: tree,
+            index,
+            domain: Vec::new(),
+        };
+        accumulator.build_index();
+        Ok(accumulator)
+    }
+
+    /// Append an item to the tree, returning the index of its leaf.
+    ///
+    /// Only the branch from the new leaf to the root is re-hashed. When the tree is full it is
+    /// first grown to twice its capacity, so the resulting root is always identical to building
+    /// the tree from scratch with `new` over the same items. An empty accumulator starts a new
+    /// tree, but one holding only its root fails with `MissingNodes` rather than discarding it.
+    pub fn insert(&mut self, item: &[u8]) -> Result<usize, AccumulatorError> {
+        if self.nodes.is_empty() {
+            if self.root != H::Hash::default() {
+                return Err(AccumulatorError::MissingNodes);
+            }
+            let domain = std::mem::take(&mut self.domain);
+            *self = Self::new_with_domain(&[item], &domain)?;
+            return Ok(0);
+        }
+
+        let index = self.len();
+        if index == self.capacity() {
+            self.grow();
+        }
+
+        let id = self.capacity() + index;
+        self.set_leaf(id, self.hash_item(item).0);
+        Ok(index)
+    }
+
+    /// Replace the item stored at leaf `index`, re-hashing only the affected branch.
+    ///
+    /// Returns `false` if `index` does not point at an existing item.
+    pub fn update_leaf(&mut self, index: usize, item: &[u8]) -> bool {
+        if index >= self.len() {
+            return false;
+        }
+
+        let id = self.capacity() + index;
+        self.set_leaf(id, self.hash_item(item).0);
+        true
+    }
+
+    /// Remove the item stored at leaf `index`.
+    ///
+    /// Like `Vec::swap_remove` the last item is moved into the removed slot so that the leaves
+    /// stay contiguous, this touches at most two branches of the tree. The tree is shrunk once
+    /// it is at most half full so the root matches a freshly built tree over the same items.
+    ///
+    /// Returns `false` if `index` does not point at an existing item or if it is the only item
+    /// left, as an empty accumulator cannot be represented.
+    pub fn remove(&mut self, index: usize) -> bool {
+        let len = self.len();
+        if index >= len || len == 1 {
+            return false;
+        }
+
+        let last = len - 1;
+        if index != last {
+            let id = self.capacity() + index;
+            self.set_leaf(id, self.nodes[self.capacity() + last]);
+        }
+
+        let id = self.capacity() + last;
+        self.set_leaf(id, hash_null::<H>());
+
+        if last <= self.capacity() / 2 {
+            self.shrink();
+        }
+
+        true
+    }
+
+    /// Prove the item at leaf `index` is a member of the accumulator.
+    ///
+    /// Unlike `prove`, which always proves the first leaf holding an item, this can prove any
+    /// copy of an item that appears in the tree more than once.
+    pub fn prove_at(&self, index: usize) -> Option<MerklePath<H>> {
+        if index >= self.len() {
+            return None;
+        }
+        Some(self.find_path(self.capacity() + index))
+    }
+
+    /// Indices of every leaf holding `item`, in ascending order.
+    pub fn leaf_index_of(&self, item: &[u8]) -> Vec<usize> {
+        let capacity = self.capacity();
+        self.index
+            .get(&self.hash_item(item).0)
+            .map(|ids| ids.iter().map(|id| id - capacity).collect())
+            .unwrap_or_default()
+    }
+
+    /// Prove several items are members of the accumulator with a single proof.
+    ///
+    /// Returns `None` if any of the items is not in the tree, or if there are more than
+    /// `MAX_MULTIPROOF_ITEMS` of them as the proof could not be deserialized.
+    pub fn prove_many(&self, items: &[&[u8]]) -> Option<MerkleMultiProof<H>> {
+        if items.len() > MAX_MULTIPROOF_ITEMS {
+            return None;
+        }
+
+        let capacity = self.capacity();
+        let mut indices = Vec::with_capacity(items.len());
+        for item in items {
+            let id = self.find_leaf(&self.hash_item(item).0)?;
+            indices.push(u32::try_from(id.checked_sub(capacity)?).ok()?);
+        }
+
+        let depth = capacity.trailing_zeros() as u8;
+        let leaves = multiproof_leaves::<H>(depth, &indices, &self.domain, items)?;
+        let mut hashes = Vec::new();
+        fold_multiproof::<H>(depth, leaves, |id| {
+            hashes.push(self.nodes[id]);
+            Some(self.nodes[id])
+        })?;
+
+        Some(MerkleMultiProof::new(depth, indices, hashes))
+    }
+
+    /// Verify that all `items` are members of the accumulator. Items must be given in the same
+    /// order they were passed to `prove_many`.
+    pub fn check_many(&self, proof: MerkleMultiProof<H>, items: &[&[u8]]) -> bool {
+        MerkleRoot::<H>::new(self.root).check_many_in_domain(proof, &self.domain, items)
+    }
+
+    /// Verify an item is a member of the accumulator, rejecting proofs longer than `max_depth`.
+    pub fn check_with_max_depth(
+        &self,
+        proof: MerklePath<H>,
+        item: &[u8],
+        max_depth: usize,
+    ) -> bool {
+        proof.verify_in_domain_with_max_depth(&self.root, &self.domain, item, max_depth)
+    }
+
+    /// Hash `item` into a leaf of this tree, taking its domain into account, so that it can be
+    /// checked against several proofs with `check_hashed`.
+    pub fn hash_item(&self, item: &[u8]) -> LeafHash<H> {
+        LeafHash::from_item_in_domain(&self.domain, item)
+    }
+
+    /// Verify a leaf hashed with `hash_item` is a member of the accumulator.
+    pub fn check_hashed(&self, proof: MerklePath<H>, leaf: &LeafHash<H>) -> bool {
+        proof.verify_hashed_with_max_depth(&self.root, leaf, self.max_depth())
+    }
+
+    /// Number of items in the tree. Items are always stored contiguously from the left so the
+    /// first NULL leaf marks the end of the items.
+    pub fn len(&self) -> usize {
+        let null = hash_null::<H>();
+        self.nodes[self.capacity()..].partition_point(|leaf| leaf != &null)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Depth of the tree, which is the length of every valid proof. Returns `None` for
+    /// accumulators deserialized without their nodes, which only know their root.
+    pub fn depth(&self) -> Option<usize> {
+        match self.capacity() {
+            0 => None,
+            capacity => Some(capacity.trailing_zeros() as usize),
+        }
+    }
+
+    /// Hashes of the items in the tree, in leaf order.
+    pub fn leaf_hashes(&self) -> impl Iterator<Item = &H::Hash> {
+        self.nodes[self.capacity()..].iter().take(self.len())
+    }
+
+    /// Hash of the `index`th node of `level`, counting levels from the root at level 0 down to
+    /// the leaves at level `depth()`. Padding leaves past `len()` hold the NULL hash.
+    pub fn node_at(&self, level: usize, index: usize) -> Option<H::Hash> {
+        if level > self.depth()? || index >= 1 << level {
+            return None;
+        }
+        Some(self.nodes[(1 << level) + index])
+    }
+
+    /// Compare this tree against a later version of it, leaf by leaf.
+    ///
+    /// Interior nodes are reported as `(level, index)` positions in `other` as used by
+    /// `node_at`. When both trees have the same depth these are exactly the nodes whose hash
+    /// differs, otherwise the tree was resized and every interior node of `other` is reported.
+    pub fn diff(&self, other: &Self) -> AccumulatorDiff<H> {
+        let mut diff = AccumulatorDiff::default();
+        let old: Vec<_> = self.leaf_hashes().collect();
+        let new: Vec<_> = other.leaf_hashes().collect();
+        for index in 0..old.len().max(new.len()) {
+            match (old.get(index), new.get(index)) {
+                (Some(&&old), Some(&&new)) if old != new => diff.changed.push(LeafChange {
+                    index,
+                    old: Some(old),
+                    new: Some(new),
+                }),
+                (Some(&&old), None) => diff.removed.push(LeafChange {
+                    index,
+                    old: Some(old),
+                    new: None,
+                }),
+                (None, Some(&&new)) => diff.added.push(LeafChange {
+                    index,
+                    old: None,
+                    new: Some(new),
+                }),
+                _ => {}
+            }
+        }
+
+        let depth = other.depth().unwrap_or(0);
+        if self.depth() != other.depth() {
+            diff.nodes = (0..depth)
+                .flat_map(|level| (0..1 << level).map(move |index| (level, index)))
+                .collect();
+            return diff;
+        }
+
+        // Every interior node above a modified leaf is affected, found level by level from the
+        // bottom and then ordered from the root down.
+        let mut indices: Vec<usize> = diff.leaves().map(|change| change.index).collect();
+        indices.sort_unstable();
+        for level in (0..depth).rev() {
+            indices.iter_mut().for_each(|index| *index /= 2);
+            indices.dedup();
+            diff.nodes
+                .extend(indices.iter().rev().map(|&index| (level, index)));
+        }
+        diff.nodes.reverse();
+        diff
+    }
+
+    /// Serialize the full tree, including its nodes and domain, so it can be reloaded with
+    /// `from_snapshot` and keep generating proofs.
+    ///
+    /// Layout:
+    ///
+    /// ```text
+    /// 1 byte:   compression, 0 for none and 1 for zstd
+    /// N bytes:  Borsh encoded root, nodes and domain, compressed if requested
+    /// ```
+    pub fn to_snapshot(&self, compression: SnapshotCompression) -> std::io::Result<Vec<u8>>
+    where
+        H::Hash: BorshSerialize,
+    {
+        let mut tree = Vec::new();
+        BorshSerialize::serialize(&self.root, &mut tree)?;
+        BorshSerialize::serialize(&self.nodes, &mut tree)?;
+        BorshSerialize::serialize(&self.domain, &mut tree)?;
+        match compression {
+            SnapshotCompression::None => Ok([&[0], &tree[..]].concat()),
+            #[cfg(feature = "compression")]
+            SnapshotCompression::Zstd { level } => {
+                Ok([&[1], &zstd::bulk::compress(&tree, level)?[..]].concat())
+            }
+        }
+    }
+
+    /// Load a tree written by `to_snapshot` with at most `MAX_SNAPSHOT_NODES` nodes.
+    ///
+    /// The interior nodes and root are re-hashed from the leaves and compared against the
+    /// snapshot, so a corrupted or tampered snapshot is rejected rather than producing proofs
+    /// against a root it does not commit to.
+    pub fn from_snapshot(snapshot: &[u8]) -> std::io::Result<Self>
+    where
+        H::Hash: BorshDeserialize,
+    {
+        Self::from_snapshot_with_max_nodes(snapshot, MAX_SNAPSHOT_NODES)
+    }
+
+    /// Load a tree written by `to_snapshot`, rejecting snapshots that declare more than
+    /// `max_nodes` nodes before anything is decompressed or allocated for them.
+    pub fn from_snapshot_with_max_nodes(snapshot: &[u8], max_nodes: usize) -> std::io::Result<Self>
+    where
+        H::Hash: BorshDeserialize,
+    {
+        let (compression, tree) = snapshot
+            .split_first()
+            .ok_or_else(|| Error::new(InvalidData, "Empty snapshot"))?;
+        let tree = match compression {
+            0 => {
+                Self::snapshot_node_count(tree, max_nodes)?;
+                Cow::Borrowed(tree)
+            }
+            #[cfg(feature = "compression")]
+            1 => Cow::Owned(Self::decompress_snapshot(tree, max_nodes)?),
+            #[cfg(not(feature = "compression"))]
+            1 => {
+                return Err(Error::new(
+                    InvalidData,
+                    "Snapshot is compressed but the `compression` feature is disabled",
+                ))
+            }
+            _ => {
+                return Err(Error::new(
+                    InvalidData,
+                    format!("Unsupported snapshot compression {compression}"),
+                ))
+            }
+        };
+
+        let (root, nodes, domain) = <(H::Hash, Vec<H::Hash>, Vec<u8>)>::try_from_slice(&tree)?;
+
+        let capacity = nodes.len() / 2;
+        let len = nodes[capacity..].partition_point(|leaf| leaf != &hash_null::<H>());
+        match Self::from_leaves(&nodes[capacity..capacity + len]) {
+            Ok(accumulator) if accumulator.root == root && accumulator.nodes == nodes => {
+                Ok(accumulator.with_domain(&domain))
+            }
+            _ => Err(Error::new(
+                InvalidData,
+                "Snapshot nodes do not match its root",
+            )),
+        }
+    }
+
+    /// Read the node count from the header of an uncompressed snapshot, the root followed by the
+    /// Borsh length of the nodes, and check it describes a tree of at most `max_nodes` nodes.
+    fn snapshot_node_count(header: &[u8], max_nodes: usize) -> std::io::Result<usize> {
+        let count = header
+            .get(H::hash_size()..H::hash_size() + 4)
+            .ok_or_else(|| Error::new(InvalidData, "Snapshot header is truncated"))?;
+        let count = u32::from_le_bytes([count[0], count[1], count[2], count[3]]) as usize;
+        if count < 2 || !count.is_power_of_two() {
+            return Err(Error::new(
+                InvalidData,
+                format!("Invalid number of nodes {count}"),
+            ));
+        }
+        if count > max_nodes {
+            return Err(Error::new(
+                InvalidData,
+                format!("Snapshot has {count} nodes, exceeding maximum of {max_nodes}"),
+            ));
+        }
+        Ok(count)
+    }
+
+    /// Decompress a zstd snapshot without producing more than the tree its header declares.
+    ///
+    /// The root and node count are decompressed first and checked against `max_nodes`, and the
+    /// rest is read through a limited reader, so a small malicious snapshot cannot expand into
+    /// more than the largest tree the caller accepts. Domains are far smaller than any tree so
+    /// they share the bound of the nodes.
+    #[cfg(feature = "compression")]
+    fn decompress_snapshot(compressed: &[u8], max_nodes: usize) -> std::io::Result<Vec<u8>> {
+        use std::io::Read;
+
+        let mut decoder = zstd::stream::Decoder::new(compressed)?;
+        let mut tree = vec![0; H::hash_size() + 4];
+        decoder.read_exact(&mut tree)?;
+        let nodes_size = Self::snapshot_node_count(&tree, max_nodes)? * H::hash_size();
+
+        // The nodes, the domain length and at most as many domain bytes as node bytes, plus one
+        // byte to detect a snapshot that keeps going.
+        let limit = 2 * nodes_size + 4;
+        let read = decoder.take(limit as u64 + 1).read_to_end(&mut tree)?;
+        if read > limit {
+            return Err(Error::new(
+                InvalidData,
+                "Snapshot is larger than its declared nodes",
+            ));
+        }
+        Ok(tree)
+    }
+
+    /// Wrap an already hashed node array, as laid out in `nodes`.
+    #[cfg(feature = "mmap")]
+    pub(crate) fn from_nodes(nodes: Vec<H::Hash>) -> Self {
+        let mut accumulator = Self {
+            root: nodes[1],
+            nodes,
+            index: HashMap::new(),
+            domain: Vec::new(),
+        };
+        accumulator.build_index();
+        accumulator
+    }
+
+    /// Maximum length of a valid proof, `MAX_PATH_LENGTH` when the depth is unknown.
+    fn max_depth(&self) -> usize {
+        self.depth().unwrap_or(MAX_PATH_LENGTH)
+    }
+
+    /// Number of leaves the tree can hold before it has to grow.
+    fn capacity(&self) -> usize {
+        self.nodes.len() / 2
+    }
+
+    /// Find the first node holding the leaf hash `leaf`.
+    fn find_leaf(&self, leaf: &H::Hash) -> Option<usize> {
+        self.index.get(leaf).and_then(|ids| ids.first()).copied()
+    }
+
+    /// Rebuild the leaf index from scratch.
+    fn build_index(&mut self) {
+        let capacity = self.capacity();
+        let null = hash_null::<H>();
+        self.index.clear();
+        for (i, leaf) in self.nodes[capacity..].iter().enumerate() {
+            if leaf != &null {
+                self.index.entry(*leaf).or_default().push(capacity + i);
+            }
+        }
+    }
+
+    /// Replace the leaf at node `id`, keeping the index up to date and re-hashing its branch.
+    fn set_leaf(&mut self, id: usize, leaf: H::Hash) {
+        let old = std::mem::replace(&mut self.nodes[id], leaf);
+        if let Some(ids) = self.index.get_mut(&old) {
+            ids.retain(|other| *other != id);
+            if ids.is_empty() {
+                self.index.remove(&old);
+            }
+        }
+        if leaf != hash_null::<H>() {
+            let ids = self.index.entry(leaf).or_default();
+            ids.insert(ids.partition_point(|other| *other < id), id);
+        }
+        self.update_path(id);
+    }
+
+    /// Re-hash every node from the node at `id` up to the root.
+    fn update_path(&mut self, mut id: usize) {
+        while id > 1 {
+            id /= 2;
+            self.nodes[id] = hash_node::<H>(&self.nodes[id * 2], &self.nodes[id * 2 + 1]);
+        }
+        self.root = self.nodes[1];
+    }
+
+    /// Double the capacity of the tree. The current tree becomes the left subtree of the new
+    /// root and the right subtree is filled with NULL leaves.
+    fn grow(&mut self) {
+        let depth = self.capacity().trailing_zeros();
+        let mut tree: Vec<H::Hash> = vec![Default::default(); self.nodes.len() * 2];
+        let mut null = hash_null::<H>();
+
+        // Level `k` of the old tree becomes the left half of level `k + 1`, while the right half
+        // is a NULL subtree whose height shrinks as we move towards the leaves.
+        for k in (0..=depth).rev() {
+            let width = 1 << k;
+            tree[2 * width..3 * width].copy_from_slice(&self.nodes[width..2 * width]);
+            tree[3 * width..4 * width].fill(null);
+            null = hash_node::<H>(&null, &null);
+        }
+
+        tree[1] = hash_node::<H>(&tree[2], &tree[3]);
+        self.root = tree[1];
+        self.nodes = tree;
+        self.build_index();
+    }
+
+    /// Halve the capacity of the tree, keeping only the left subtree of the root. Callers must
+    /// ensure the right subtree contains no items.
+    fn shrink(&mut self) {
+        let depth = self.capacity().trailing_zeros();
+        let mut tree: Vec<H::Hash> = vec![Default::default(); self.nodes.len() / 2];
+        for k in 0..depth {
+            let width = 1 << k;
+            tree[width..2 * width].copy_from_slice(&self.nodes[2 * width..3 * width]);
+        }
+
+        self.root = tree[1];
+        self.nodes = tree;
+        self.build_index();
+    }
+
+    fn find_path(&self, mut index: usize) -> MerklePath<H> {
+        let mut path = Vec::new();
+        while index > 1 {
+            path.push(self.nodes[index ^ 1].clone());
+            index /= 2;
+        }
+        MerklePath::new(path)
+    }
+}
+
+/// A MerkleArena owns node storage that is reused across MerkleAccumulators.
+///
+/// The validator rebuilds the accumulator every slot, usually with a similar number of items.
+/// Building with `MerkleAccumulator::new_in` and handing the accumulator back with `reclaim`
+/// once the slot is done lets every rebuild reuse the same allocations.
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub struct MerkleArena<H: Hasher = Keccak256> {
+    nodes: Vec<H::Hash>,
+    index: HashMap<H::Hash, Vec<usize>>,
+}
+
+#[cfg(feature = "std")]
+impl<H: Hasher> MerkleArena<H> {
+    /// Create an arena that can build trees of up to `items` items without allocating.
+    pub fn with_capacity(items: usize) -> Self {
+        Self {
+            nodes: Vec::with_capacity(2 * items.next_power_of_two()),
+            index: HashMap::with_capacity(items),
+        }
+    }
+
+    /// Take back the storage of an accumulator that is no longer needed.
+    pub fn reclaim(&mut self, accumulator: MerkleAccumulator<H>) {
+        // Keep whichever storage is larger so the arena never shrinks.
+        if accumulator.nodes.capacity() > self.nodes.capacity() {
+            self.nodes = accumulator.nodes;
+        }
+        if accumulator.index.capacity() > self.index.capacity() {
+            self.index = accumulator.index;
+        }
+    }
+}
+
+/// Largest number of nodes `MerkleAccumulator::from_snapshot` loads, enough for a tree of two
+/// million items. Trees that are larger can be loaded with `from_snapshot_with_max_nodes`.
+#[cfg(feature = "std")]
+pub const MAX_SNAPSHOT_NODES: usize = 1 << 22;
+
+/// Compression applied by `MerkleAccumulator::to_snapshot`.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnapshotCompression {
+    None,
+    /// Zstandard at the given level, see `zstd::compression_level_range`.
+    #[cfg(feature = "compression")]
+    Zstd {
+        level: i32,
+    },
+}
+
+/// A change to the leaf at `index`, holding the leaf hashes before and after the change.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LeafChange<H: Hasher> {
+    pub index: usize,
+    pub old:   Option<H::Hash>,
+    pub new:   Option<H::Hash>,
+}
+
+/// The differences between two MerkleAccumulators, as returned by `MerkleAccumulator::diff`.
+///
+/// Leaves are compared by position, so a leaf present in both trees but holding a different item
+/// is changed, while leaves past the end of the shorter tree are added or removed.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccumulatorDiff<H: Hasher> {
+    pub added:   Vec<LeafChange<H>>,
+    pub removed: Vec<LeafChange<H>>,
+    pub changed: Vec<LeafChange<H>>,
+    /// Affected interior nodes as `(level, index)`, ordered from the root down.
+    pub nodes:   Vec<(usize, usize)>,
+}
+
+#[cfg(feature = "std")]
+impl<H: Hasher> Default for AccumulatorDiff<H> {
+    fn default() -> Self {
+        Self {
+            added:   Vec::new(),
+            removed: Vec::new(),
+            changed: Vec::new(),
+            nodes:   Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<H: Hasher> AccumulatorDiff<H> {
+    /// Whether both trees hold the same leaves.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Every leaf change regardless of its kind.
+    pub fn leaves(&self) -> impl Iterator<Item = &LeafChange<H>> {
+        self.added
+            .iter()
+            .chain(self.removed.iter())
+            .chain(self.changed.iter())
+    }
 }
 
-/// A MerkleAccumulator maintains a Merkle Tree.
+/// An AbsenceProof proves an item is not a member of a sorted accumulator.
 ///
-/// The implementation is based on Solana's Merkle Tree implementation. This structure also stores
-/// the items that are in the tree due to the need to look-up the index of an item in the tree in
-/// order to create a proof.
-#[derive(
-    Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize, Serialize, Deserialize, Default,
-)]
-pub struct MerkleAccumulator<H: Hasher = Keccak256> {
-    pub root:  H::Hash,
-    #[serde(skip)]
-    pub nodes: Vec<H::Hash>,
+/// Because `hash_node` orders its children the position of a leaf cannot be recovered from its
+/// path, so two independent paths cannot prove two leaves are adjacent. Instead a sorted tree
+/// stores a gap leaf between every pair of adjacent items, and the proof is the pair of items
+/// surrounding the absent item together with the path of their gap leaf.
+///
+/// When Borsh encoded the bounds are Borsh options of byte vectors, followed by the path.
+#[derive(Clone, Default, Debug, PartialEq, Eq, Serialize)]
+pub struct AbsenceProof<H: Hasher> {
+    pub low:  Option<Vec<u8>>,
+    pub high: Option<Vec<u8>>,
+    pub path: MerklePath<H>,
 }
 
-// Layout:
-//
-// ```
-// 4 bytes:  magic number
-// 1 byte:   update type
-// 4 byte:   storage id
-// 32 bytes: root hash
-// ```
-//
-// TODO: This code does not belong to MerkleAccumulator, we should be using the wire data types in
-// calling code to wrap this value.
-impl<'a, H: Hasher + 'a> MerkleAccumulator<H> {
-    pub fn serialize(&self, storage: u32) -> Vec<u8> {
-        let mut serialized = vec![];
-        serialized.extend_from_slice(0x41555756u32.to_be_bytes().as_ref());
-        serialized.extend_from_slice(0u8.to_be_bytes().as_ref());
-        serialized.extend_from_slice(storage.to_be_bytes().as_ref());
-        serialized.extend_from_slice(self.root.as_ref());
-        serialized
+#[cfg(feature = "wire")]
+impl<H: Hasher> BorshSerialize for AbsenceProof<H>
+where
+    H::Hash: BorshSerialize,
+{
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        BorshSerialize::serialize(&self.low, writer)?;
+        BorshSerialize::serialize(&self.high, writer)?;
+        BorshSerialize::serialize(&self.path, writer)
     }
 }
 
-impl<'a, H: Hasher + 'a> Accumulator<'a> for MerkleAccumulator<H> {
-    type Proof = MerklePath<H>;
-
-    fn from_set(items: impl Iterator<Item = &'a [u8]>) -> Option<Self> {
-        let items: Vec<&[u8]> = items.collect();
-        Self::new(&items)
+#[cfg(feature = "wire")]
+impl<H: Hasher> SerializedSize for AbsenceProof<H> {
+    fn serialized_size(&self) -> usize {
+        let bound = |bound: &Option<Vec<u8>>| bound.as_ref().map_or(1, |bound| 5 + bound.len());
+        bound(&self.low) + bound(&self.high) + self.path.serialized_size()
     }
+}
 
-    fn prove(&'a self, item: &[u8]) -> Option<Self::Proof> {
-        let item = hash_leaf::<H>(item);
-        let index = self.nodes.iter().position(|i| i == &item)?;
-        Some(self.find_path(index))
+#[cfg(feature = "wire")]
+impl<H: Hasher> BorshDeserialize for AbsenceProof<H>
+where
+    H::Hash: BorshDeserialize,
+{
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        Ok(Self {
+            low:  BorshDeserialize::deserialize(buf)?,
+            high: BorshDeserialize::deserialize(buf)?,
+            path: BorshDeserialize::deserialize(buf)?,
+        })
     }
+}
 
-    // NOTE: This `check` call is intended to be generic accross accumulator implementations, but
-    // for a merkle tree the proof does not use the `self` parameter as the proof is standalone
-    // and doesn't need the original nodes. Normally a merkle API would be something like:
-    //
-    // ```
-    // MerkleTree::check(proof)
-    // ```
-    //
-    // or even:
-    //
-    // ```
-    // proof.verify()
-    // ```
-    //
-    // But to stick to the Accumulator trait we do it via the trait method.
-    fn check(&'a self, proof: Self::Proof, item: &[u8]) -> bool {
-        let mut current = hash_leaf::<H>(item);
-        for hash in proof.0 {
-            current = hash_node::<H>(&current, &hash);
-        }
-        current == self.root
-    }
+/// A SortedMerkleAccumulator is a MerkleAccumulator built over sorted items which can also prove
+/// non-membership.
+///
+/// The leaves are laid out as `[gap(-, a), a, gap(a, b), b, ..., z, gap(z, -)]` so membership
+/// proofs for the items work exactly like they do for an unsorted tree. The items are kept in
+/// order to locate the gap surrounding an absent item.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SortedMerkleAccumulator<H: Hasher = Keccak256> {
+    pub accumulator: MerkleAccumulator<H>,
+    items:           Vec<Vec<u8>>,
 }
 
-impl<H: Hasher> MerkleAccumulator<H> {
-    pub fn new(items: &[&[u8]]) -> Option<Self> {
+#[cfg(feature = "std")]
+impl<H: Hasher> SortedMerkleAccumulator<H> {
+    /// Create a sorted accumulator, duplicate items are only included once.
+    pub fn new(items: &[&[u8]]) -> Result<Self, AccumulatorError> {
+        let mut items: Vec<Vec<u8>> = items.iter().map(|i| i.to_vec()).collect();
+        items.sort();
+        items.dedup();
         if items.is_empty() {
-            return None;
+            return Err(AccumulatorError::EmptyInput);
         }
 
-        let depth = items.len().next_power_of_two().trailing_zeros();
-        let mut tree: Vec<H::Hash> = vec![Default::default(); 1 << (depth + 1)];
-
-        // Filling the leaf hashes
-        for i in 0..(1 << depth) {
-            if i < items.len() {
-                tree[(1 << depth) + i] = hash_leaf::<H>(items[i].as_ref());
-            } else {
-                tree[(1 << depth) + i] = hash_null::<H>();
-            }
+        let mut leaves = Vec::with_capacity(items.len() * 2 + 1);
+        let mut low = None;
+        for item in &items {
+            leaves.push(hash_gap::<H>(low, Some(item)));
+            leaves.push(hash_leaf::<H>(item));
+            low = Some(item.as_slice());
         }
+        leaves.push(hash_gap::<H>(low, None));
 
-        // Filling the node hashes from bottom to top
-        for k in (1..=depth).rev() {
-            let level = k - 1;
-            let level_num_nodes = 1 << level;
-            for i in 0..level_num_nodes {
-                let id = (1 << level) + i;
-                tree[id] = hash_node::<H>(&tree[id * 2], &tree[id * 2 + 1]);
-            }
-        }
+        Ok(Self {
+            accumulator: MerkleAccumulator::from_leaves(&leaves)?,
+            items,
+        })
+    }
 
-        Some(Self {
-            root:  tree[1],
-            nodes: tree,
+    pub fn root(&self) -> H::Hash {
+        self.accumulator.root
+    }
+
+    /// Prove an item is a member of the accumulator.
+    pub fn prove(&self, item: &[u8]) -> Option<MerklePath<H>> {
+        let index = self
+            .items
+            .binary_search_by(|i| i.as_slice().cmp(item))
+            .ok()?;
+        Some(
+            self.accumulator
+                .find_path(self.accumulator.capacity() + 2 * index + 1),
+        )
+    }
+
+    /// Prove an item is not a member of the accumulator. Returns `None` if the item is present.
+    pub fn prove_absence(&self, item: &[u8]) -> Option<AbsenceProof<H>> {
+        let index = self
+            .items
+            .binary_search_by(|i| i.as_slice().cmp(item))
+            .err()?;
+
+        Some(AbsenceProof {
+            low:  index.checked_sub(1).map(|i| self.items[i].clone()),
+            high: self.items.get(index).cloned(),
+            path: self
+                .accumulator
+                .find_path(self.accumulator.capacity() + 2 * index),
         })
     }
 
-    fn find_path(&self, mut index: usize) -> MerklePath<H> {
-        let mut path = Vec::new();
-        while index > 1 {
-            path.push(self.nodes[index ^ 1].clone());
-            index /= 2;
-        }
-        MerklePath::new(path)
+    /// Verify an item is not a member of the accumulator.
+    pub fn check_absence(&self, proof: AbsenceProof<H>, item: &[u8]) -> bool {
+        MerkleRoot::<H>::new(self.root()).check_absence(proof, item)
     }
 }
 
@@ -182,6 +1768,7 @@ impl<H: Hasher> MerkleAccumulator<H> {
 mod test {
     use {
         super::*,
+        crate::test_utils::MerkleAccumulatorDataWrapper,
         proptest::prelude::*,
         std::{
             collections::BTreeSet,
@@ -216,53 +1803,6 @@ mod test {
         }
     }
 
-    #[derive(Debug)]
-    struct MerkleAccumulatorDataWrapper {
-        pub accumulator: MerkleAccumulator,
-        pub data:        BTreeSet<Vec<u8>>,
-    }
-
-    impl Arbitrary for MerkleAccumulatorDataWrapper {
-        type Parameters = usize;
-
-        fn arbitrary_with(size: Self::Parameters) -> Self::Strategy {
-            let size = size.saturating_add(1);
-            prop::collection::vec(
-                prop::collection::vec(any::<u8>(), 1..=10),
-                size..=size.saturating_add(100),
-            )
-            .prop_map(|v| {
-                let data: BTreeSet<Vec<u8>> = v.into_iter().collect();
-                let accumulator =
-                    MerkleAccumulator::<Keccak256>::from_set(data.iter().map(|i| i.as_ref()))
-                        .unwrap();
-                MerkleAccumulatorDataWrapper { accumulator, data }
-            })
-            .boxed()
-        }
-
-        type Strategy = BoxedStrategy<Self>;
-    }
-
-    impl Arbitrary for MerklePath<Keccak256> {
-        type Parameters = usize;
-
-        fn arbitrary_with(size: Self::Parameters) -> Self::Strategy {
-            let size = size.saturating_add(1);
-            prop::collection::vec(
-                prop::collection::vec(any::<u8>(), 32),
-                size..=size.saturating_add(100),
-            )
-            .prop_map(|v| {
-                let v = v.into_iter().map(|i| i.try_into().unwrap()).collect();
-                MerklePath(v)
-            })
-            .boxed()
-        }
-
-        type Strategy = BoxedStrategy<Self>;
-    }
-
     #[test]
     fn test_merkle() {
         let mut set: BTreeSet<&[u8]> = BTreeSet::new();
@@ -397,13 +1937,15 @@ mod test {
         // falsely prove `A` was in the original tree by tricking the implementation into performing
         // H(a || b) at the leaf.
         let faulty_accumulator = MerkleAccumulator::<Keccak256> {
-            root:  accumulator.root,
-            nodes: vec![
+            root:   accumulator.root,
+            nodes:  vec![
                 accumulator.nodes[0].clone(),
                 accumulator.nodes[1].clone(), // Root Stays the Same
                 accumulator.nodes[2].clone(), // Left node hash becomes a leaf.
                 accumulator.nodes[3].clone(), // Right node hash becomes a leaf.
             ],
+            index:  HashMap::new(),
+            domain: Vec::new(),
         };
 
         // `a || b` is the concatenation of a and b, which when hashed without pre-image fixes in
@@ -424,6 +1966,792 @@ mod test {
         assert!(faulty_accumulator.check(proof, &fake_leaf_A));
     }
 
+    #[test]
+    fn test_incremental_updates() {
+        let items: Vec<[u8; 8]> = (0..9usize).map(|i| i.to_be_bytes()).collect();
+        let items: Vec<&[u8]> = items.iter().map(|i| i.as_ref()).collect();
+
+        // Inserting one by one, including across tree growth, matches a full rebuild.
+        let mut accumulator = MerkleAccumulator::<Keccak256>::default();
+        for (i, item) in items.iter().enumerate() {
+            assert_eq!(accumulator.insert(item), Ok(i));
+            let expected = MerkleAccumulator::<Keccak256>::new(&items[..=i]).unwrap();
+            assert_eq!(accumulator, expected);
+        }
+
+        // Updating a leaf matches a rebuild with the replaced item.
+        let item_x = 100usize.to_be_bytes();
+        assert!(accumulator.update_leaf(3, &item_x));
+        assert!(!accumulator.update_leaf(9, &item_x));
+        let mut expected_items = items.clone();
+        expected_items[3] = &item_x;
+        let expected = MerkleAccumulator::<Keccak256>::new(&expected_items).unwrap();
+        assert_eq!(accumulator, expected);
+        let proof = accumulator.prove(&item_x).unwrap();
+        assert!(accumulator.check(proof, &item_x));
+
+        // Removing swaps the last item into place and shrinks the tree when possible.
+        assert!(!accumulator.remove(9));
+        while expected_items.len() > 1 {
+            assert!(accumulator.remove(0));
+            expected_items.swap_remove(0);
+            let expected = MerkleAccumulator::<Keccak256>::new(&expected_items).unwrap();
+            assert_eq!(accumulator, expected);
+        }
+
+        // The last item cannot be removed.
+        assert!(!accumulator.remove(0));
+
+        // An accumulator that only knows its root cannot be modified without losing it.
+        let mut root_only = MerkleAccumulator::<Keccak256> {
+            root: accumulator.root,
+            ..Default::default()
+        };
+        assert_eq!(
+            root_only.insert(&item_x),
+            Err(AccumulatorError::MissingNodes)
+        );
+        assert!(!root_only.update_leaf(0, &item_x));
+        assert_eq!(root_only.root, accumulator.root);
+    }
+
+    #[test]
+    fn test_prove_after_deserialization() {
+        let items: Vec<[u8; 8]> = (0..5usize).map(|i| i.to_be_bytes()).collect();
+        let items: Vec<&[u8]> = items.iter().map(|i| i.as_ref()).collect();
+        let accumulator = MerkleAccumulator::<Keccak256>::new(&items).unwrap();
+
+        // The leaf index is not serialized but rebuilt on load, so proofs are still found after
+        // a round trip.
+        let bytes = BorshSerialize::try_to_vec(&accumulator).unwrap();
+        let deserialized = MerkleAccumulator::<Keccak256>::try_from_slice(&bytes).unwrap();
+        assert_eq!(deserialized.index, accumulator.index);
+        assert_eq!(deserialized, accumulator);
+        for item in &items {
+            assert_eq!(deserialized.prove(item), accumulator.prove(item));
+        }
+
+        // A root alone deserializes, but nodes that can't form a tree under it are rejected.
+        let mut root_only = accumulator.clone();
+        root_only.nodes.clear();
+        let bytes = BorshSerialize::try_to_vec(&root_only).unwrap();
+        assert!(MerkleAccumulator::<Keccak256>::try_from_slice(&bytes).is_ok());
+        for len in [1, 3, 15] {
+            let mut invalid = accumulator.clone();
+            invalid.nodes.resize(len, Default::default());
+            let bytes = BorshSerialize::try_to_vec(&invalid).unwrap();
+            assert!(MerkleAccumulator::<Keccak256>::try_from_slice(&bytes).is_err());
+        }
+        let mut invalid = accumulator.clone();
+        invalid.root = Default::default();
+        let bytes = BorshSerialize::try_to_vec(&invalid).unwrap();
+        assert!(MerkleAccumulator::<Keccak256>::try_from_slice(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_streaming_construction() {
+        let items: Vec<[u8; 8]> = (0..5usize).map(|i| i.to_be_bytes()).collect();
+        let expected = MerkleAccumulator::<Keccak256>::from_set(items.iter().map(|i| i.as_ref()));
+        let accumulator =
+            MerkleAccumulator::<Keccak256>::from_exact_iter(items.iter().map(|i| i.as_ref()));
+        assert!(accumulator.is_ok());
+        assert_eq!(accumulator, expected);
+
+        let item = items[3];
+        let proof = accumulator.as_ref().unwrap().prove(&item).unwrap();
+        assert!(accumulator.unwrap().check(proof, &item));
+
+        assert_eq!(
+            MerkleAccumulator::<Keccak256>::from_exact_iter(std::iter::empty()),
+            Err(AccumulatorError::EmptyInput)
+        );
+
+        // Iterators reporting the wrong length are rejected rather than padded or truncated.
+        struct Misreported<I>(I, usize);
+        impl<I: Iterator> Iterator for Misreported<I> {
+            type Item = I::Item;
+
+            fn next(&mut self) -> Option<I::Item> {
+                self.0.next()
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                (self.1, Some(self.1))
+            }
+        }
+        impl<I: Iterator> ExactSizeIterator for Misreported<I> {
+        }
+
+        for len in [4, 6] {
+            let items = Misreported(items.iter().map(|i| i.as_ref()), len);
+            assert_eq!(
+                MerkleAccumulator::<Keccak256>::from_exact_iter(items),
+                Err(AccumulatorError::LengthMismatch)
+            );
+        }
+    }
+
+    #[test]
+    fn test_prove_duplicates_by_index() {
+        let item_a = 1usize.to_be_bytes();
+        let item_b = 2usize.to_be_bytes();
+        let item_x = 3usize.to_be_bytes();
+        let accumulator =
+            MerkleAccumulator::<Keccak256>::new(&[&item_a, &item_b, &item_a, &item_b, &item_a])
+                .unwrap();
+
+        assert_eq!(accumulator.leaf_index_of(&item_a), vec![0, 2, 4]);
+        assert_eq!(accumulator.leaf_index_of(&item_b), vec![1, 3]);
+        assert!(accumulator.leaf_index_of(&item_x).is_empty());
+
+        // Every copy has its own path, `prove` only ever finds the first one.
+        assert_eq!(accumulator.prove(&item_a).ok(), accumulator.prove_at(0));
+        for index in accumulator.leaf_index_of(&item_a) {
+            let proof = accumulator.prove_at(index).unwrap();
+            assert!(accumulator.check(proof, &item_a));
+        }
+        assert_ne!(accumulator.prove_at(0), accumulator.prove_at(4));
+
+        // Padding leaves past the items cannot be proven.
+        assert!(accumulator.prove_at(5).is_none());
+        assert!(accumulator.prove_at(8).is_none());
+
+        // Replacing a copy keeps the others reachable through the index.
+        let mut accumulator = accumulator;
+        accumulator.update_leaf(0, &item_x);
+        assert_eq!(accumulator.leaf_index_of(&item_a), vec![2, 4]);
+        assert_eq!(accumulator.prove(&item_a).ok(), accumulator.prove_at(2));
+        accumulator.update_leaf(2, &item_b);
+        accumulator.update_leaf(4, &item_b);
+        assert_eq!(
+            accumulator.prove(&item_a),
+            Err(AccumulatorError::ItemNotFound)
+        );
+        assert_eq!(accumulator.leaf_index_of(&item_b), vec![1, 2, 3, 4]);
+
+        // Only leaves can be proven, never interior nodes.
+        let interior = accumulator.nodes[2];
+        assert!(accumulator.find_leaf(&interior).is_none());
+    }
+
+    #[test]
+    fn test_unique_construction() {
+        let items: Vec<[u8; 8]> = (0..5usize).map(|i| i.to_be_bytes()).collect();
+        let mut items: Vec<&[u8]> = items.iter().map(|i| i.as_ref()).collect();
+        assert_eq!(
+            MerkleAccumulator::<Keccak256>::try_new_unique(&items),
+            MerkleAccumulator::<Keccak256>::new(&items)
+        );
+
+        items.push(items[1]);
+        items.push(items[3]);
+        assert_eq!(
+            MerkleAccumulator::<Keccak256>::try_new_unique(&items),
+            Err(AccumulatorError::DuplicateItem {
+                first:     1,
+                duplicate: 5,
+            })
+        );
+        assert_eq!(
+            MerkleAccumulator::<Keccak256>::try_new_unique(&[]),
+            Err(AccumulatorError::EmptyInput)
+        );
+    }
+
+    #[test]
+    fn test_canonical_construction() {
+        let items: Vec<[u8; 8]> = (0..5usize).map(|i| i.to_be_bytes()).collect();
+        let items: Vec<&[u8]> = items.iter().map(|i| i.as_ref()).collect();
+        let accumulator = MerkleAccumulator::<Keccak256>::new_canonical(&items).unwrap();
+
+        // The root is the same whatever the order of the items.
+        let mut reversed = items.clone();
+        reversed.reverse();
+        let mut rotated = items.clone();
+        rotated.rotate_left(2);
+        for permutation in [&reversed, &rotated] {
+            let ordered = MerkleAccumulator::<Keccak256>::new(permutation).unwrap();
+            assert_ne!(ordered.root, accumulator.root);
+            assert_eq!(
+                MerkleAccumulator::<Keccak256>::new_canonical(permutation).unwrap(),
+                accumulator
+            );
+        }
+
+        for item in &items {
+            let proof = accumulator.prove(item).unwrap();
+            assert!(accumulator.check(proof, item));
+        }
+        assert_eq!(
+            MerkleAccumulator::<Keccak256>::new_canonical(&[]),
+            Err(AccumulatorError::EmptyInput)
+        );
+    }
+
+    #[test]
+    fn test_arena_construction() {
+        let items: Vec<[u8; 8]> = (0..100usize).map(|i| i.to_be_bytes()).collect();
+        let items: Vec<&[u8]> = items.iter().map(|i| i.as_ref()).collect();
+        let mut arena = MerkleArena::<Keccak256>::with_capacity(items.len());
+        let nodes = arena.nodes.as_ptr();
+
+        // Every rebuild matches a freshly allocated tree and reuses the arena's storage.
+        for len in [100, 7, 64, 1] {
+            let accumulator = MerkleAccumulator::new_in(&mut arena, &items[..len]).unwrap();
+            assert_eq!(
+                accumulator,
+                MerkleAccumulator::<Keccak256>::new(&items[..len]).unwrap()
+            );
+            assert_eq!(accumulator.nodes.as_ptr(), nodes);
+            let proof = accumulator.prove(items[len - 1]).unwrap();
+            assert!(accumulator.check(proof, items[len - 1]));
+            arena.reclaim(accumulator);
+        }
+
+        assert_eq!(
+            MerkleAccumulator::new_in(&mut arena, &[]),
+            Err(AccumulatorError::EmptyInput)
+        );
+        assert_eq!(arena.nodes.as_ptr(), nodes);
+    }
+
+    #[test]
+    fn test_introspection() {
+        let items: Vec<[u8; 8]> = (0..5usize).map(|i| i.to_be_bytes()).collect();
+        let items: Vec<&[u8]> = items.iter().map(|i| i.as_ref()).collect();
+        let accumulator = MerkleAccumulator::<Keccak256>::new(&items).unwrap();
+
+        assert_eq!(accumulator.len(), 5);
+        assert_eq!(accumulator.depth(), Some(3));
+        assert_eq!(accumulator.prove(items[0]).unwrap().0.len(), 3);
+
+        let leaves: Vec<_> = items.iter().map(|i| hash_leaf::<Keccak256>(i)).collect();
+        assert!(accumulator.leaf_hashes().eq(leaves.iter()));
+        assert_eq!(accumulator.node_at(0, 0), Some(accumulator.root));
+        assert_eq!(accumulator.node_at(3, 4), Some(leaves[4]));
+        assert_eq!(accumulator.node_at(3, 5), Some(hash_null::<Keccak256>()));
+        assert_eq!(
+            accumulator.node_at(2, 0),
+            Some(hash_node::<Keccak256>(&leaves[0], &leaves[1]))
+        );
+        assert_eq!(accumulator.node_at(3, 8), None);
+        assert_eq!(accumulator.node_at(4, 0), None);
+
+        // Accumulators without their nodes only know their root.
+        let bytes = serde_json::to_vec(&accumulator).unwrap();
+        let root_only: MerkleAccumulator<Keccak256> = serde_json::from_slice(&bytes).unwrap();
+        assert!(root_only.is_empty());
+        assert_eq!(root_only.depth(), None);
+        assert_eq!(root_only.node_at(0, 0), None);
+    }
+
+    #[test]
+    fn test_domain_separation() {
+        let items: Vec<[u8; 8]> = (0..5usize).map(|i| i.to_be_bytes()).collect();
+        let items: Vec<&[u8]> = items.iter().map(|i| i.as_ref()).collect();
+        let plain = MerkleAccumulator::<Keccak256>::new(&items).unwrap();
+        let prices = MerkleAccumulator::<Keccak256>::new_with_domain(&items, b"prices").unwrap();
+        let stakes = MerkleAccumulator::<Keccak256>::new_with_domain(&items, b"stakes").unwrap();
+        assert_eq!(prices.domain(), b"prices");
+        assert_ne!(prices.root, plain.root);
+        assert_ne!(prices.root, stakes.root);
+        assert_eq!(
+            MerkleAccumulator::<Keccak256>::new_with_domain(&items, b"").unwrap(),
+            plain
+        );
+
+        // Proofs only verify in the domain they were made for.
+        let proof = prices.prove(items[2]).unwrap();
+        assert!(prices.check(proof.clone(), items[2]));
+        assert!(proof.verify_in_domain(&prices.root, b"prices", items[2]));
+        assert!(!proof.verify(&prices.root, items[2]));
+        assert!(!stakes.check(proof.clone(), items[2]));
+        let root = MerkleRoot::<Keccak256>::new(prices.root);
+        assert!(root.check_in_domain(proof, b"prices", items[2]));
+        assert!(plain.prove(items[2]).is_ok());
+
+        let multiproof = prices.prove_many(&items[1..3]).unwrap();
+        assert!(prices.check_many(multiproof.clone(), &items[1..3]));
+        assert!(!root.check_many(multiproof.clone(), &items[1..3]));
+        assert!(root.check_many_in_domain(multiproof, b"prices", &items[1..3]));
+
+        // Updates stay in the domain of the tree.
+        let mut updated = prices.clone();
+        let item_x = 100usize.to_be_bytes();
+        updated.update_leaf(1, &item_x);
+        updated.insert(&item_x).unwrap();
+        let mut expected = items.clone();
+        expected[1] = &item_x;
+        expected.push(&item_x);
+        assert_eq!(
+            updated,
+            MerkleAccumulator::new_with_domain(&expected, b"prices").unwrap()
+        );
+
+        // The domain is not serialized and is restored with `with_domain`.
+        let bytes = BorshSerialize::try_to_vec(&prices).unwrap();
+        let restored = MerkleAccumulator::<Keccak256>::try_from_slice(&bytes).unwrap();
+        assert!(restored.prove(items[2]).is_err());
+        assert_eq!(restored.with_domain(b"prices"), prices);
+    }
+
+    #[test]
+    fn test_prehashed_leaves() {
+        let items: Vec<[u8; 8]> = (0..5usize).map(|i| i.to_be_bytes()).collect();
+        let items: Vec<&[u8]> = items.iter().map(|i| i.as_ref()).collect();
+        let accumulator = MerkleAccumulator::<Keccak256>::new(&items).unwrap();
+        let root = MerkleRoot::<Keccak256>::new(accumulator.root);
+
+        // A leaf hashed once checks against its proof the same as the item would.
+        let leaf = accumulator.hash_item(items[2]);
+        assert_eq!(leaf, LeafHash::from_item(items[2]));
+        assert_eq!(leaf, LeafHash::new(hash_leaf::<Keccak256>(items[2])));
+        let proof = accumulator.prove(items[2]).unwrap();
+        assert!(proof.verify_hashed(&accumulator.root, &leaf));
+        assert!(root.check_hashed(proof.clone(), &leaf));
+        assert!(accumulator.check_hashed(proof.clone(), &leaf));
+        assert!(!accumulator.check_hashed(proof, &accumulator.hash_item(items[3])));
+
+        // Leaves are hashed in the domain of the tree.
+        let prices = MerkleAccumulator::<Keccak256>::new_with_domain(&items, b"prices").unwrap();
+        let leaf = prices.hash_item(items[2]);
+        assert_eq!(leaf, LeafHash::from_item_in_domain(b"prices", items[2]));
+        assert!(prices.check_hashed(prices.prove(items[2]).unwrap(), &leaf));
+        assert!(!accumulator.check_hashed(accumulator.prove(items[2]).unwrap(), &leaf));
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let items: Vec<[u8; 8]> = (0..5usize).map(|i| i.to_be_bytes()).collect();
+        let items: Vec<&[u8]> = items.iter().map(|i| i.as_ref()).collect();
+        let accumulator = MerkleAccumulator::<Keccak256>::new_with_domain(&items, b"pyth").unwrap();
+
+        let snapshot = accumulator.to_snapshot(SnapshotCompression::None).unwrap();
+        let restored = MerkleAccumulator::<Keccak256>::from_snapshot(&snapshot).unwrap();
+        assert_eq!(restored, accumulator);
+        let proof = restored.prove(items[3]).unwrap();
+        assert!(accumulator.check(proof, items[3]));
+
+        #[cfg(feature = "compression")]
+        {
+            let compressed = accumulator
+                .to_snapshot(SnapshotCompression::Zstd { level: 3 })
+                .unwrap();
+            assert_eq!(compressed[0], 1);
+            let restored = MerkleAccumulator::<Keccak256>::from_snapshot(&compressed).unwrap();
+            assert_eq!(restored, accumulator);
+
+            // A snapshot declaring a small tree that decompresses into far more data is cut off
+            // at the declared size instead of being decompressed in full.
+            let mut bomb = snapshot[1..37].to_vec();
+            bomb.resize(64 * 1024 * 1024, 0);
+            let bomb = [&[1], &zstd::bulk::compress(&bomb, 3).unwrap()[..]].concat();
+            assert!(bomb.len() < 64 * 1024);
+            assert!(MerkleAccumulator::<Keccak256>::from_snapshot(&bomb).is_err());
+
+            // A forged node count is rejected from the header alone, before anything past it is
+            // decompressed, however much output the count would allow.
+            let mut forged = snapshot[1..37].to_vec();
+            forged[32..36].copy_from_slice(&(1u32 << 31).to_le_bytes());
+            let forged = [&[1], &zstd::bulk::compress(&forged, 3).unwrap()[..]].concat();
+            let err = MerkleAccumulator::<Keccak256>::from_snapshot(&forged).unwrap_err();
+            assert!(err.to_string().contains("exceeding maximum"));
+
+            let err = MerkleAccumulator::<Keccak256>::from_snapshot_with_max_nodes(&compressed, 8)
+                .unwrap_err();
+            assert!(err.to_string().contains("exceeding maximum"));
+            assert_eq!(
+                MerkleAccumulator::<Keccak256>::from_snapshot_with_max_nodes(&compressed, 16)
+                    .unwrap(),
+                accumulator
+            );
+        }
+
+        // Uncompressed snapshots are held to the same limit.
+        let mut forged = snapshot.clone();
+        forged[33..37].copy_from_slice(&(1u32 << 31).to_le_bytes());
+        let err = MerkleAccumulator::<Keccak256>::from_snapshot(&forged).unwrap_err();
+        assert!(err.to_string().contains("exceeding maximum"));
+        assert!(
+            MerkleAccumulator::<Keccak256>::from_snapshot_with_max_nodes(&snapshot, 8).is_err()
+        );
+        forged[33..37].copy_from_slice(&3u32.to_le_bytes());
+        assert!(MerkleAccumulator::<Keccak256>::from_snapshot(&forged).is_err());
+
+        // Snapshots whose nodes do not hash to their root are rejected.
+        let mut tampered = snapshot.clone();
+        let last = tampered.len() - 12;
+        tampered[last] ^= 1;
+        assert!(MerkleAccumulator::<Keccak256>::from_snapshot(&tampered).is_err());
+        let mut tampered = snapshot.clone();
+        tampered[1] ^= 1;
+        assert!(MerkleAccumulator::<Keccak256>::from_snapshot(&tampered).is_err());
+
+        let mut unknown = snapshot;
+        unknown[0] = 2;
+        assert!(MerkleAccumulator::<Keccak256>::from_snapshot(&unknown).is_err());
+        assert!(MerkleAccumulator::<Keccak256>::from_snapshot(&[]).is_err());
+    }
+
+    #[test]
+    fn test_diff() {
+        let items: Vec<[u8; 8]> = (0..6usize).map(|i| i.to_be_bytes()).collect();
+        let items: Vec<&[u8]> = items.iter().map(|i| i.as_ref()).collect();
+        let old = MerkleAccumulator::<Keccak256>::new(&items[..5]).unwrap();
+        assert!(old.diff(&old).is_empty());
+        assert!(old.diff(&old).nodes.is_empty());
+
+        // Change leaf 1 and add leaf 5, which stays within the same depth.
+        let item_x = 100usize.to_be_bytes();
+        let mut new = old.clone();
+        new.update_leaf(1, &item_x);
+        new.insert(items[5]).unwrap();
+
+        let diff = old.diff(&new);
+        assert_eq!(
+            diff.changed,
+            vec![LeafChange {
+                index: 1,
+                old:   Some(hash_leaf::<Keccak256>(items[1])),
+                new:   Some(hash_leaf::<Keccak256>(&item_x)),
+            }]
+        );
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].index, 5);
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.nodes, vec![(0, 0), (1, 0), (1, 1), (2, 0), (2, 2)]);
+        for &(level, index) in &diff.nodes {
+            assert_ne!(old.node_at(level, index), new.node_at(level, index));
+        }
+
+        // The reverse diff removes the added leaf.
+        let diff = new.diff(&old);
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].new, None);
+
+        // Resizing the tree affects every interior node.
+        let small = MerkleAccumulator::<Keccak256>::new(&items[..2]).unwrap();
+        let diff = small.diff(&old);
+        assert_eq!(diff.added.len(), 3);
+        assert_eq!(diff.nodes.len(), 7);
+    }
+
+    #[test]
+    fn test_merkle_path_serialization() {
+        let items: Vec<[u8; 8]> = (0..5usize).map(|i| i.to_be_bytes()).collect();
+        let items: Vec<&[u8]> = items.iter().map(|i| i.as_ref()).collect();
+        let accumulator = MerkleAccumulator::<Keccak256>::new(&items).unwrap();
+        let proof = accumulator.prove(items[2]).unwrap();
+        assert!(proof.verify(&accumulator.root, items[2]));
+        assert!(!proof.verify(&accumulator.root, items[3]));
+
+        // Proofs survive both Borsh and serde round trips and still verify.
+        let bytes = BorshSerialize::try_to_vec(&proof).unwrap();
+        let borsh_proof = MerklePath::<Keccak256>::try_from_slice(&bytes).unwrap();
+        assert_eq!(borsh_proof, proof);
+
+        let json = serde_json::to_string(&proof).unwrap();
+        let json_proof: MerklePath<Keccak256> = serde_json::from_str(&json).unwrap();
+        assert_eq!(json_proof, proof);
+        assert!(json_proof.verify(&accumulator.root, items[2]));
+
+        // Oversized paths are rejected.
+        let oversized = MerklePath::<Keccak256>::new(vec![[0u8; 32]; MAX_PATH_LENGTH + 1]);
+        let bytes = BorshSerialize::try_to_vec(&oversized).unwrap();
+        assert!(MerklePath::<Keccak256>::try_from_slice(&bytes).is_err());
+        let json = serde_json::to_string(&oversized).unwrap();
+        assert!(serde_json::from_str::<MerklePath<Keccak256>>(&json).is_err());
+        assert!(MerklePath::<Keccak256>::try_from(Vec::from(oversized)).is_err());
+
+        // Proofs embedded in a wire payload convert back into the same path, including after a
+        // round trip through the payload encoding.
+        use crate::payload::v1::MerkleProof;
+        let wire = MerkleProof::try_from((&proof, items[2])).unwrap();
+        assert_eq!(wire, MerkleProof::new(&proof, items[2]));
+        assert_eq!(MerklePath::<Keccak256>::try_from(&wire), Ok(proof.clone()));
+        assert_eq!(wire.data(), items[2]);
+        let json = serde_json::to_string(&wire).unwrap();
+        let json_wire: MerkleProof<Keccak256> = serde_json::from_str(&json).unwrap();
+        assert_eq!(json_wire, wire);
+        assert_eq!(MerklePath::try_from(&json_wire), Ok(proof.clone()));
+        let bytes = bincode::serialize(&wire).unwrap();
+        let bincode_wire: MerkleProof<Keccak256> = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(bincode_wire, wire);
+
+        // Neither direction accepts a path longer than the limit.
+        let oversized = MerklePath::<Keccak256>::new(vec![[0u8; 32]; MAX_PATH_LENGTH + 1]);
+        assert_eq!(
+            MerkleProof::try_from((&oversized, items[2])),
+            Err(PathTooLong {
+                len: MAX_PATH_LENGTH + 1,
+            })
+        );
+        let unchecked = MerkleProof::new(&oversized, items[2]);
+        assert_eq!(
+            MerklePath::try_from(&unchecked),
+            Err(PathTooLong {
+                len: MAX_PATH_LENGTH + 1,
+            })
+        );
+        let json = serde_json::to_string(&unchecked).unwrap();
+        assert!(serde_json::from_str::<MerkleProof<Keccak256>>(&json).is_err());
+
+        let limit = MerklePath::<Keccak256>::new(vec![[0u8; 32]; MAX_PATH_LENGTH]);
+        let bytes = BorshSerialize::try_to_vec(&limit).unwrap();
+        assert_eq!(
+            MerklePath::<Keccak256>::try_from_slice(&bytes).unwrap(),
+            limit
+        );
+    }
+
+    #[test]
+    fn test_check_with_max_depth() {
+        let items: Vec<[u8; 8]> = (0..5usize).map(|i| i.to_be_bytes()).collect();
+        let items: Vec<&[u8]> = items.iter().map(|i| i.as_ref()).collect();
+        let accumulator = MerkleAccumulator::<Keccak256>::new(&items).unwrap();
+
+        // 5 items are stored in a tree of depth 3.
+        let proof = accumulator.prove(items[0]).unwrap();
+        assert!(accumulator.check_with_max_depth(proof.clone(), items[0], 3));
+        assert!(!accumulator.check_with_max_depth(proof.clone(), items[0], 2));
+
+        // Proofs longer than the tree are rejected by default.
+        let mut long_proof = proof.clone();
+        long_proof.0.push(Default::default());
+        assert!(!accumulator.check(long_proof, items[0]));
+
+        // Without the nodes only the global bound applies.
+        let root = MerkleRoot::<Keccak256>::new(accumulator.root);
+        assert!(root.check(proof, items[0]));
+        let absurd_proof = MerklePath::<Keccak256>::new(vec![[0u8; 32]; MAX_PATH_LENGTH + 1]);
+        assert!(!root.check(absurd_proof, items[0]));
+    }
+
+    #[test]
+    fn test_multiproof() {
+        let items: Vec<[u8; 8]> = (0..8usize).map(|i| i.to_be_bytes()).collect();
+        let items: Vec<&[u8]> = items.iter().map(|i| i.as_ref()).collect();
+        let accumulator = MerkleAccumulator::<Keccak256>::new(&items).unwrap();
+
+        // Proving two siblings plus one item from the other half of the tree only needs the
+        // hashes that can't be derived from the items: 1 sibling for item 5, and 1 node above
+        // the pair (0, 1) and (4, 5) each.
+        let proven = [items[5], items[0], items[1]];
+        let proof = accumulator.prove_many(&proven).unwrap();
+        assert_eq!(proof.indices(), &[5, 0, 1]);
+        assert_eq!(proof.hashes.len(), 3);
+        assert!(accumulator.check_many(proof.clone(), &proven));
+
+        // Items must line up with the proven indices.
+        assert!(!accumulator.check_many(proof.clone(), &[items[0], items[5], items[1]]));
+        assert!(!accumulator.check_many(proof.clone(), &proven[..2]));
+
+        // Corrupting or extending the hashes fails.
+        for i in 0..proof.hashes.len() {
+            let mut corrupted_proof = proof.clone();
+            corrupted_proof.hashes[i] = Default::default();
+            assert!(!accumulator.check_many(corrupted_proof, &proven));
+        }
+        let mut extended_proof = proof.clone();
+        extended_proof.hashes.push(Default::default());
+        assert!(!accumulator.check_many(extended_proof, &proven));
+
+        // Items not in the tree cannot be proven.
+        let item_x = 100usize.to_be_bytes();
+        assert!(accumulator.prove_many(&[items[0], &item_x]).is_none());
+        assert!(accumulator.prove_many(&[]).is_none());
+    }
+
+    #[test]
+    fn test_aggregated_proof() {
+        let slots: Vec<Vec<[u8; 8]>> = (0..3usize)
+            .map(|slot| {
+                (0..6usize)
+                    .map(|i| (slot * 100 + i).to_be_bytes())
+                    .collect()
+            })
+            .collect();
+        let slots: Vec<Vec<&[u8]>> = slots
+            .iter()
+            .map(|items| items.iter().map(|i| i.as_ref()).collect())
+            .collect();
+        let accumulators: Vec<MerkleAccumulator<Keccak256>> = slots
+            .iter()
+            .map(|items| MerkleAccumulator::new(items).unwrap())
+            .collect();
+
+        let proven: Vec<&[&[u8]]> = slots.iter().map(|items| &items[1..3]).collect();
+        let proof = AggregatedProof::prove(
+            &accumulators
+                .iter()
+                .zip(&proven)
+                .map(|(accumulator, items)| (accumulator, *items))
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+        assert_eq!(proof.proofs().len(), 3);
+
+        let batches: Vec<(_, &[&[u8]])> = accumulators
+            .iter()
+            .zip(&proven)
+            .map(|(accumulator, items)| (accumulator.root, *items))
+            .collect();
+        assert!(proof.clone().verify(&batches));
+
+        // Batches must line up with the roots they were proven against.
+        let mut swapped = batches.clone();
+        swapped.swap(0, 1);
+        assert!(!proof.clone().verify(&swapped));
+        assert!(!proof.clone().verify(&batches[..2]));
+
+        let mut wrong_items = batches.clone();
+        wrong_items[2].1 = &slots[2][2..4];
+        assert!(!proof.verify(&wrong_items));
+
+        // Items missing from their slot cannot be proven.
+        assert!(AggregatedProof::prove(&[
+            (&accumulators[0], &slots[0][..1]),
+            (&accumulators[1], &slots[0][..1]),
+        ])
+        .is_none());
+    }
+
+    #[test]
+    fn test_multiproof_serialization() {
+        let items: Vec<[u8; 8]> = (0..8usize).map(|i| i.to_be_bytes()).collect();
+        let items: Vec<&[u8]> = items.iter().map(|i| i.as_ref()).collect();
+        let accumulator = MerkleAccumulator::<Keccak256>::new(&items).unwrap();
+        let proven = [items[5], items[0], items[1]];
+        let proof = accumulator.prove_many(&proven).unwrap();
+
+        // Proofs survive both Borsh and serde round trips and still verify.
+        let bytes = BorshSerialize::try_to_vec(&proof).unwrap();
+        assert_eq!(&bytes[..9], &[3, 0, 0, 0, 3, 0, 0, 0, 5]);
+        assert_eq!(&bytes[17..21], &(proof.hashes.len() as u32).to_be_bytes());
+        let borsh_proof = MerkleMultiProof::<Keccak256>::try_from_slice(&bytes).unwrap();
+        assert_eq!(borsh_proof, proof);
+        assert!(accumulator.check_many(borsh_proof, &proven));
+
+        let json = serde_json::to_string(&proof).unwrap();
+        let json_proof: MerkleMultiProof<Keccak256> = serde_json::from_str(&json).unwrap();
+        assert_eq!(json_proof, proof);
+
+        // Truncated proofs are rejected wherever they are cut off.
+        for len in 0..bytes.len() {
+            assert!(MerkleMultiProof::<Keccak256>::try_from_slice(&bytes[..len]).is_err());
+        }
+
+        // As are proofs deeper than any valid path or with more hashes than their items need.
+        let deep = MerkleMultiProof::<Keccak256>::new(MAX_PATH_LENGTH as u8 + 1, vec![0], vec![]);
+        let bytes = BorshSerialize::try_to_vec(&deep).unwrap();
+        assert!(MerkleMultiProof::<Keccak256>::try_from_slice(&bytes).is_err());
+        let json = serde_json::to_string(&deep).unwrap();
+        assert!(serde_json::from_str::<MerkleMultiProof<Keccak256>>(&json).is_err());
+
+        let padded = MerkleMultiProof::<Keccak256>::new(3, vec![0], vec![[0u8; 32]; 4]);
+        let bytes = BorshSerialize::try_to_vec(&padded).unwrap();
+        assert!(MerkleMultiProof::<Keccak256>::try_from_slice(&bytes).is_err());
+        let json = serde_json::to_string(&padded).unwrap();
+        assert!(serde_json::from_str::<MerkleMultiProof<Keccak256>>(&json).is_err());
+
+        // Item counts are bounded before the indices are read, in both formats. Serde formats
+        // without field names, like bincode, are bounded the same way.
+        let wide = MerkleMultiProof::<Keccak256>::new(3, vec![0; MAX_MULTIPROOF_ITEMS + 1], vec![]);
+        let bytes = BorshSerialize::try_to_vec(&wide).unwrap();
+        let err = MerkleMultiProof::<Keccak256>::try_from_slice(&bytes).unwrap_err();
+        assert!(err.to_string().contains("exceeding maximum"));
+        let json = serde_json::to_string(&wide).unwrap();
+        assert!(serde_json::from_str::<MerkleMultiProof<Keccak256>>(&json).is_err());
+        let bytes = bincode::serialize(&wide).unwrap();
+        assert!(bincode::deserialize::<MerkleMultiProof<Keccak256>>(&bytes).is_err());
+        let bytes = bincode::serialize(&proof).unwrap();
+        assert_eq!(
+            bincode::deserialize::<MerkleMultiProof<Keccak256>>(&bytes).unwrap(),
+            proof
+        );
+        let reordered = r#"{"indices":[0],"depth":3,"hashes":[]}"#;
+        assert!(serde_json::from_str::<MerkleMultiProof<Keccak256>>(reordered).is_err());
+
+        // Aggregated proofs carry their multiproofs the same way.
+        let aggregated = AggregatedProof::new(vec![proof.clone(), proof]);
+        let bytes = BorshSerialize::try_to_vec(&aggregated).unwrap();
+        assert_eq!(
+            AggregatedProof::<Keccak256>::try_from_slice(&bytes).unwrap(),
+            aggregated
+        );
+        assert!(AggregatedProof::<Keccak256>::try_from_slice(&bytes[..bytes.len() - 1]).is_err());
+        let json = serde_json::to_string(&aggregated).unwrap();
+        assert_eq!(
+            serde_json::from_str::<AggregatedProof<Keccak256>>(&json).unwrap(),
+            aggregated
+        );
+    }
+
+    #[test]
+    fn test_absence_proofs() {
+        let item_a = 10usize.to_be_bytes();
+        let item_b = 20usize.to_be_bytes();
+        let item_c = 30usize.to_be_bytes();
+        let accumulator =
+            SortedMerkleAccumulator::<Keccak256>::new(&[&item_c, &item_a, &item_b, &item_a])
+                .unwrap();
+
+        // Members can be proven but not proven absent.
+        for item in [item_a, item_b, item_c] {
+            let proof = accumulator.prove(&item).unwrap();
+            assert!(accumulator.accumulator.check(proof, &item));
+            assert!(accumulator.prove_absence(&item).is_none());
+        }
+
+        // Items before, between and after the members can be proven absent.
+        for item in [0usize, 15, 25, 40].map(usize::to_be_bytes) {
+            assert!(accumulator.prove(&item).is_none());
+            let proof = accumulator.prove_absence(&item).unwrap();
+            assert!(accumulator.check_absence(proof, &item));
+        }
+
+        // A gap proof cannot be reused for an item outside of its bounds.
+        let item_x = 15usize.to_be_bytes();
+        let proof = accumulator.prove_absence(&item_x).unwrap();
+        assert_eq!(proof.low.as_deref(), Some(item_a.as_ref()));
+        assert_eq!(proof.high.as_deref(), Some(item_b.as_ref()));
+        assert!(!accumulator.check_absence(proof.clone(), &item_a));
+        assert!(!accumulator.check_absence(proof.clone(), &25usize.to_be_bytes()));
+
+        // Widening the bounds to cover a member invalidates the proof.
+        let mut widened_proof = proof;
+        widened_proof.high = Some(item_c.to_vec());
+        assert!(!accumulator.check_absence(widened_proof, &item_b));
+
+        assert_eq!(
+            SortedMerkleAccumulator::<Keccak256>::new(&[]),
+            Err(AccumulatorError::EmptyInput)
+        );
+    }
+
+    #[test]
+    fn test_merkle_root() {
+        let items: Vec<[u8; 8]> = (0..5usize).map(|i| i.to_be_bytes()).collect();
+        let items: Vec<&[u8]> = items.iter().map(|i| i.as_ref()).collect();
+        let accumulator = MerkleAccumulator::<Keccak256>::new(&items).unwrap();
+
+        // Proofs verify against the root alone, without any of the tree nodes.
+        let root = MerkleRoot::<Keccak256>::new(accumulator.root);
+        for item in &items {
+            let proof = accumulator.prove(item).unwrap();
+            assert!(root.check(proof, item));
+        }
+
+        let proof = accumulator.prove(items[0]).unwrap();
+        assert!(!root.check(proof.clone(), &100usize.to_be_bytes()));
+        assert!(!MerkleRoot::<Keccak256>::default().check(proof, items[0]));
+
+        let sorted = SortedMerkleAccumulator::<Keccak256>::new(&items).unwrap();
+        let item_x = 100usize.to_be_bytes();
+        let proof = sorted.prove_absence(&item_x).unwrap();
+        assert!(MerkleRoot::<Keccak256>::new(sorted.root()).check_absence(proof, &item_x));
+    }
+
     proptest! {
         // Use proptest to generate arbitrary Merkle trees as part of our fuzzing strategy. This
         // will help us identify any edge cases or unexpected behavior in the implementation.
@@ -435,6 +2763,29 @@ mod test {
             }
         }
 
+        // Building a tree in one go, which is parallel with the `parallel` feature, produces the
+        // same tree as the strictly serial incremental construction.
+        #[test]
+        fn test_merkle_construction_matches_serial(v in any::<MerkleAccumulatorDataWrapper>()) {
+            let mut serial = MerkleAccumulator::<Keccak256>::default();
+            for d in &v.data {
+                serial.insert(d).unwrap();
+            }
+            assert_eq!(serial.root, v.accumulator.root);
+            assert_eq!(serial, v.accumulator);
+        }
+
+        // A multiproof over any subset of the items verifies, and is never larger than the
+        // independent paths it replaces.
+        #[test]
+        fn test_merkle_multiproof(v in any::<MerkleAccumulatorDataWrapper>(), step in 1..4usize) {
+            let items: Vec<&[u8]> = v.data.iter().step_by(step).map(|d| d.as_ref()).collect();
+            let proof = v.accumulator.prove_many(&items).unwrap();
+            let paths: usize = items.iter().map(|i| v.accumulator.prove(i).unwrap().0.len()).sum();
+            assert!(proof.hashes.len() <= paths);
+            assert!(v.accumulator.check_many(proof, &items));
+        }
+
         // Use proptest to generate arbitrary proofs for Merkle Trees trying to find a proof that
         // passes which should not.
         #[test]
@@ -452,5 +2803,18 @@ mod test {
                 assert!(!v.accumulator.check(p.clone(), &d));
             }
         }
+
+        // Generated paths are always within the limits the crate itself enforces.
+        #[test]
+        fn test_arbitrary_merkle_path_is_bounded(
+            p in any::<MerklePath<Keccak256>>(),
+            q in any_with::<MerklePath<Keccak256>>(1000),
+        ) {
+            for path in [p, q] {
+                prop_assert!((1..=MAX_PATH_LENGTH).contains(&path.hashes().len()));
+                let bytes = BorshSerialize::try_to_vec(&path).unwrap();
+                prop_assert_eq!(MerklePath::<Keccak256>::try_from_slice(&bytes).unwrap(), path);
+            }
+        }
     }
 }
@@ -0,0 +1,208 @@
+//! Hashing primitives of the Merkle trees built by this crate.
+//!
+//! These define the exact bytes every leaf and interior node commits to, so verifiers written in
+//! other languages, such as target chain contracts, can reproduce roots and check proofs without
+//! depending on this crate. Their output is part of the wire format: the prefixes and layouts here
+//! are stable and any change to them is a breaking change to every deployed verifier. The test
+//! vectors at the bottom of this file pin the Keccak256 output of each primitive.
+//!
+//! We need to discern between leaf and intermediate nodes to prevent trivial second pre-image
+//! attacks. If we did not do this it would be possible for an attacker to intentionally create
+//! non-leaf nodes that have the same hash as a leaf node, and then use that to prove the existence
+//! of a leaf node that does not exist.
+//!
+//! See:
+//!
+//! - <https://flawed.net.nz/2018/02/21/attacking-merkle-trees-with-a-second-preimage-attack>
+//! - <https://en.wikipedia.org/wiki/Merkle_tree#Second_preimage_attack>
+//!
+//! NOTE: We use a NULL prefix for leaf nodes to distinguish them from the empty message (""),
+//! while there is no path that allows empty messages this is a safety measure to prevent future
+//! vulnerabilities being introduced.
+
+use {
+    crate::hashers::{
+        HashStream,
+        Hasher,
+        StreamingHasher,
+    },
+    alloc::{
+        vec,
+        vec::Vec,
+    },
+};
+
+/// Prefix of a leaf: `H(0x00 || item)`.
+pub const LEAF_PREFIX: &[u8] = &[0];
+
+/// Prefix of an interior node: `H(0x01 || min(l, r) || max(l, r))`.
+pub const NODE_PREFIX: &[u8] = &[1];
+
+/// Prefix of the padding leaves of a tree that is not full: `H(0x02)`.
+pub const NULL_PREFIX: &[u8] = &[2];
+
+/// Prefix of the gap leaves of a sorted tree, see `hash_gap`.
+pub const GAP_PREFIX: &[u8] = &[3];
+
+/// Prefix of a leaf in a non-empty domain, see `hash_leaf_in_domain`.
+pub const DOMAIN_LEAF_PREFIX: &[u8] = &[4];
+
+/// Hash of a leaf holding `leaf`.
+pub fn hash_leaf<H: Hasher>(leaf: &[u8]) -> H::Hash {
+    H::hashv(&[LEAF_PREFIX, leaf])
+}
+
+/// Hashes of leaves holding each of `leaves`, in order.
+///
+/// The same as `hash_leaf` on each item, but lets the hasher hash several leaves at once, see
+/// `Hasher::hashv_many`.
+pub fn hash_leaves<H: Hasher>(leaves: &[&[u8]]) -> Vec<H::Hash> {
+    H::hashv_many(LEAF_PREFIX, leaves)
+}
+
+/// Hash of a leaf holding the concatenation of `parts`.
+///
+/// The parts are streamed into the hasher one at a time, so a large leaf never has to be
+/// assembled in memory. The result is the same as `hash_leaf` over the concatenated parts.
+pub fn hash_leaf_parts<H: StreamingHasher>(
+    parts: impl IntoIterator<Item = impl AsRef<[u8]>>,
+) -> H::Hash {
+    let mut stream = H::stream();
+    stream.update(LEAF_PREFIX);
+    parts
+        .into_iter()
+        .for_each(|part| stream.update(part.as_ref()));
+    stream.finalize()
+}
+
+/// Hash of a leaf holding `leaf` in a tree built for a specific application.
+///
+/// The domain is prefixed with its length as a big-endian u32 so the split between domain and
+/// item is unambiguous, and uses its own prefix so it can never collide with a leaf hashed without
+/// a domain. The empty domain is the plain leaf hash so existing trees keep their roots.
+pub fn hash_leaf_in_domain<H: Hasher>(domain: &[u8], leaf: &[u8]) -> H::Hash {
+    if domain.is_empty() {
+        return hash_leaf::<H>(leaf);
+    }
+
+    H::hashv(&[
+        DOMAIN_LEAF_PREFIX,
+        &(domain.len() as u32).to_be_bytes(),
+        domain,
+        leaf,
+    ])
+}
+
+/// Hash of the interior node with children `l` and `r`.
+///
+/// The children are sorted by their bytes before hashing, so a proof does not need to encode
+/// whether each sibling is on the left or the right.
+pub fn hash_node<H: Hasher>(l: &H::Hash, r: &H::Hash) -> H::Hash {
+    H::hashv(&[
+        NODE_PREFIX,
+        (if l <= r { l } else { r }).as_ref(),
+        (if l <= r { r } else { l }).as_ref(),
+    ])
+}
+
+/// Hash of a padding leaf.
+pub fn hash_null<H: Hasher>() -> H::Hash {
+    H::hashv(&[NULL_PREFIX])
+}
+
+/// Hash of a gap leaf, which commits to two adjacent items of a sorted tree.
+///
+/// `None` stands for the open end before the first and after the last item. A missing bound is
+/// encoded as `0x00` and a present one as `0x01 || len(item) as big-endian u32 || item`, so that
+/// the split between `low` and `high` is unambiguous.
+pub fn hash_gap<H: Hasher>(low: Option<&[u8]>, high: Option<&[u8]>) -> H::Hash {
+    fn bound(item: Option<&[u8]>) -> Vec<u8> {
+        match item {
+            None => vec![0],
+            Some(item) => [&[1], &(item.len() as u32).to_be_bytes()[..], item].concat(),
+        }
+    }
+
+    H::hashv(&[GAP_PREFIX, &bound(low), &bound(high)])
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::hashers::{
+            keccak256::Keccak256,
+            keccak256_160::Keccak160,
+            sha256::Sha256,
+        },
+    };
+
+    #[test]
+    fn test_known_answers() {
+        let a = hash_leaf::<Keccak256>(b"a");
+        let b = hash_leaf::<Keccak256>(b"b");
+        for (hash, expected) in [
+            (
+                hash_leaf::<Keccak256>(b""),
+                "bc36789e7a1e281436464229828f817d6612f7b477d66591ff96a9e064bcc98a",
+            ),
+            (
+                a,
+                "9722201502e620d70d78ee63045f3493812c206b988cbbe76c28918a7364fdbd",
+            ),
+            (
+                b,
+                "e99905ac9f9583a5737a07d20a7129343f486f5f549b42c05192046188ef5f66",
+            ),
+            (
+                hash_leaf_in_domain::<Keccak256>(b"pyth", b"a"),
+                "c3b746f35d5422e0be00aa851c01a4e15fcde9142d367228abd43e97510a6cb4",
+            ),
+            (
+                hash_node::<Keccak256>(&a, &b),
+                "00d25e3ecfd5a8430c58b5562d4a00f53ce3e76001e3683df8496c541fecb9da",
+            ),
+            (
+                hash_null::<Keccak256>(),
+                "f2ee15ea639b73fa3db9b34a245bdfa015c260c598b211bf05a1ecc4b3e3b4f2",
+            ),
+            (
+                hash_gap::<Keccak256>(None, None),
+                "c9ed189e2c9c66e239663cf9dfe9e9f245d47aad578a1a0b6c151cd2503af0bd",
+            ),
+            (
+                hash_gap::<Keccak256>(Some(b"a"), Some(b"b")),
+                "fad7381b6bb740a94b2d41e8a04821ff13d352772165887b585309e4955fd6a7",
+            ),
+        ] {
+            assert_eq!(hex::encode(hash), expected);
+        }
+
+        assert_eq!(
+            hash_node::<Keccak256>(&b, &a),
+            hash_node::<Keccak256>(&a, &b)
+        );
+        assert_eq!(hash_leaf_in_domain::<Keccak256>(b"", b"a"), a);
+    }
+
+    #[test]
+    fn test_streamed_leaves() {
+        let parts: [&[u8]; 3] = [b"hello", b"", b"world"];
+        assert_eq!(
+            hash_leaf_parts::<Keccak256>(parts),
+            hash_leaf::<Keccak256>(b"helloworld")
+        );
+        assert_eq!(
+            hash_leaf_parts::<Keccak160>(parts),
+            hash_leaf::<Keccak160>(b"helloworld")
+        );
+        assert_eq!(
+            hash_leaf_parts::<Sha256>(parts),
+            hash_leaf::<Sha256>(b"helloworld")
+        );
+        assert_eq!(
+            hash_leaf_parts::<Keccak256>(core::iter::empty::<&[u8]>()),
+            hash_leaf::<Keccak256>(b"")
+        );
+    }
+}
@@ -0,0 +1,366 @@
+//! A MerkleTree based Accumulator whose nodes are stored in a memory-mapped file.
+
+use {
+    crate::{
+        accumulators::{
+            merkle::{
+                hash::{
+                    hash_leaf_in_domain,
+                    hash_node,
+                    hash_null,
+                },
+                MerkleAccumulator,
+                MerklePath,
+            },
+            AccumulatorError,
+        },
+        hashers::{
+            keccak256::Keccak256,
+            Hasher,
+        },
+    },
+    memmap2::MmapMut,
+    std::{
+        fs::{
+            File,
+            OpenOptions,
+        },
+        io::{
+            Error,
+            ErrorKind::InvalidData,
+            Result,
+        },
+        marker::PhantomData,
+        ops::Range,
+        path::Path,
+    },
+};
+
+const MAGIC: &[u8; 8] = b"PYTHMRKL";
+const HEADER_SIZE: usize = 24;
+
+/// A MmapMerkleAccumulator keeps the nodes of a MerkleAccumulator in a memory-mapped file.
+///
+/// Services that keep many historical trees around, each with hundreds of thousands of items, can
+/// leave the nodes on disk and let the OS page in only the parts of the tree touched by a proof.
+/// Leaves can be updated in place, changes are only written back to the file when `flush` is
+/// called and only the range of nodes that changed since the last flush is synced.
+///
+/// The domain of a tree built with `MerkleAccumulator::new_with_domain` is stored with its nodes,
+/// so updated leaves are hashed in the same domain and `to_accumulator` restores it.
+///
+/// Layout:
+///
+/// ```text
+/// 8 bytes:       magic number
+/// 8 bytes:       number of nodes, big-endian
+/// 8 bytes:       length of the domain, big-endian
+/// N bytes each: nodes, in the same order as `MerkleAccumulator::nodes`
+/// M bytes:       domain
+/// ```
+#[derive(Debug)]
+pub struct MmapMerkleAccumulator<H: Hasher = Keccak256> {
+    mmap:     MmapMut,
+    capacity: usize,
+    domain:   Vec<u8>,
+    dirty:    Option<Range<usize>>,
+    phantom:  PhantomData<H>,
+}
+
+impl<H: Hasher> MmapMerkleAccumulator<H> {
+    /// Write the nodes and domain of an accumulator to a new file at `path` and map it.
+    pub fn create(path: impl AsRef<Path>, accumulator: &MerkleAccumulator<H>) -> Result<Self> {
+        let count = accumulator.nodes.len();
+        Self::check_node_count(count)?;
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(path)?;
+        let domain = accumulator.domain();
+        file.set_len((HEADER_SIZE + count * H::hash_size() + domain.len()) as u64)?;
+
+        // SAFETY: The file was just created by us, nothing else can be modifying it.
+        let mut mmap = unsafe { MmapMut::map_mut(&file)? };
+        mmap[..8].copy_from_slice(MAGIC);
+        mmap[8..16].copy_from_slice(&(count as u64).to_be_bytes());
+        mmap[16..HEADER_SIZE].copy_from_slice(&(domain.len() as u64).to_be_bytes());
+        let (nodes, domain_bytes) = mmap[HEADER_SIZE..].split_at_mut(count * H::hash_size());
+        for (node, chunk) in accumulator
+            .nodes
+            .iter()
+            .zip(nodes.chunks_exact_mut(H::hash_size()))
+        {
+            chunk.copy_from_slice(node.as_ref());
+        }
+        domain_bytes.copy_from_slice(domain);
+        mmap.flush()?;
+
+        Ok(Self {
+            mmap,
+            capacity: count / 2,
+            domain: domain.to_vec(),
+            dirty: None,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Map an existing file written by `create`.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified by anything else while it is mapped, see `MmapMut::map_mut`.
+    pub unsafe fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let file: File = OpenOptions::new().read(true).write(true).open(path)?;
+        let mmap = MmapMut::map_mut(&file)?;
+        if mmap.len() < HEADER_SIZE || &mmap[..8] != MAGIC {
+            return Err(Error::new(InvalidData, "Not a Merkle accumulator file"));
+        }
+
+        let read_u64 = |range: Range<usize>| {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&mmap[range]);
+            u64::from_be_bytes(bytes) as usize
+        };
+        let count = read_u64(8..16);
+        let domain_len = read_u64(16..HEADER_SIZE);
+        Self::check_node_count(count)?;
+        let nodes_end = count
+            .checked_mul(H::hash_size())
+            .and_then(|len| len.checked_add(HEADER_SIZE));
+        if nodes_end.and_then(|end| end.checked_add(domain_len)) != Some(mmap.len()) {
+            return Err(Error::new(
+                InvalidData,
+                "File size does not match node count and domain length",
+            ));
+        }
+
+        let domain = mmap[mmap.len() - domain_len..].to_vec();
+        Ok(Self {
+            mmap,
+            capacity: count / 2,
+            domain,
+            dirty: None,
+            phantom: PhantomData,
+        })
+    }
+
+    pub fn root(&self) -> H::Hash {
+        self.node(1)
+    }
+
+    /// Number of items in the tree.
+    ///
+    /// Items are stored contiguously from the left, so the end of the items is binary searched
+    /// for and only a logarithmic number of leaves are paged in.
+    pub fn len(&self) -> usize {
+        let null = hash_null::<H>();
+        let capacity = self.capacity();
+        let (mut low, mut high) = (0, capacity);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.node(capacity + mid) == null {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        low
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Domain mixed into the leaves of the tree, see `MerkleAccumulator::new_with_domain`.
+    pub fn domain(&self) -> &[u8] {
+        &self.domain
+    }
+
+    /// Prove an item is a member of the tree. Leaves are not indexed so this scans the leaves.
+    pub fn prove(&self, item: &[u8]) -> core::result::Result<MerklePath<H>, AccumulatorError> {
+        let leaf = hash_leaf_in_domain::<H>(&self.domain, item);
+        (0..self.len())
+            .find(|i| self.node(self.capacity() + i) == leaf)
+            .and_then(|index| self.prove_at(index))
+            .ok_or(AccumulatorError::ItemNotFound)
+    }
+
+    /// Prove the item at leaf `index` is a member of the tree.
+    pub fn prove_at(&self, index: usize) -> Option<MerklePath<H>> {
+        if index >= self.len() {
+            return None;
+        }
+
+        let mut id = self.capacity() + index;
+        let mut path = Vec::new();
+        while id > 1 {
+            path.push(self.node(id ^ 1));
+            id /= 2;
+        }
+        Some(MerklePath::new(path))
+    }
+
+    /// Replace the item at leaf `index`, returning `false` if there is no item at that index.
+    /// The change is visible immediately but only persisted by `flush`.
+    pub fn update_leaf(&mut self, index: usize, item: &[u8]) -> bool {
+        if index >= self.len() {
+            return false;
+        }
+
+        let mut id = self.capacity() + index;
+        self.set_node(id, hash_leaf_in_domain::<H>(&self.domain, item));
+        while id > 1 {
+            id /= 2;
+            let node = hash_node::<H>(&self.node(2 * id), &self.node(2 * id + 1));
+            self.set_node(id, node);
+        }
+        true
+    }
+
+    /// Write back the nodes changed since the last flush.
+    pub fn flush(&mut self) -> Result<()> {
+        if let Some(dirty) = self.dirty.take() {
+            self.mmap.flush_range(dirty.start, dirty.len())?;
+        }
+        Ok(())
+    }
+
+    /// Load the whole tree into memory.
+    pub fn to_accumulator(&self) -> MerkleAccumulator<H> {
+        let nodes = (0..2 * self.capacity()).map(|id| self.node(id)).collect();
+        MerkleAccumulator::from_nodes(nodes).with_domain(&self.domain)
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// A tree has a power of two number of nodes, and at least a root and one leaf.
+    fn check_node_count(count: usize) -> Result<()> {
+        if count < 2 || !count.is_power_of_two() {
+            return Err(Error::new(
+                InvalidData,
+                format!("Invalid node count {count}"),
+            ));
+        }
+        Ok(())
+    }
+
+    fn node(&self, id: usize) -> H::Hash {
+        // Offsets always span exactly one hash so the conversion cannot fail.
+        H::Hash::try_from(&self.mmap[Self::offset(id)]).unwrap_or_default()
+    }
+
+    fn set_node(&mut self, id: usize, node: H::Hash) {
+        let range = Self::offset(id);
+        self.mmap[range.clone()].copy_from_slice(node.as_ref());
+        self.dirty = Some(match self.dirty.take() {
+            Some(dirty) => dirty.start.min(range.start)..dirty.end.max(range.end),
+            None => range,
+        });
+    }
+
+    fn offset(id: usize) -> Range<usize> {
+        let start = HEADER_SIZE + id * H::hash_size();
+        start..start + H::hash_size()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::accumulators::Accumulator,
+    };
+
+    #[test]
+    fn test_mmap_accumulator() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("accumulator");
+        let items: Vec<[u8; 8]> = (0..5usize).map(|i| i.to_be_bytes()).collect();
+        let items: Vec<&[u8]> = items.iter().map(|i| i.as_ref()).collect();
+        let mut accumulator = MerkleAccumulator::<Keccak256>::new(&items).unwrap();
+
+        let mut mapped = MmapMerkleAccumulator::create(&path, &accumulator).unwrap();
+        assert!(MmapMerkleAccumulator::create(&path, &accumulator).is_err());
+        assert_eq!(mapped.root(), accumulator.root);
+        assert_eq!(mapped.len(), 5);
+        assert_eq!(mapped.to_accumulator(), accumulator);
+        for (index, item) in items.iter().enumerate() {
+            assert_eq!(mapped.prove(item), accumulator.prove(item));
+            assert_eq!(mapped.prove_at(index), accumulator.prove_at(index));
+        }
+        assert!(mapped.prove_at(5).is_none());
+        assert_eq!(mapped.prove(b"x"), Err(AccumulatorError::ItemNotFound));
+
+        // Updates are reflected in the file once flushed.
+        let item_x = 100usize.to_be_bytes();
+        assert!(mapped.update_leaf(3, &item_x));
+        assert!(!mapped.update_leaf(5, &item_x));
+        mapped.flush().unwrap();
+        drop(mapped);
+
+        accumulator.update_leaf(3, &item_x);
+        let reopened = unsafe { MmapMerkleAccumulator::<Keccak256>::open(&path).unwrap() };
+        assert_eq!(reopened.to_accumulator(), accumulator);
+        let proof = reopened.prove(&item_x).unwrap();
+        assert!(accumulator.check(proof, &item_x));
+
+        // Truncated files are rejected.
+        let file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(HEADER_SIZE as u64 + 3 * Keccak256::hash_size() as u64)
+            .unwrap();
+        assert!(unsafe { MmapMerkleAccumulator::<Keccak256>::open(&path) }.is_err());
+
+        // Accumulators without a full tree of nodes are rejected up front.
+        let mut root_only = accumulator.clone();
+        root_only.nodes.clear();
+        let path = dir.path().join("root_only");
+        assert!(MmapMerkleAccumulator::create(&path, &root_only).is_err());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_mmap_domain() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("accumulator");
+        let items: Vec<[u8; 8]> = (0..5usize).map(|i| i.to_be_bytes()).collect();
+        let items: Vec<&[u8]> = items.iter().map(|i| i.as_ref()).collect();
+        let mut accumulator =
+            MerkleAccumulator::<Keccak256>::new_with_domain(&items, b"prices").unwrap();
+
+        // Updates are hashed in the domain of the tree, which survives reopening the file.
+        let mut mapped = MmapMerkleAccumulator::create(&path, &accumulator).unwrap();
+        assert_eq!(mapped.domain(), b"prices");
+        assert_eq!(mapped.prove(items[1]), accumulator.prove(items[1]));
+        let item_x = 100usize.to_be_bytes();
+        assert!(mapped.update_leaf(1, &item_x));
+        accumulator.update_leaf(1, &item_x);
+        assert_eq!(mapped.root(), accumulator.root);
+        mapped.flush().unwrap();
+        drop(mapped);
+
+        let reopened = unsafe { MmapMerkleAccumulator::<Keccak256>::open(&path).unwrap() };
+        assert_eq!(reopened.domain(), b"prices");
+        assert_eq!(reopened.to_accumulator(), accumulator);
+        let proof = reopened.prove(&item_x).unwrap();
+        assert!(accumulator.check(proof, &item_x));
+    }
+
+    #[test]
+    fn test_mmap_len() {
+        let dir = tempfile::tempdir().unwrap();
+        for len in 1..=9usize {
+            let items: Vec<[u8; 8]> = (0..len).map(|i| i.to_be_bytes()).collect();
+            let items: Vec<&[u8]> = items.iter().map(|i| i.as_ref()).collect();
+            let accumulator = MerkleAccumulator::<Keccak256>::new(&items).unwrap();
+
+            let path = dir.path().join(len.to_string());
+            let mapped = MmapMerkleAccumulator::create(&path, &accumulator).unwrap();
+            assert_eq!(mapped.len(), len);
+            assert_eq!(mapped.len(), accumulator.len());
+        }
+    }
+}
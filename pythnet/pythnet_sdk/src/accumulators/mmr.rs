@@ -0,0 +1,294 @@
+//! A Merkle Mountain Range based Accumulator.
+
+use {
+    crate::{
+        accumulators::{
+            merkle::hash::{
+                hash_leaf,
+                hash_node,
+            },
+            Accumulator,
+            AccumulatorError,
+        },
+        hashers::{
+            keccak256::Keccak256,
+            Hasher,
+        },
+    },
+    alloc::vec::Vec,
+    serde::Serialize,
+};
+
+/// An MmrProof proves membership of an item in an MmrAccumulator of a given size.
+///
+/// The proof consists of the path from the item to the peak of the mountain containing it, and
+/// the remaining peaks that are bagged together with that peak into the root. As the range only
+/// ever grows, a proof made against an older size stays valid against the root of that size.
+#[derive(Clone, Default, Debug, PartialEq, Eq, Serialize)]
+pub struct MmrProof<H: Hasher> {
+    pub size:       u64,
+    pub peak_index: u8,
+    pub path:       Vec<H::Hash>,
+    pub peaks:      Vec<H::Hash>,
+}
+
+impl<H: Hasher> MmrProof<H> {
+    /// Verify an item is a member of an MmrAccumulator with the given root.
+    pub fn verify(&self, root: &H::Hash, item: &[u8]) -> bool {
+        // The size determines the shape of the range, so both the number of peaks and the height
+        // of the mountain containing the item are fixed by it.
+        let heights = peak_heights(self.size);
+        let peak_index = self.peak_index as usize;
+        if self.peaks.len() + 1 != heights.len()
+            || heights.get(peak_index) != Some(&(self.path.len() as u32))
+        {
+            return false;
+        }
+
+        let mut peak = hash_leaf::<H>(item);
+        for hash in &self.path {
+            peak = hash_node::<H>(&peak, hash);
+        }
+
+        let mut peaks = self.peaks.clone();
+        peaks.insert(peak_index, peak);
+        bag_peaks::<H>(&peaks).as_ref() == Some(root)
+    }
+}
+
+/// Heights of the mountains of a range over `size` leaves, from left to right.
+fn peak_heights(size: u64) -> Vec<u32> {
+    (0..u64::BITS)
+        .rev()
+        .filter(|h| size & (1 << h) != 0)
+        .collect()
+}
+
+/// Bag the peaks into a single root, folding them from right to left.
+fn bag_peaks<H: Hasher>(peaks: &[H::Hash]) -> Option<H::Hash> {
+    peaks
+        .iter()
+        .rev()
+        .copied()
+        .reduce(|bag, peak| hash_node::<H>(&peak, &bag))
+}
+
+/// A MmrAccumulator maintains a Merkle Mountain Range.
+///
+/// A Merkle Mountain Range is an append-only list of perfect binary Merkle trees, one for each set
+/// bit in the number of leaves. Appending only ever adds nodes, so every historical root and proof
+/// can be reproduced from the current structure. This makes it suitable for committing to the
+/// full history of accumulator roots rather than a single slot.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MmrAccumulator<H: Hasher = Keccak256> {
+    // `levels[0]` holds the leaf hashes and `levels[h][i]` is the parent of `levels[h - 1][2i]`
+    // and `levels[h - 1][2i + 1]`, created as soon as both children exist.
+    levels: Vec<Vec<H::Hash>>,
+}
+
+impl<'a, H: Hasher + 'a> Accumulator<'a> for MmrAccumulator<H> {
+    type Proof = MmrProof<H>;
+
+    fn from_set(items: impl Iterator<Item = &'a [u8]>) -> Result<Self, AccumulatorError> {
+        let mut accumulator = Self::default();
+        for item in items {
+            accumulator.append(item);
+        }
+        match accumulator.is_empty() {
+            true => Err(AccumulatorError::EmptyInput),
+            false => Ok(accumulator),
+        }
+    }
+
+    fn prove(&'a self, item: &[u8]) -> Result<Self::Proof, AccumulatorError> {
+        let item = hash_leaf::<H>(item);
+        self.levels
+            .first()
+            .and_then(|leaves| leaves.iter().position(|i| i == &item))
+            .and_then(|index| self.prove_at(index as u64, self.len()))
+            .ok_or(AccumulatorError::ItemNotFound)
+    }
+
+    // Proofs are checked against the root of the size they were made for, so proofs generated
+    // before later appends remain valid.
+    fn check(&'a self, proof: Self::Proof, item: &[u8]) -> bool {
+        match self.root_at(proof.size) {
+            Some(root) => proof.verify(&root, item),
+            None => false,
+        }
+    }
+}
+
+impl<H: Hasher> MmrAccumulator<H> {
+    /// Number of leaves in the range.
+    pub fn len(&self) -> u64 {
+        self.levels.first().map_or(0, |leaves| leaves.len() as u64)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Append an item to the range, returning the index of its leaf.
+    pub fn append(&mut self, item: &[u8]) -> u64 {
+        let index = self.len();
+        let mut hash = hash_leaf::<H>(item);
+        let mut height = 0;
+        loop {
+            if self.levels.len() == height {
+                self.levels.push(Vec::new());
+            }
+
+            let level = &mut self.levels[height];
+            level.push(hash);
+
+            // A node with an even number of nodes to its left starts a new mountain, otherwise it
+            // completes a pair that is merged into the next level.
+            if level.len() % 2 == 1 {
+                break index;
+            }
+
+            hash = hash_node::<H>(&level[level.len() - 2], &hash);
+            height += 1;
+        }
+    }
+
+    /// Peaks of the range when it held `size` leaves, from left to right.
+    pub fn peaks_at(&self, size: u64) -> Option<Vec<H::Hash>> {
+        if size > self.len() {
+            return None;
+        }
+
+        let mut offset = 0;
+        let mut peaks = Vec::new();
+        for height in peak_heights(size) {
+            peaks.push(self.levels[height as usize][(offset >> height) as usize]);
+            offset += 1 << height;
+        }
+        Some(peaks)
+    }
+
+    /// Root of the range when it held `size` leaves. Returns `None` for an empty range.
+    pub fn root_at(&self, size: u64) -> Option<H::Hash> {
+        bag_peaks::<H>(&self.peaks_at(size)?)
+    }
+
+    /// Root of the range at its current size.
+    pub fn root(&self) -> Option<H::Hash> {
+        self.root_at(self.len())
+    }
+
+    /// Prove the leaf at `index` was a member of the range when it held `size` leaves.
+    pub fn prove_at(&self, index: u64, size: u64) -> Option<MmrProof<H>> {
+        if index >= size {
+            return None;
+        }
+
+        let mut peaks = self.peaks_at(size)?;
+        let mut offset = 0;
+        for (peak_index, height) in peak_heights(size).into_iter().enumerate() {
+            if index < offset + (1 << height) {
+                let path = (0..height)
+                    .map(|level| self.levels[level as usize][((index >> level) ^ 1) as usize])
+                    .collect();
+                peaks.remove(peak_index);
+                return Some(MmrProof {
+                    size,
+                    peak_index: peak_index as u8,
+                    path,
+                    peaks,
+                });
+            }
+            offset += 1 << height;
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::accumulators::merkle::MerkleAccumulator,
+    };
+
+    #[test]
+    fn test_mmr_membership() {
+        let items: Vec<[u8; 8]> = (0..11usize).map(|i| i.to_be_bytes()).collect();
+        let accumulator =
+            MmrAccumulator::<Keccak256>::from_set(items.iter().map(|i| i.as_ref())).unwrap();
+        assert_eq!(accumulator.len(), 11);
+
+        // 11 leaves form mountains of 8, 2 and 1 leaves.
+        assert_eq!(accumulator.peaks_at(11).unwrap().len(), 3);
+
+        for item in &items {
+            let proof = accumulator.prove(item).unwrap();
+            assert!(accumulator.check(proof, item));
+        }
+
+        let item_x = 100usize.to_be_bytes();
+        assert_eq!(
+            accumulator.prove(&item_x),
+            Err(AccumulatorError::ItemNotFound)
+        );
+        let proof = accumulator.prove(&items[0]).unwrap();
+        assert!(!accumulator.check(proof, &item_x));
+
+        assert_eq!(
+            MmrAccumulator::<Keccak256>::from_set(std::iter::empty()),
+            Err(AccumulatorError::EmptyInput)
+        );
+    }
+
+    #[test]
+    fn test_mmr_historical_proofs() {
+        let items: Vec<[u8; 8]> = (0..16usize).map(|i| i.to_be_bytes()).collect();
+        let mut accumulator = MmrAccumulator::<Keccak256>::default();
+
+        let mut roots = Vec::new();
+        let mut proofs = Vec::new();
+        for item in &items {
+            let index = accumulator.append(item);
+            roots.push(accumulator.root().unwrap());
+            proofs.push(accumulator.prove_at(0, index + 1).unwrap());
+        }
+
+        // Historical roots are reproducible and proofs made at each size still verify against
+        // the root of that size after further appends.
+        for (size, (root, proof)) in roots.iter().zip(proofs).enumerate() {
+            assert_eq!(accumulator.root_at(size as u64 + 1).as_ref(), Some(root));
+            assert!(proof.verify(root, &items[0]));
+            assert!(accumulator.check(proof, &items[0]));
+        }
+
+        // A power of two sized range has a single peak which matches the equivalent Merkle tree.
+        let leaves: Vec<&[u8]> = items.iter().map(|i| i.as_ref()).collect();
+        let merkle = MerkleAccumulator::<Keccak256>::new(&leaves).unwrap();
+        assert_eq!(accumulator.root(), Some(merkle.root));
+    }
+
+    #[test]
+    fn test_mmr_malformed_proofs() {
+        let items: Vec<[u8; 8]> = (0..7usize).map(|i| i.to_be_bytes()).collect();
+        let accumulator =
+            MmrAccumulator::<Keccak256>::from_set(items.iter().map(|i| i.as_ref())).unwrap();
+        let proof = accumulator.prove(&items[5]).unwrap();
+
+        let mut wrong_size = proof.clone();
+        wrong_size.size = 6;
+        assert!(!accumulator.check(wrong_size, &items[5]));
+
+        let mut wrong_peak = proof.clone();
+        wrong_peak.peak_index = 0;
+        assert!(!accumulator.check(wrong_peak, &items[5]));
+
+        let mut corrupted = proof;
+        corrupted.path[0] = Default::default();
+        assert!(!accumulator.check(corrupted, &items[5]));
+
+        assert!(accumulator.prove_at(7, 7).is_none());
+        assert!(accumulator.prove_at(0, 8).is_none());
+    }
+}
@@ -1,7 +1,10 @@
 //! A multiplication based Accumulator (should not use, example only)
 
 use crate::{
-    accumulators::Accumulator,
+    accumulators::{
+        Accumulator,
+        AccumulatorError,
+    },
     hashers::{
         prime::PrimeHasher,
         Hasher,
@@ -23,10 +26,10 @@ pub struct MulAccumulator<H: Hasher> {
 impl<'a> Accumulator<'a> for MulAccumulator<PrimeHasher> {
     type Proof = <PrimeHasher as Hasher>::Hash;
 
-    fn prove(&self, item: &[u8]) -> Option<Self::Proof> {
+    fn prove(&self, item: &[u8]) -> Result<Self::Proof, AccumulatorError> {
         let bytes = u128::from_be_bytes(PrimeHasher::hashv(&[item]));
         let acc = u128::from_be_bytes(self.accumulator);
-        Some((acc / bytes).to_be_bytes())
+        Ok((acc / bytes).to_be_bytes())
     }
 
     fn check(&self, proof: Self::Proof, item: &[u8]) -> bool {
@@ -35,13 +38,16 @@ impl<'a> Accumulator<'a> for MulAccumulator<PrimeHasher> {
         proof * bytes == u128::from_be_bytes(self.accumulator)
     }
 
-    fn from_set(items: impl Iterator<Item = &'a [u8]>) -> Option<Self> {
+    fn from_set(items: impl Iterator<Item = &'a [u8]>) -> Result<Self, AccumulatorError> {
         let primes: Vec<[u8; 16]> = items.map(|i| PrimeHasher::hashv(&[i])).collect();
-        Some(Self {
+        Ok(Self {
             items:       primes.clone(),
-            accumulator: primes.into_iter().reduce(|acc, v| {
-                u128::to_be_bytes(u128::from_be_bytes(acc) * u128::from_be_bytes(v))
-            })?,
+            accumulator: primes
+                .into_iter()
+                .reduce(|acc, v| {
+                    u128::to_be_bytes(u128::from_be_bytes(acc) * u128::from_be_bytes(v))
+                })
+                .ok_or(AccumulatorError::EmptyInput)?,
         })
     }
 }
@@ -0,0 +1,162 @@
+//! A frontier-only MerkleTree based Accumulator for append-heavy workloads.
+
+#[cfg(feature = "std")]
+use crate::accumulators::merkle::MerkleAccumulator;
+use {
+    crate::{
+        accumulators::merkle::hash::{
+            hash_leaf,
+            hash_node,
+            hash_null,
+        },
+        hashers::{
+            keccak256::Keccak256,
+            Hasher,
+        },
+    },
+    alloc::vec::Vec,
+};
+
+/// A PrunedMerkleAccumulator tracks the root of a MerkleAccumulator without storing its nodes.
+///
+/// Only the right-edge frontier of the tree is kept: for every level, the root of the last
+/// complete subtree that new leaves will never touch again. This is all that is needed to append
+/// items and recompute the root, so a long-running builder holds `O(log n)` hashes instead of the
+/// full tree. Roots are identical to a MerkleAccumulator built over the same items, but as the
+/// interior nodes are discarded this type cannot generate proofs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrunedMerkleAccumulator<H: Hasher = Keccak256> {
+    len:      u64,
+    // `frontier[h]` is the root of the subtree of `2^h` leaves covered by bit `h` of `len`, or
+    // the default hash while that bit is unset. There is one entry per bit of `len`.
+    frontier: Vec<H::Hash>,
+}
+
+impl<H: Hasher> Default for PrunedMerkleAccumulator<H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<H: Hasher> PrunedMerkleAccumulator<H> {
+    /// Create an empty tree.
+    pub fn new() -> Self {
+        Self {
+            len:      0,
+            frontier: Vec::new(),
+        }
+    }
+
+    /// Number of items appended to the tree.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Append an item to the tree, returning the index of its leaf.
+    pub fn append(&mut self, item: &[u8]) -> u64 {
+        let index = self.len;
+
+        // Carry the new leaf up for as long as it completes a subtree, the first level left
+        // incomplete stores the result. Levels carried through are cleared so that equal trees
+        // compare equal.
+        let mut node = hash_leaf::<H>(item);
+        let mut height = 0;
+        while index >> height & 1 == 1 {
+            node = hash_node::<H>(&core::mem::take(&mut self.frontier[height]), &node);
+            height += 1;
+        }
+
+        match self.frontier.get_mut(height) {
+            Some(slot) => *slot = node,
+            None => self.frontier.push(node),
+        }
+        self.len += 1;
+        index
+    }
+
+    /// Root of the tree, which is the same as the root of a MerkleAccumulator over the same
+    /// items. Returns `None` for an empty tree.
+    pub fn root(&self) -> Option<H::Hash> {
+        if self.is_empty() {
+            return None;
+        }
+
+        // A full tree is a single complete subtree, otherwise the frontier is combined with the
+        // NULL padding to the right of the last item up to the depth of the tree.
+        let depth = self.len.next_power_of_two().trailing_zeros() as usize;
+        if self.len.is_power_of_two() {
+            return Some(self.frontier[depth]);
+        }
+
+        let mut node = hash_null::<H>();
+        let mut empty = node;
+        for height in 0..depth {
+            node = if self.len >> height & 1 == 1 {
+                hash_node::<H>(&self.frontier[height], &node)
+            } else {
+                hash_node::<H>(&node, &empty)
+            };
+            empty = hash_node::<H>(&empty, &empty);
+        }
+        Some(node)
+    }
+}
+
+/// Prune an existing tree, keeping only what is needed to continue appending to it. Items are
+/// appended without a domain, so trees built with `MerkleAccumulator::new_with_domain` cannot be
+/// continued this way.
+#[cfg(feature = "std")]
+impl<H: Hasher> From<&MerkleAccumulator<H>> for PrunedMerkleAccumulator<H> {
+    fn from(accumulator: &MerkleAccumulator<H>) -> Self {
+        let len = accumulator.len();
+        let depth = accumulator.depth().unwrap_or(0);
+        let frontier = (0..usize::BITS - len.leading_zeros())
+            .map(|height| match len >> height {
+                n if n & 1 == 0 => Default::default(),
+                n => accumulator
+                    .node_at(depth - height as usize, n - 1)
+                    .unwrap_or_default(),
+            })
+            .collect();
+
+        Self {
+            len: len as u64,
+            frontier,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pruned_merkle() {
+        let items: Vec<[u8; 8]> = (0..17usize).map(|i| i.to_be_bytes()).collect();
+        let mut accumulator = PrunedMerkleAccumulator::<Keccak256>::new();
+        assert_eq!(accumulator.root(), None);
+
+        // Every size matches the equivalent MerkleAccumulator, both when appending from scratch
+        // and when continuing from a pruned MerkleAccumulator.
+        for (i, item) in items.iter().enumerate() {
+            assert_eq!(accumulator.append(item), i as u64);
+            let leaves: Vec<&[u8]> = items[..=i].iter().map(|i| i.as_ref()).collect();
+            let merkle = MerkleAccumulator::<Keccak256>::new(&leaves).unwrap();
+            assert_eq!(accumulator.root(), Some(merkle.root));
+
+            let mut pruned = PrunedMerkleAccumulator::from(&merkle);
+            assert_eq!(pruned, accumulator);
+            assert_eq!(pruned.root(), Some(merkle.root));
+
+            let item_x = 100usize.to_be_bytes();
+            let mut merkle = merkle;
+            merkle.insert(&item_x).unwrap();
+            pruned.append(&item_x);
+            assert_eq!(pruned.root(), Some(merkle.root));
+        }
+    }
+}
@@ -0,0 +1,281 @@
+//! A MerkleTree based Accumulator compatible with the `solana-merkle-tree` crate.
+
+use {
+    crate::{
+        accumulators::{
+            merkle::{
+                hash::{
+                    hash_leaf,
+                    hash_node,
+                    NODE_PREFIX,
+                },
+                MerklePath,
+            },
+            Accumulator,
+            AccumulatorError,
+        },
+        hashers::Hasher,
+    },
+    alloc::vec::Vec,
+    serde::Serialize,
+};
+
+// Solana orders the children of a node by position rather than by value.
+fn hash_intermediate<H: Hasher>(l: &H::Hash, r: &H::Hash) -> H::Hash {
+    H::hashv(&[NODE_PREFIX, l.as_ref(), r.as_ref()])
+}
+
+/// One level of a SolanaMerklePath, equivalent to `solana_merkle_tree::ProofEntry`.
+///
+/// `target` is the parent node computed at this level, and exactly one of the siblings is set
+/// depending on which side of the parent the sibling sits.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct SolanaProofEntry<H: Hasher> {
+    pub target:        H::Hash,
+    pub left_sibling:  Option<H::Hash>,
+    pub right_sibling: Option<H::Hash>,
+}
+
+/// A proof in the format of `solana_merkle_tree::Proof`, which Solana-style verifiers can check
+/// without knowing anything about this crate.
+#[derive(Clone, Default, Debug, PartialEq, Eq, Serialize)]
+pub struct SolanaMerklePath<H: Hasher>(Vec<SolanaProofEntry<H>>);
+
+impl<H: Hasher> SolanaMerklePath<H> {
+    pub fn new(entries: Vec<SolanaProofEntry<H>>) -> Self {
+        Self(entries)
+    }
+
+    pub fn entries(&self) -> &[SolanaProofEntry<H>] {
+        &self.0
+    }
+
+    /// Verify the path starting from the leaf hash `candidate`, with the same semantics as
+    /// `solana_merkle_tree::Proof::verify`. As there, this only checks the path is consistent and
+    /// not which root it leads to.
+    pub fn verify(&self, candidate: H::Hash) -> bool {
+        let mut current = candidate;
+        for entry in &self.0 {
+            let (l, r) = match (&entry.left_sibling, &entry.right_sibling) {
+                (Some(l), None) => (l, &current),
+                (None, Some(r)) => (&current, r),
+                _ => return false,
+            };
+            current = hash_intermediate::<H>(l, r);
+            if current != entry.target {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Verify an item is a member of the tree with the given root.
+    pub fn verify_item(&self, root: &H::Hash, item: &[u8]) -> bool {
+        let leaf = hash_leaf::<H>(item);
+        let top = self.0.last().map_or(&leaf, |entry| &entry.target);
+        top == root && self.verify(leaf)
+    }
+
+    /// Convert a proof generated by a MerkleAccumulator for `item`.
+    ///
+    /// Leaves are hashed identically and a node of a MerkleAccumulator is the Solana intermediate
+    /// hash of its children in ascending order, so placing each sibling on the side its value
+    /// sorts to produces a path that Solana-style verifiers accept against the same root.
+    pub fn from_merkle_path(path: &MerklePath<H>, item: &[u8]) -> Self {
+        let mut current = hash_leaf::<H>(item);
+        let entries = path
+            .hashes()
+            .iter()
+            .map(|sibling| {
+                let left = sibling <= &current;
+                current = hash_node::<H>(&current, sibling);
+                SolanaProofEntry {
+                    target:        current,
+                    left_sibling:  left.then_some(*sibling),
+                    right_sibling: (!left).then_some(*sibling),
+                }
+            })
+            .collect();
+        Self(entries)
+    }
+}
+
+/// A SolanaCompatMerkleAccumulator builds the same tree as `solana_merkle_tree::MerkleTree`.
+///
+/// Unlike MerkleAccumulator, children are hashed in positional order and a level with an odd
+/// number of nodes pairs its last node with itself instead of padding with NULL leaves. With a
+/// SHA-256 hasher, matching `solana_program::hash::hashv`, roots and proofs are byte for byte
+/// identical to those of the Solana crate.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct SolanaCompatMerkleAccumulator<H: Hasher> {
+    leaf_count: usize,
+    // Levels are stored one after another from the leaves up, the root being the last node.
+    nodes:      Vec<H::Hash>,
+}
+
+impl<'a, H: Hasher + 'a> Accumulator<'a> for SolanaCompatMerkleAccumulator<H> {
+    type Proof = SolanaMerklePath<H>;
+
+    fn from_set(items: impl Iterator<Item = &'a [u8]>) -> Result<Self, AccumulatorError> {
+        let items: Vec<&[u8]> = items.collect();
+        Self::new(&items).ok_or(AccumulatorError::EmptyInput)
+    }
+
+    fn prove(&'a self, item: &[u8]) -> Result<Self::Proof, AccumulatorError> {
+        let leaf = hash_leaf::<H>(item);
+        self.nodes[..self.leaf_count]
+            .iter()
+            .position(|node| node == &leaf)
+            .and_then(|index| self.prove_at(index))
+            .ok_or(AccumulatorError::ItemNotFound)
+    }
+
+    fn check(&'a self, proof: Self::Proof, item: &[u8]) -> bool {
+        proof.verify_item(&self.root(), item)
+    }
+}
+
+impl<H: Hasher> SolanaCompatMerkleAccumulator<H> {
+    fn next_level_len(level_len: usize) -> usize {
+        match level_len {
+            1 => 0,
+            _ => level_len / 2 + level_len % 2,
+        }
+    }
+
+    /// Build a tree over `items`, returning `None` if there are none.
+    pub fn new(items: &[&[u8]]) -> Option<Self> {
+        if items.is_empty() {
+            return None;
+        }
+
+        let mut nodes: Vec<H::Hash> = items.iter().map(|i| hash_leaf::<H>(i)).collect();
+        let mut level_start = 0;
+        let mut level_len = items.len();
+        while level_len > 1 {
+            for i in (0..level_len).step_by(2) {
+                let l = nodes[level_start + i];
+                let r = nodes[level_start + (i + 1).min(level_len - 1)];
+                nodes.push(hash_intermediate::<H>(&l, &r));
+            }
+            level_start += level_len;
+            level_len = Self::next_level_len(level_len);
+        }
+
+        Some(Self {
+            leaf_count: items.len(),
+            nodes,
+        })
+    }
+
+    pub fn root(&self) -> H::Hash {
+        self.nodes.last().copied().unwrap_or_default()
+    }
+
+    /// Number of items in the tree.
+    pub fn len(&self) -> usize {
+        self.leaf_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaf_count == 0
+    }
+
+    /// Prove the item at leaf `index`, equivalent to `solana_merkle_tree::MerkleTree::find_path`.
+    pub fn prove_at(&self, mut index: usize) -> Option<SolanaMerklePath<H>> {
+        if index >= self.leaf_count {
+            return None;
+        }
+
+        let mut entries = Vec::new();
+        let mut level_start = 0;
+        let mut level_len = self.leaf_count;
+        while level_len > 1 {
+            let level = &self.nodes[level_start..level_start + level_len];
+            let (left_sibling, right_sibling) = match index % 2 {
+                0 => (None, Some(level[(index + 1).min(level_len - 1)])),
+                _ => (Some(level[index - 1]), None),
+            };
+
+            index /= 2;
+            level_start += level_len;
+            level_len = Self::next_level_len(level_len);
+            entries.push(SolanaProofEntry {
+                target: self.nodes[level_start + index],
+                left_sibling,
+                right_sibling,
+            });
+        }
+
+        Some(SolanaMerklePath(entries))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::accumulators::merkle::MerkleAccumulator,
+    };
+
+    // SHA-256 as used by `solana_merkle_tree`.
+    #[derive(Clone, Default, Debug, Eq, PartialEq, Serialize)]
+    struct SolanaHasher;
+
+    impl Hasher for SolanaHasher {
+        type Hash = [u8; 32];
+
+        fn hashv(data: &[impl AsRef<[u8]>]) -> [u8; 32] {
+            let data: Vec<&[u8]> = data.iter().map(|d| d.as_ref()).collect();
+            solana_sdk::hash::hashv(&data).to_bytes()
+        }
+    }
+
+    const TEST: &[&[u8]] = &[
+        b"my", b"very", b"eager", b"mother", b"just", b"served", b"us", b"nine", b"pizzas",
+        b"make", b"prime",
+    ];
+
+    #[test]
+    fn test_solana_compat() {
+        // Golden root from the `solana-merkle-tree` test suite.
+        let accumulator = SolanaCompatMerkleAccumulator::<SolanaHasher>::new(TEST).unwrap();
+        assert_eq!(
+            hex::encode(accumulator.root()),
+            "b40c847546fdceea166f927fc46c5ca33c3638236a36275c1346d3dffb84e1bc"
+        );
+        assert_eq!(accumulator.len(), TEST.len());
+
+        for (index, item) in TEST.iter().enumerate() {
+            let proof = accumulator.prove(item).unwrap();
+            assert_eq!(accumulator.prove_at(index), Some(proof.clone()));
+            assert!(proof.verify(hash_leaf::<SolanaHasher>(item)));
+            assert!(accumulator.check(proof, item));
+        }
+        let proof = accumulator.prove_at(0).unwrap();
+        assert!(!accumulator.check(proof, b"bad"));
+        assert!(accumulator.prove_at(TEST.len()).is_none());
+        assert_eq!(
+            accumulator.prove(b"bad"),
+            Err(AccumulatorError::ItemNotFound)
+        );
+
+        let single = SolanaCompatMerkleAccumulator::<SolanaHasher>::new(&[b"test"]).unwrap();
+        assert_eq!(single.root(), hash_leaf::<SolanaHasher>(b"test"));
+        assert!(single.check(single.prove(b"test").unwrap(), b"test"));
+        assert!(SolanaCompatMerkleAccumulator::<SolanaHasher>::new(&[]).is_none());
+    }
+
+    #[test]
+    fn test_solana_compat_conversion() {
+        // Pythnet proofs converted to the Solana format verify against the Pythnet root.
+        let accumulator = MerkleAccumulator::<SolanaHasher>::new(TEST).unwrap();
+        for item in TEST {
+            let path = accumulator.prove(item).unwrap();
+            let proof = SolanaMerklePath::from_merkle_path(&path, item);
+            assert_eq!(proof.entries().len(), path.hashes().len());
+            assert!(proof.verify_item(&accumulator.root, item));
+            assert!(!proof.verify_item(&accumulator.root, b"bad"));
+        }
+    }
+}
@@ -1,14 +1,48 @@
 use {
+    alloc::vec::Vec,
+    bytemuck::{
+        Pod,
+        Zeroable,
+    },
+    core::fmt::Debug,
     serde::{
+        de::{
+            Error as _,
+            SeqAccess,
+            Visitor,
+        },
+        ser::SerializeTuple,
         Deserialize,
+        Deserializer,
         Serialize,
+        Serializer,
+    },
+};
+#[cfg(feature = "std")]
+use {
+    borsh::{
+        BorshDeserialize,
+        BorshSerialize,
+    },
+    std::io::{
+        Error,
+        ErrorKind::InvalidData,
+        Write,
     },
-    std::fmt::Debug,
 };
 
+#[cfg(feature = "blake3")]
+pub mod blake3;
 pub mod keccak256;
 pub mod keccak256_160;
+pub mod keccak512;
+#[cfg(feature = "poseidon")]
+pub mod poseidon;
+#[cfg(feature = "std")]
 pub mod prime;
+pub mod sha256;
+pub mod sha3_256;
+pub mod test_vectors;
 
 /// We provide `Hasher` as a small hashing abstraction.
 ///
@@ -28,10 +62,166 @@ where
         + Debug
         + Default
         + Eq
+        + core::hash::Hash
+        + Send
+        + Sync
         + PartialOrd
         + PartialEq
         + serde::Serialize
-        + for<'a> Deserialize<'a>;
+        + for<'a> Deserialize<'a>
+        + for<'a> TryFrom<&'a [u8]>
+        + PodHash;
 
     fn hashv(data: &[impl AsRef<[u8]>]) -> Self::Hash;
+
+    /// Hash `prefix || message` for each of `messages`, as `hashv(&[prefix, message])` would.
+    ///
+    /// Tree leaves are hashed through this, so hashers able to hash several messages at once can
+    /// override it.
+    fn hashv_many(prefix: &[u8], messages: &[&[u8]]) -> Vec<Self::Hash> {
+        messages
+            .iter()
+            .map(|message| Self::hashv(&[prefix, message]))
+            .collect()
+    }
+
+    /// Size of the digests produced by this hasher, in bytes.
+    fn hash_size() -> usize {
+        Self::Hash::default().as_ref().len()
+    }
+}
+
+/// Additional bounds on `Hasher::Hash` required by the `pod` feature.
+///
+/// With the feature enabled every hash is `bytemuck::Pod`, so on-chain programs can keep roots and
+/// proofs in zero-copy account structs and cast them to and from account data without unsafe
+/// transmutes. Without it this trait is implemented for every type.
+#[cfg(feature = "pod")]
+pub trait PodHash: Pod + Zeroable {}
+
+#[cfg(feature = "pod")]
+impl<T: Pod + Zeroable> PodHash for T {
+}
+
+/// Additional bounds on `Hasher::Hash` required by the `pod` feature.
+#[cfg(not(feature = "pod"))]
+pub trait PodHash {}
+
+#[cfg(not(feature = "pod"))]
+impl<T> PodHash for T {
+}
+
+/// A digest of `N` bytes.
+///
+/// Arrays only implement `Default` and serde up to 32 elements, so hashers with larger outputs
+/// use this instead. It encodes exactly like the array it wraps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct FixedHash<const N: usize>(pub [u8; N]);
+
+// SAFETY: FixedHash is a transparent wrapper around a byte array.
+unsafe impl<const N: usize> Zeroable for FixedHash<N> where [u8; N]: Zeroable
+{
+}
+unsafe impl<const N: usize> Pod for FixedHash<N> where [u8; N]: Pod
+{
+}
+
+impl<const N: usize> Default for FixedHash<N> {
+    fn default() -> Self {
+        Self([0; N])
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for FixedHash<N> {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for FixedHash<N> {
+    fn from(bytes: [u8; N]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl<'a, const N: usize> TryFrom<&'a [u8]> for FixedHash<N> {
+    type Error = core::array::TryFromSliceError;
+
+    fn try_from(bytes: &'a [u8]) -> Result<Self, Self::Error> {
+        bytes.try_into().map(Self)
+    }
+}
+
+impl<const N: usize> Serialize for FixedHash<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tuple = serializer.serialize_tuple(N)?;
+        for byte in &self.0 {
+            tuple.serialize_element(byte)?;
+        }
+        tuple.end()
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for FixedHash<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FixedHashVisitor<const N: usize>;
+
+        impl<'de, const N: usize> Visitor<'de> for FixedHashVisitor<N> {
+            type Value = FixedHash<N>;
+
+            fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                write!(f, "{N} bytes")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut hash = [0u8; N];
+                for (i, byte) in hash.iter_mut().enumerate() {
+                    *byte = seq
+                        .next_element()?
+                        .ok_or_else(|| A::Error::invalid_length(i, &self))?;
+                }
+                Ok(FixedHash(hash))
+            }
+        }
+
+        deserializer.deserialize_tuple(N, FixedHashVisitor::<N>)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const N: usize> BorshSerialize for FixedHash<N> {
+    fn serialize<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const N: usize> BorshDeserialize for FixedHash<N> {
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        if buf.len() < N {
+            return Err(Error::new(InvalidData, "Not enough bytes"));
+        }
+
+        let (hash, rest) = buf.split_at(N);
+        *buf = rest;
+        Ok(Self::try_from(hash).unwrap_or_default())
+    }
+}
+
+/// A `Hasher` that can consume its input incrementally.
+///
+/// `hashv` needs every part of a message up front, so callers holding a large message in pieces
+/// have to collect them first. A `StreamingHasher` instead hands out a `HashStream` that the parts
+/// are written to one at a time, producing the same hash as `hashv` over all of them.
+pub trait StreamingHasher: Hasher {
+    type Stream: HashStream<Self::Hash>;
+
+    fn stream() -> Self::Stream;
+}
+
+/// The running state of a `StreamingHasher`.
+pub trait HashStream<Hash> {
+    fn update(&mut self, data: &[u8]);
+    fn finalize(self) -> Hash;
 }
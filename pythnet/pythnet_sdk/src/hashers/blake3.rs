@@ -0,0 +1,70 @@
+use {
+    crate::hashers::{
+        Hasher,
+        StreamingHasher,
+    },
+    serde::Serialize,
+};
+
+/// BLAKE3, for building large trees off-chain.
+///
+/// BLAKE3 is several times faster than Keccak256 in software, which matters to services such as
+/// indexers that build auxiliary trees over many items. Few target chains can verify it cheaply,
+/// so trees built with this hasher are not meant to be checked on-chain.
+#[derive(Clone, Default, Debug, Eq, PartialEq, Serialize)]
+pub struct Blake3 {}
+
+impl Hasher for Blake3 {
+    type Hash = [u8; 32];
+
+    fn hashv(data: &[impl AsRef<[u8]>]) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new();
+        data.iter().for_each(|d| {
+            hasher.update(d.as_ref());
+        });
+        hasher.finalize().into()
+    }
+}
+
+impl StreamingHasher for Blake3 {
+    type Stream = blake3::Hasher;
+
+    fn stream() -> Self::Stream {
+        blake3::Hasher::new()
+    }
+}
+
+impl crate::hashers::HashStream<[u8; 32]> for blake3::Hasher {
+    fn update(&mut self, data: &[u8]) {
+        blake3::Hasher::update(self, data);
+    }
+
+    fn finalize(self) -> [u8; 32] {
+        blake3::Hasher::finalize(&self).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::hashers::Hasher,
+    };
+
+    #[test]
+    fn test_blake3() {
+        let data = b"helloworld";
+        let hash_a = Blake3::hashv(&[data]);
+
+        let data = [b"hello", b"world"];
+        let hash_b = Blake3::hashv(&data);
+
+        assert_eq!(hash_a, hash_b);
+
+        // Vector from the BLAKE3 reference implementation.
+        assert_eq!(
+            hex::encode(Blake3::hashv(&[b""])),
+            "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"
+        );
+    }
+}
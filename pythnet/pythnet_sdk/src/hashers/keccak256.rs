@@ -1,23 +1,71 @@
+// With the `simd` feature single messages are hashed by the CRYPTOGAMS assembly on x86_64, which
+// picks its AVX-512VL variant when compiled for it, and by `sha3` elsewhere, which detects the
+// ARMv8 SHA3 extension at runtime on aarch64. Those only speed up one message at a time. Leaves
+// are hashed through `hashv_many`, which detects AVX2 or NEON at runtime and hashes several
+// messages side by side, see `simd`.
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+use keccak_asm::Keccak256 as Keccak256Digest;
+#[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+use sha3::Keccak256 as Keccak256Digest;
 use {
-    crate::hashers::Hasher,
-    serde::Serialize,
-    sha3::{
-        Digest,
-        Keccak256 as Keccak256Digest,
+    crate::hashers::{
+        Hasher,
+        StreamingHasher,
     },
+    serde::Serialize,
+    sha3::Digest,
 };
 
+#[cfg(all(feature = "simd", feature = "std", not(target_os = "solana")))]
+mod simd;
+
+/// Keccak256, as used by the EVM.
+///
+/// Off-chain builders hashing many leaves should enable the `simd` feature, which switches to an
+/// assembly or hardware accelerated implementation where one is available and hashes leaves
+/// several at a time on CPUs with AVX2 or NEON. Outputs are identical.
 #[derive(Clone, Default, Debug, Eq, PartialEq, Serialize)]
 pub struct Keccak256 {}
 
 impl Hasher for Keccak256 {
     type Hash = [u8; 32];
 
+    #[cfg(not(target_os = "solana"))]
     fn hashv(data: &[impl AsRef<[u8]>]) -> [u8; 32] {
         let mut hasher = Keccak256Digest::new();
         data.iter().for_each(|d| hasher.update(d));
         hasher.finalize().into()
     }
+
+    // On-chain the keccak syscall costs a fraction of the compute units of hashing in the VM.
+    #[cfg(target_os = "solana")]
+    fn hashv(data: &[impl AsRef<[u8]>]) -> [u8; 32] {
+        let data: alloc::vec::Vec<&[u8]> = data.iter().map(|d| d.as_ref()).collect();
+        solana_program::keccak::hashv(&data).to_bytes()
+    }
+
+    #[cfg(all(feature = "simd", feature = "std", not(target_os = "solana")))]
+    fn hashv_many(prefix: &[u8], messages: &[&[u8]]) -> alloc::vec::Vec<[u8; 32]> {
+        simd::hashv_many(prefix, messages)
+    }
+}
+
+impl StreamingHasher for Keccak256 {
+    type Stream = Keccak256Digest;
+
+    fn stream() -> Self::Stream {
+        Keccak256Digest::new()
+    }
+}
+
+impl crate::hashers::HashStream<[u8; 32]> for Keccak256Digest {
+    fn update(&mut self, data: &[u8]) {
+        Digest::update(self, data);
+    }
+
+    fn finalize(self) -> [u8; 32] {
+        Digest::finalize(self).into()
+    }
 }
 
 #[cfg(test)]
@@ -37,4 +85,70 @@ mod tests {
 
         assert_eq!(hash_a, hash_b);
     }
+
+    #[test]
+    fn test_keccak256_known_answers() {
+        // Checks whichever backend the build selected against the reference output.
+        assert_eq!(
+            hex::encode(Keccak256::hashv(&[b""])),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        assert_eq!(
+            Keccak256::hashv(&[[7u8; 1000]])[..],
+            sha3::Keccak256::digest([7u8; 1000])[..]
+        );
+    }
+
+    #[cfg(feature = "pod")]
+    #[test]
+    fn test_keccak256_pod() {
+        use crate::accumulators::{
+            merkle::{
+                MerkleAccumulator,
+                MerklePath,
+            },
+            Accumulator,
+        };
+
+        #[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+        #[repr(C)]
+        struct RootAccount {
+            slot: u64,
+            root: <Keccak256 as Hasher>::Hash,
+        }
+
+        // Generic code can rely on the bound as well.
+        fn hash_bytes<H: Hasher>(hashes: &[H::Hash]) -> &[u8] {
+            bytemuck::cast_slice(hashes)
+        }
+
+        let items: &[&[u8]] = &[b"a", b"b", b"c"];
+        let accumulator = MerkleAccumulator::<Keccak256>::new(items).unwrap();
+        let account = RootAccount {
+            slot: 7,
+            root: accumulator.root,
+        };
+        let data = bytemuck::bytes_of(&account).to_vec();
+        let account: RootAccount = bytemuck::pod_read_unaligned(&data);
+        assert_eq!(account.root, accumulator.root);
+
+        // Proofs are stored as a flat byte array and cast back without copying each hash.
+        let proof = accumulator.prove(b"b").unwrap();
+        let data = hash_bytes::<Keccak256>(proof.hashes()).to_vec();
+        let hashes: &[[u8; 32]] = bytemuck::cast_slice(&data);
+        let proof = MerklePath::<Keccak256>::new(hashes.to_vec());
+        assert!(accumulator.check(proof, b"b"));
+    }
+
+    #[test]
+    fn test_keccak256_matches_solana() {
+        // The syscall used on-chain must agree with the sha3 implementation used everywhere else.
+        let inputs: &[&[&[u8]]] = &[&[], &[b""], &[b"hello", b"world"], &[&[0], &[1; 200]]];
+        for data in inputs {
+            assert_eq!(
+                Keccak256::hashv(data),
+                solana_sdk::keccak::hashv(data).to_bytes()
+            );
+        }
+    }
 }
@@ -0,0 +1,482 @@
+//! Multi-way Keccak256 for hashing many short messages at once, such as the leaves of a tree.
+//!
+//! The Keccak-f[1600] permutation is written once over `Lanes`, a vector of 64-bit lanes, and
+//! runs as many messages side by side as the vector holds: four with AVX2 on x86_64 and two with
+//! NEON on aarch64. The instruction set is detected at runtime, so a binary built for baseline
+//! x86_64 still uses AVX2 where the CPU has it. Without a usable vector unit, or on aarch64 CPUs
+//! with the SHA3 extension which the single message path already uses, messages are hashed one by
+//! one with `Keccak256::hashv`.
+
+#[cfg(target_arch = "aarch64")]
+use core::arch::aarch64::*;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+use {
+    super::Keccak256,
+    crate::hashers::Hasher,
+    alloc::vec::Vec,
+};
+
+/// Bytes absorbed per permutation by Keccak256.
+const RATE: usize = 136;
+
+/// Widest `Lanes` implementation, used to size the per-batch buffers.
+const MAX_WIDTH: usize = 4;
+
+/// Round constants of Keccak-f[1600].
+const RC: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+/// Hash `prefix || message` for each of `messages` with the widest backend the CPU supports.
+pub fn hashv_many(prefix: &[u8], messages: &[&[u8]]) -> Vec<[u8; 32]> {
+    #[cfg(target_arch = "x86_64")]
+    if is_x86_feature_detected!("avx2") {
+        // SAFETY: AVX2 was just detected.
+        return unsafe { hashv_many_avx2(prefix, messages) };
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    if std::arch::is_aarch64_feature_detected!("neon")
+        && !std::arch::is_aarch64_feature_detected!("sha3")
+    {
+        // SAFETY: NEON was just detected.
+        return unsafe { hashv_many_neon(prefix, messages) };
+    }
+
+    messages
+        .iter()
+        .map(|message| Keccak256::hashv(&[prefix, message]))
+        .collect()
+}
+
+/// # Safety
+///
+/// The CPU must support AVX2.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn hashv_many_avx2(prefix: &[u8], messages: &[&[u8]]) -> Vec<[u8; 32]> {
+    hashv_many_with::<__m256i>(prefix, messages)
+}
+
+/// # Safety
+///
+/// The CPU must support NEON.
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+unsafe fn hashv_many_neon(prefix: &[u8], messages: &[&[u8]]) -> Vec<[u8; 32]> {
+    hashv_many_with::<uint64x2_t>(prefix, messages)
+}
+
+/// `WIDTH` Keccak lanes, one from each of the messages hashed together.
+///
+/// The vector implementations use intrinsics of an instruction set the crate isn't compiled for,
+/// so every method is an `unsafe fn` enabling that instruction set with `#[target_feature]`.
+/// Nothing outside this module can reach them, and within it they are only called through
+/// `hashv_many`, which detects the instruction set first.
+///
+/// # Safety
+///
+/// Callers of any method must ensure the CPU supports the instruction set of the implementation.
+trait Lanes: Copy {
+    const WIDTH: usize;
+
+    unsafe fn splat(lane: u64) -> Self;
+    unsafe fn load(lanes: &[u64]) -> Self;
+    unsafe fn store(self, lanes: &mut [u64]);
+    unsafe fn xor(self, other: Self) -> Self;
+    /// `!self & other`.
+    unsafe fn andnot(self, other: Self) -> Self;
+    /// Rotate left by `N`, where `M` is `64 - N`.
+    unsafe fn rotl<const N: i32, const M: i32>(self) -> Self;
+}
+
+// Plain integers need no instruction set beyond the baseline.
+impl Lanes for u64 {
+    const WIDTH: usize = 1;
+
+    #[inline(always)]
+    unsafe fn splat(lane: u64) -> Self {
+        lane
+    }
+
+    #[inline(always)]
+    unsafe fn load(lanes: &[u64]) -> Self {
+        lanes[0]
+    }
+
+    #[inline(always)]
+    unsafe fn store(self, lanes: &mut [u64]) {
+        lanes[0] = self;
+    }
+
+    #[inline(always)]
+    unsafe fn xor(self, other: Self) -> Self {
+        self ^ other
+    }
+
+    #[inline(always)]
+    unsafe fn andnot(self, other: Self) -> Self {
+        !self & other
+    }
+
+    #[inline(always)]
+    unsafe fn rotl<const N: i32, const M: i32>(self) -> Self {
+        self.rotate_left(N as u32)
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+impl Lanes for __m256i {
+    const WIDTH: usize = 4;
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn splat(lane: u64) -> Self {
+        _mm256_set1_epi64x(lane as i64)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn load(lanes: &[u64]) -> Self {
+        assert!(lanes.len() >= 4);
+        _mm256_loadu_si256(lanes.as_ptr().cast())
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn store(self, lanes: &mut [u64]) {
+        assert!(lanes.len() >= 4);
+        _mm256_storeu_si256(lanes.as_mut_ptr().cast(), self)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn xor(self, other: Self) -> Self {
+        _mm256_xor_si256(self, other)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn andnot(self, other: Self) -> Self {
+        _mm256_andnot_si256(self, other)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn rotl<const N: i32, const M: i32>(self) -> Self {
+        _mm256_or_si256(_mm256_slli_epi64::<N>(self), _mm256_srli_epi64::<M>(self))
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+impl Lanes for uint64x2_t {
+    const WIDTH: usize = 2;
+
+    #[inline]
+    #[target_feature(enable = "neon")]
+    unsafe fn splat(lane: u64) -> Self {
+        vdupq_n_u64(lane)
+    }
+
+    #[inline]
+    #[target_feature(enable = "neon")]
+    unsafe fn load(lanes: &[u64]) -> Self {
+        assert!(lanes.len() >= 2);
+        vld1q_u64(lanes.as_ptr())
+    }
+
+    #[inline]
+    #[target_feature(enable = "neon")]
+    unsafe fn store(self, lanes: &mut [u64]) {
+        assert!(lanes.len() >= 2);
+        vst1q_u64(lanes.as_mut_ptr(), self)
+    }
+
+    #[inline]
+    #[target_feature(enable = "neon")]
+    unsafe fn xor(self, other: Self) -> Self {
+        veorq_u64(self, other)
+    }
+
+    #[inline]
+    #[target_feature(enable = "neon")]
+    unsafe fn andnot(self, other: Self) -> Self {
+        vbicq_u64(other, self)
+    }
+
+    #[inline]
+    #[target_feature(enable = "neon")]
+    unsafe fn rotl<const N: i32, const M: i32>(self) -> Self {
+        vorrq_u64(vshlq_n_u64::<N>(self), vshrq_n_u64::<M>(self))
+    }
+}
+
+macro_rules! rotl {
+    ($lane:expr, $n:literal) => {
+        $lane.rotl::<$n, { 64 - $n }>()
+    };
+}
+
+/// Keccak-f[1600] applied to every message's state at once.
+///
+/// # Safety
+///
+/// The CPU must support the instruction set of `L`, see `Lanes`.
+#[inline(always)]
+unsafe fn f1600<L: Lanes>(a: &mut [L; 25]) {
+    for rc in RC {
+        // θ
+        let c = [
+            a[0].xor(a[5]).xor(a[10]).xor(a[15]).xor(a[20]),
+            a[1].xor(a[6]).xor(a[11]).xor(a[16]).xor(a[21]),
+            a[2].xor(a[7]).xor(a[12]).xor(a[17]).xor(a[22]),
+            a[3].xor(a[8]).xor(a[13]).xor(a[18]).xor(a[23]),
+            a[4].xor(a[9]).xor(a[14]).xor(a[19]).xor(a[24]),
+        ];
+        let d = [
+            c[4].xor(rotl!(c[1], 1)),
+            c[0].xor(rotl!(c[2], 1)),
+            c[1].xor(rotl!(c[3], 1)),
+            c[2].xor(rotl!(c[4], 1)),
+            c[3].xor(rotl!(c[0], 1)),
+        ];
+        for (i, lane) in a.iter_mut().enumerate() {
+            *lane = lane.xor(d[i % 5]);
+        }
+
+        // ρ and π
+        let b = [
+            a[0],
+            rotl!(a[6], 44),
+            rotl!(a[12], 43),
+            rotl!(a[18], 21),
+            rotl!(a[24], 14),
+            rotl!(a[3], 28),
+            rotl!(a[9], 20),
+            rotl!(a[10], 3),
+            rotl!(a[16], 45),
+            rotl!(a[22], 61),
+            rotl!(a[1], 1),
+            rotl!(a[7], 6),
+            rotl!(a[13], 25),
+            rotl!(a[19], 8),
+            rotl!(a[20], 18),
+            rotl!(a[4], 27),
+            rotl!(a[5], 36),
+            rotl!(a[11], 10),
+            rotl!(a[17], 15),
+            rotl!(a[23], 56),
+            rotl!(a[2], 62),
+            rotl!(a[8], 55),
+            rotl!(a[14], 39),
+            rotl!(a[15], 41),
+            rotl!(a[21], 2),
+        ];
+
+        // χ
+        for y in (0..25).step_by(5) {
+            for x in 0..5 {
+                a[y + x] = b[y + x].xor(b[y + (x + 1) % 5].andnot(b[y + (x + 2) % 5]));
+            }
+        }
+
+        // ι
+        a[0] = a[0].xor(L::splat(rc));
+    }
+}
+
+/// Blocks `prefix || message` takes once padded.
+fn block_count(prefix: &[u8], message: &[u8]) -> usize {
+    (prefix.len() + message.len()) / RATE + 1
+}
+
+/// Copy block `index` of the padded `prefix || message` into `block`.
+fn padded_block(prefix: &[u8], message: &[u8], index: usize, block: &mut [u8; RATE]) {
+    let start = index * RATE;
+    let end = start + RATE;
+    *block = [0; RATE];
+    for (part, offset) in [(prefix, 0), (message, prefix.len())] {
+        let low = offset.max(start);
+        let high = (offset + part.len()).min(end);
+        if low < high {
+            block[low - start..high - start].copy_from_slice(&part[low - offset..high - offset]);
+        }
+    }
+
+    if index + 1 == block_count(prefix, message) {
+        block[prefix.len() + message.len() - start] ^= 0x01;
+        block[RATE - 1] ^= 0x80;
+    }
+}
+
+/// Hash the messages `L::WIDTH` at a time. A batch is only hashed together if every message in it
+/// pads to the same number of blocks, the rest fall back to `Keccak256::hashv`.
+///
+/// # Safety
+///
+/// The CPU must support the instruction set of `L`, see `Lanes`.
+#[inline(always)]
+unsafe fn hashv_many_with<L: Lanes>(prefix: &[u8], messages: &[&[u8]]) -> Vec<[u8; 32]> {
+    let mut hashes = Vec::with_capacity(messages.len());
+    for batch in messages.chunks(L::WIDTH) {
+        let blocks = block_count(prefix, batch[0]);
+        if batch.len() < L::WIDTH || batch.iter().any(|m| block_count(prefix, m) != blocks) {
+            hashes.extend(batch.iter().map(|m| Keccak256::hashv(&[prefix, m])));
+            continue;
+        }
+
+        let mut state = [L::splat(0); 25];
+        let mut block = [[0u8; RATE]; MAX_WIDTH];
+        let mut words = [0u64; MAX_WIDTH];
+        for index in 0..blocks {
+            for (message, block) in batch.iter().zip(block.iter_mut()) {
+                padded_block(prefix, message, index, block);
+            }
+            for (i, lane) in state.iter_mut().take(RATE / 8).enumerate() {
+                for (word, block) in words.iter_mut().zip(&block[..L::WIDTH]) {
+                    *word = u64::from_le_bytes(block[8 * i..8 * i + 8].try_into().unwrap());
+                }
+                *lane = lane.xor(L::load(&words));
+            }
+            f1600(&mut state);
+        }
+
+        let mut out = [[0u8; 32]; MAX_WIDTH];
+        for (i, lane) in state.iter().take(4).enumerate() {
+            lane.store(&mut words);
+            for (hash, word) in out.iter_mut().zip(words) {
+                hash[8 * i..8 * i + 8].copy_from_slice(&word.to_le_bytes());
+            }
+        }
+        hashes.extend_from_slice(&out[..L::WIDTH]);
+    }
+    hashes
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        sha3::Digest,
+    };
+
+    fn expected(prefix: &[u8], messages: &[&[u8]]) -> Vec<[u8; 32]> {
+        messages
+            .iter()
+            .map(|m| sha3::Keccak256::digest([prefix, m].concat()).into())
+            .collect()
+    }
+
+    #[test]
+    fn test_hashv_many() {
+        // Lengths around the block boundaries, so batches both share and mix block counts.
+        let messages: Vec<Vec<u8>> = (0..300).map(|len| vec![len as u8; len]).collect();
+        let messages: Vec<&[u8]> = messages.iter().map(|m| m.as_slice()).collect();
+        let uniform: Vec<&[u8]> = messages.iter().map(|_| messages[85]).collect();
+
+        for prefix in [&[][..], &[0], &[4; 140]] {
+            for messages in [&messages, &uniform] {
+                let expected = expected(prefix, messages);
+                assert_eq!(hashv_many(prefix, messages), expected);
+                // SAFETY: The portable instantiation needs no instruction set, it runs the same
+                // permutation on every target.
+                assert_eq!(
+                    unsafe { hashv_many_with::<u64>(prefix, messages) },
+                    expected
+                );
+            }
+        }
+
+        assert!(hashv_many(&[0], &[]).is_empty());
+    }
+
+    /// Every backend usable on this CPU, so each is checked directly rather than only the one
+    /// `hashv_many` picks.
+    #[allow(clippy::type_complexity)]
+    fn backends() -> Vec<(&'static str, fn(&[u8], &[&[u8]]) -> Vec<[u8; 32]>)> {
+        let mut backends: Vec<(_, fn(&[u8], &[&[u8]]) -> Vec<[u8; 32]>)> = vec![
+            ("dispatch", hashv_many),
+            // SAFETY: The portable instantiation needs no instruction set.
+            ("u64", |prefix, messages| unsafe {
+                hashv_many_with::<u64>(prefix, messages)
+            }),
+        ];
+        #[cfg(target_arch = "x86_64")]
+        if is_x86_feature_detected!("avx2") {
+            // SAFETY: AVX2 was just detected.
+            backends.push(("avx2", |prefix, messages| unsafe {
+                hashv_many_avx2(prefix, messages)
+            }));
+        }
+        #[cfg(target_arch = "aarch64")]
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            // SAFETY: NEON was just detected.
+            backends.push(("neon", |prefix, messages| unsafe {
+                hashv_many_neon(prefix, messages)
+            }));
+        }
+        backends
+    }
+
+    #[test]
+    fn test_rate_boundaries() {
+        // Every padded length from empty up to a few bytes past three blocks, with batches whose
+        // messages all have that length so that they are hashed side by side rather than falling
+        // back to one by one. The messages of a batch differ so lanes can't be mixed up.
+        for (name, backend) in backends() {
+            for prefix in [&[][..], &[1]] {
+                for len in 0..=3 * RATE + 4 {
+                    let messages: Vec<Vec<u8>> = (0..2 * MAX_WIDTH + 1)
+                        .map(|i| (0..len).map(|j| (i * 31 + j) as u8).collect())
+                        .collect();
+                    let messages: Vec<&[u8]> = messages.iter().map(|m| m.as_slice()).collect();
+                    assert_eq!(
+                        backend(prefix, &messages),
+                        expected(prefix, &messages),
+                        "{name} backend, prefix of {} and messages of {len} bytes",
+                        prefix.len(),
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_padded_block() {
+        let mut block = [0xff; RATE];
+        padded_block(&[9], &[7; RATE - 2], 0, &mut block);
+        assert_eq!(block[0], 9);
+        assert_eq!(block[RATE - 2], 7);
+        assert_eq!(block[RATE - 1], 0x81);
+
+        // A message filling the block exactly is followed by a block of padding alone.
+        padded_block(&[9], &[7; RATE - 1], 1, &mut block);
+        assert_eq!(block[0], 0x01);
+        assert!(block[1..RATE - 1].iter().all(|b| *b == 0));
+        assert_eq!(block[RATE - 1], 0x80);
+    }
+}
@@ -1,5 +1,8 @@
 use {
-    crate::hashers::Hasher,
+    crate::hashers::{
+        Hasher,
+        StreamingHasher,
+    },
     serde::Serialize,
     sha3::{
         Digest,
@@ -7,6 +10,12 @@ use {
     },
 };
 
+/// Keccak256 truncated to its first 20 bytes.
+///
+/// Trees built with this hasher have proofs 37.5% smaller than with `Keccak256`, which matters on
+/// target chains where calldata dominates the cost of an update. The trade-off is explicit: a 160
+/// bit digest only offers 80 bits of collision resistance, against 128 bits for the full digest,
+/// so it should only be used where forging a colliding leaf is not worth that much work.
 #[derive(Clone, Default, Debug, Eq, PartialEq, Serialize)]
 pub struct Keccak160 {}
 
@@ -22,3 +31,68 @@ impl Hasher for Keccak160 {
         hash
     }
 }
+
+impl StreamingHasher for Keccak160 {
+    type Stream = Keccak256;
+
+    fn stream() -> Self::Stream {
+        Keccak256::new()
+    }
+}
+
+impl crate::hashers::HashStream<[u8; 20]> for Keccak256 {
+    fn update(&mut self, data: &[u8]) {
+        Digest::update(self, data);
+    }
+
+    fn finalize(self) -> [u8; 20] {
+        let bytes: [u8; 32] = Digest::finalize(self).into();
+        let mut hash = [0u8; 20];
+        hash.copy_from_slice(&bytes[0..20]);
+        hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            accumulators::{
+                merkle::{
+                    MerkleAccumulator,
+                    MerklePath,
+                },
+                Accumulator,
+            },
+            hashers::keccak256::Keccak256 as FullKeccak256,
+            payload::v1::MerkleProof,
+            wire::v1::WormholeMerkleRoot,
+        },
+        borsh::{
+            BorshDeserialize,
+            BorshSerialize,
+        },
+    };
+
+    #[test]
+    fn test_keccak160() {
+        let full = FullKeccak256::hashv(&[b"hello", b"world"]);
+        assert_eq!(Keccak160::hashv(&[b"helloworld"]), full[..20]);
+
+        // Trees, proofs and the wire formats all carry 20 byte digests.
+        let items: Vec<[u8; 8]> = (0..5usize).map(|i| i.to_be_bytes()).collect();
+        let items: Vec<&[u8]> = items.iter().map(|i| i.as_ref()).collect();
+        let accumulator = MerkleAccumulator::<Keccak160>::new(&items).unwrap();
+        let proof = accumulator.prove(items[2]).unwrap();
+        let embedded = MerkleProof::new(&proof, items[2]);
+        let path = MerklePath::<Keccak160>::try_from(&embedded).unwrap();
+        assert_eq!(BorshSerialize::try_to_vec(&path).unwrap().len(), 4 + 3 * 20);
+        assert!(accumulator.check(path, embedded.data()));
+
+        let root = WormholeMerkleRoot::from_accumulator(&accumulator, 0);
+        let bytes = root.try_to_vec().unwrap();
+        assert_eq!(bytes.len(), 9 + 20);
+        assert_eq!(WormholeMerkleRoot::try_from_slice(&bytes).unwrap(), root);
+    }
+}
@@ -0,0 +1,115 @@
+use {
+    crate::hashers::{
+        FixedHash,
+        Hasher,
+        StreamingHasher,
+    },
+    serde::Serialize,
+    sha3::{
+        Digest,
+        Keccak512 as Keccak512Digest,
+    },
+};
+
+/// Keccak512, for proof formats that want a wider security margin than a 256 bit digest.
+///
+/// Proofs built with this hasher are twice the size of those built with `Keccak256`.
+#[derive(Clone, Default, Debug, Eq, PartialEq, Serialize)]
+pub struct Keccak512 {}
+
+impl Hasher for Keccak512 {
+    type Hash = FixedHash<64>;
+
+    fn hashv(data: &[impl AsRef<[u8]>]) -> FixedHash<64> {
+        let mut hasher = Keccak512Digest::new();
+        data.iter().for_each(|d| hasher.update(d));
+        FixedHash(hasher.finalize().into())
+    }
+}
+
+impl StreamingHasher for Keccak512 {
+    type Stream = Keccak512Digest;
+
+    fn stream() -> Self::Stream {
+        Keccak512Digest::new()
+    }
+}
+
+impl crate::hashers::HashStream<FixedHash<64>> for Keccak512Digest {
+    fn update(&mut self, data: &[u8]) {
+        Digest::update(self, data);
+    }
+
+    fn finalize(self) -> FixedHash<64> {
+        FixedHash(Digest::finalize(self).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            accumulators::{
+                merkle::{
+                    hash::{
+                        hash_leaf,
+                        hash_leaf_parts,
+                    },
+                    MerkleAccumulator,
+                    MerklePath,
+                },
+                Accumulator,
+            },
+            wire::v1::WormholeMerkleRoot,
+        },
+        borsh::{
+            BorshDeserialize,
+            BorshSerialize,
+        },
+    };
+
+    #[test]
+    fn test_keccak512() {
+        let data = b"helloworld";
+        let hash_a = Keccak512::hashv(&[data]);
+
+        let data = [b"hello", b"world"];
+        let hash_b = Keccak512::hashv(&data);
+
+        assert_eq!(hash_a, hash_b);
+        assert_eq!(Keccak512::hash_size(), 64);
+        assert_eq!(
+            hex::encode(Keccak512::hashv(&[b""])),
+            "0eab42de4c3ceb9235fc91acffe746b29c29a8c366b7c60e4e67c466f36a4304\
+             c00fa9caf9d87976ba469bcbe06713b435f091ef2769fb160cdab33d3670680e"
+        );
+        assert_eq!(
+            hash_leaf_parts::<Keccak512>([b"hello", b"world"]),
+            hash_leaf::<Keccak512>(b"helloworld")
+        );
+
+        // Trees, proofs and the wire formats all carry 64 byte digests.
+        let items: Vec<[u8; 8]> = (0..5usize).map(|i| i.to_be_bytes()).collect();
+        let items: Vec<&[u8]> = items.iter().map(|i| i.as_ref()).collect();
+        let accumulator = MerkleAccumulator::<Keccak512>::new(&items).unwrap();
+        let proof = accumulator.prove(items[3]).unwrap();
+        assert!(accumulator.check(proof.clone(), items[3]));
+
+        let bytes = proof.try_to_vec().unwrap();
+        assert_eq!(
+            MerklePath::<Keccak512>::try_from_slice(&bytes).unwrap(),
+            proof
+        );
+        assert_eq!(
+            serde_json::to_vec(&proof.hashes()[0]).unwrap(),
+            serde_json::to_vec(&proof.hashes()[0].0.to_vec()).unwrap()
+        );
+
+        let root = WormholeMerkleRoot::from_accumulator(&accumulator, 7);
+        let bytes = root.try_to_vec().unwrap();
+        assert_eq!(bytes.len(), 9 + 64);
+        assert_eq!(WormholeMerkleRoot::try_from_slice(&bytes).unwrap(), root);
+        assert!(WormholeMerkleRoot::<Keccak512>::try_from_slice(&bytes[..40]).is_err());
+    }
+}
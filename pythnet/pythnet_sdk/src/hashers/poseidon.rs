@@ -0,0 +1,139 @@
+use {
+    crate::hashers::Hasher,
+    ark_bn254::Fr,
+    ark_ff::{
+        BigInteger,
+        PrimeField,
+    },
+    light_poseidon::{
+        Poseidon as PoseidonSponge,
+        PoseidonHasher,
+    },
+    serde::Serialize,
+};
+
+/// Number of bytes packed into each field element. 31 bytes always fit below the BN254 scalar
+/// modulus, so packing never reduces and is injective.
+pub const BYTES_PER_ELEMENT: usize = 31;
+
+/// Largest number of field elements absorbed by a single permutation, the width of the widest
+/// circom parameter set minus its capacity element.
+pub const MAX_INPUTS: usize = 12;
+
+/// Poseidon over the BN254 scalar field with the circom parameters, for trees verified inside
+/// SNARK circuits.
+///
+/// Poseidon takes field elements rather than bytes, so the bytes passed to `hashv` are packed as
+/// follows, which a circuit must reproduce:
+///
+/// 1. All slices are concatenated into a single message `m`.
+/// 2. The first element is `len(m)`, followed by `m` split into chunks of `BYTES_PER_ELEMENT`
+///    bytes, each read as a big-endian integer. The last chunk may be shorter, the leading length
+///    keeps messages that only differ in trailing zeros apart.
+/// 3. The first `MAX_INPUTS` elements are hashed with `Poseidon(e_0, ..., e_n)`. Any remaining
+///    elements are absorbed `MAX_INPUTS - 1` at a time, chaining the previous output as the first
+///    input of the next permutation.
+///
+/// The hash is the output field element as 32 big-endian bytes. An interior node hashes 65 bytes
+/// and so costs a single permutation over 4 inputs.
+#[derive(Clone, Default, Debug, Eq, PartialEq, Serialize)]
+pub struct Poseidon {}
+
+fn permute(inputs: &[Fr]) -> Fr {
+    // Parameters only exist for 1 to MAX_INPUTS inputs, which `hashv` guarantees.
+    PoseidonSponge::<Fr>::new_circom(inputs.len())
+        .and_then(|mut sponge| sponge.hash(inputs))
+        .expect("Poseidon called with a supported number of inputs")
+}
+
+impl Hasher for Poseidon {
+    type Hash = [u8; 32];
+
+    fn hashv(data: &[impl AsRef<[u8]>]) -> [u8; 32] {
+        let message: Vec<u8> = data.iter().flat_map(|d| d.as_ref()).copied().collect();
+        let mut elements = vec![Fr::from(message.len() as u64)];
+        elements.extend(
+            message
+                .chunks(BYTES_PER_ELEMENT)
+                .map(Fr::from_be_bytes_mod_order),
+        );
+
+        let (first, rest) = elements.split_at(elements.len().min(MAX_INPUTS));
+        let state = rest
+            .chunks(MAX_INPUTS - 1)
+            .fold(permute(first), |state, chunk| {
+                permute(&[&[state], chunk].concat())
+            });
+
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&state.into_bigint().to_bytes_be());
+        hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            accumulators::{
+                merkle::MerkleAccumulator,
+                Accumulator,
+            },
+            hashers::Hasher,
+        },
+    };
+
+    #[test]
+    fn test_poseidon() {
+        let data = b"helloworld";
+        let hash_a = Poseidon::hashv(&[data]);
+
+        let data = [b"hello", b"world"];
+        let hash_b = Poseidon::hashv(&data);
+
+        assert_eq!(hash_a, hash_b);
+
+        // Vector from circomlib, `Poseidon(1, 2)`, to pin the parameters.
+        assert_eq!(
+            hex::encode(
+                permute(&[Fr::from(1u64), Fr::from(2u64)])
+                    .into_bigint()
+                    .to_bytes_be()
+            ),
+            "115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a"
+        );
+
+        // The empty message is `Poseidon(0)`, and one byte is `Poseidon(1, byte)`.
+        assert_eq!(
+            Poseidon::hashv(&[b""]).to_vec(),
+            permute(&[Fr::from(0u64)]).into_bigint().to_bytes_be()
+        );
+        assert_eq!(
+            Poseidon::hashv(&[[7u8]]).to_vec(),
+            permute(&[Fr::from(1u64), Fr::from(7u64)])
+                .into_bigint()
+                .to_bytes_be()
+        );
+
+        // Trailing zeros and long messages that need chaining are all distinct.
+        assert_ne!(Poseidon::hashv(&[[7u8]]), Poseidon::hashv(&[[7u8, 0]]));
+        assert_ne!(Poseidon::hashv(&[[7u8]]), Poseidon::hashv(&[[0u8, 7]]));
+        let long = [1u8; BYTES_PER_ELEMENT * MAX_INPUTS * 2];
+        assert_ne!(
+            Poseidon::hashv(&[&long[..]]),
+            Poseidon::hashv(&[&long[1..]])
+        );
+    }
+
+    #[test]
+    fn test_poseidon_accumulator() {
+        let items: &[&[u8]] = &[b"a", b"b", b"c"];
+        let accumulator = MerkleAccumulator::<Poseidon>::new(items).unwrap();
+        for item in items {
+            let proof = accumulator.prove(item).unwrap();
+            assert!(accumulator.check(proof, item));
+        }
+        assert!(accumulator.prove(b"d").is_err());
+    }
+}
@@ -0,0 +1,115 @@
+use {
+    crate::hashers::{
+        Hasher,
+        StreamingHasher,
+    },
+    serde::Serialize,
+    sha2::{
+        Digest,
+        Sha256 as Sha256Digest,
+    },
+};
+
+/// SHA-256, for target chains where it is cheaper to compute than Keccak256.
+///
+/// Many chains, Cosmos chains among them, expose SHA-256 as a precompile or a native host function
+/// while Keccak has to be run in the VM. Trees built with this hasher have the same layout and
+/// proof sizes as with `Keccak256`, only the roots differ.
+#[derive(Clone, Default, Debug, Eq, PartialEq, Serialize)]
+pub struct Sha256 {}
+
+impl Hasher for Sha256 {
+    type Hash = [u8; 32];
+
+    fn hashv(data: &[impl AsRef<[u8]>]) -> [u8; 32] {
+        let mut hasher = Sha256Digest::new();
+        data.iter().for_each(|d| hasher.update(d));
+        hasher.finalize().into()
+    }
+}
+
+impl StreamingHasher for Sha256 {
+    type Stream = Sha256Digest;
+
+    fn stream() -> Self::Stream {
+        Sha256Digest::new()
+    }
+}
+
+impl crate::hashers::HashStream<[u8; 32]> for Sha256Digest {
+    fn update(&mut self, data: &[u8]) {
+        Digest::update(self, data);
+    }
+
+    fn finalize(self) -> [u8; 32] {
+        Digest::finalize(self).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            accumulators::{
+                merkle::{
+                    hash::{
+                        hash_leaf,
+                        hash_null,
+                    },
+                    MerkleAccumulator,
+                },
+                Accumulator,
+            },
+            hashers::Hasher,
+        },
+    };
+
+    #[test]
+    fn test_sha256() {
+        let data = b"helloworld";
+        let hash_a = Sha256::hashv(&[data]);
+
+        let data = [b"hello", b"world"];
+        let hash_b = Sha256::hashv(&data);
+
+        assert_eq!(hash_a, hash_b);
+
+        // FIPS 180-2 vectors, and the leaf and padding hashes of a tree.
+        for (hash, expected) in [
+            (
+                Sha256::hashv(&[b""]),
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            ),
+            (
+                Sha256::hashv(&[b"abc"]),
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+            (
+                hash_leaf::<Sha256>(b""),
+                "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d",
+            ),
+            (
+                hash_leaf::<Sha256>(b"hello"),
+                "8a2a5c9b768827de5a9552c38a044c66959c68f6d2f21b5260af54d2f87db827",
+            ),
+            (
+                hash_null::<Sha256>(),
+                "dbc1b4c900ffe48d575b5da5c638040125f65db0fe3e24494b76ea986457d986",
+            ),
+        ] {
+            assert_eq!(hex::encode(hash), expected);
+        }
+    }
+
+    #[test]
+    fn test_sha256_accumulator() {
+        let items: &[&[u8]] = &[b"a", b"b", b"c"];
+        let accumulator = MerkleAccumulator::<Sha256>::new(items).unwrap();
+        for item in items {
+            let proof = accumulator.prove(item).unwrap();
+            assert!(accumulator.check(proof, item));
+        }
+        assert!(accumulator.prove(b"d").is_err());
+    }
+}
@@ -0,0 +1,77 @@
+use {
+    crate::hashers::{
+        Hasher,
+        StreamingHasher,
+    },
+    serde::Serialize,
+    sha3::{
+        Digest,
+        Sha3_256 as Sha3_256Digest,
+    },
+};
+
+/// SHA3-256 as standardized in FIPS 202, for target chains with a SHA3-256 precompile.
+///
+/// This is not the same function as `Keccak256`. Both use the Keccak-f[1600] permutation with the
+/// same rate, but FIPS 202 pads messages with the domain bits `01` before the `10*1` padding
+/// while the original Keccak submission used by Ethereum pads with `10*1` alone. The outputs are
+/// unrelated, so a root built with one hasher can only be verified with the same one.
+#[derive(Clone, Default, Debug, Eq, PartialEq, Serialize)]
+pub struct Sha3_256 {}
+
+impl Hasher for Sha3_256 {
+    type Hash = [u8; 32];
+
+    fn hashv(data: &[impl AsRef<[u8]>]) -> [u8; 32] {
+        let mut hasher = Sha3_256Digest::new();
+        data.iter().for_each(|d| hasher.update(d));
+        hasher.finalize().into()
+    }
+}
+
+impl StreamingHasher for Sha3_256 {
+    type Stream = Sha3_256Digest;
+
+    fn stream() -> Self::Stream {
+        Sha3_256Digest::new()
+    }
+}
+
+impl crate::hashers::HashStream<[u8; 32]> for Sha3_256Digest {
+    fn update(&mut self, data: &[u8]) {
+        Digest::update(self, data);
+    }
+
+    fn finalize(self) -> [u8; 32] {
+        Digest::finalize(self).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::hashers::{
+            keccak256::Keccak256,
+            Hasher,
+        },
+    };
+
+    #[test]
+    fn test_sha3_256() {
+        let data = b"helloworld";
+        let hash_a = Sha3_256::hashv(&[data]);
+
+        let data = [b"hello", b"world"];
+        let hash_b = Sha3_256::hashv(&data);
+
+        assert_eq!(hash_a, hash_b);
+
+        // The NIST padding makes every output differ from Keccak256.
+        assert_eq!(
+            hex::encode(Sha3_256::hashv(&[b""])),
+            "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a"
+        );
+        assert_ne!(Sha3_256::hashv(&[b""]), Keccak256::hashv(&[b""]));
+    }
+}
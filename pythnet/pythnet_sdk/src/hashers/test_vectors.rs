@@ -0,0 +1,380 @@
+//! Known answers for every hasher in this crate.
+//!
+//! Verifiers that reimplement these hashers on target chains, in Solidity, CosmWasm or Move, can
+//! check their output byte for byte against these vectors. Besides plain messages, each list
+//! covers the prefixed forms hashed by `accumulators::merkle::hash`: two leaves, the interior node
+//! joining them with its children in sorted order, and the padding leaf. Inputs and outputs are
+//! hex encoded so they can be copied into test suites in any language.
+
+/// The hash of `input`, whose parts are passed to `Hasher::hashv` in order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TestVector {
+    pub name:   &'static str,
+    pub input:  &'static [&'static str],
+    pub output: &'static str,
+}
+
+/// Vectors for `keccak256::Keccak256`.
+pub const KECCAK256: &[TestVector] = &[
+    TestVector {
+        name:   "empty",
+        input:  &[""],
+        output: "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+    },
+    TestVector {
+        name:   "abc",
+        input:  &["616263"],
+        output: "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45",
+    },
+    TestVector {
+        name:   "leaf(a)",
+        input:  &["00", "61"],
+        output: "9722201502e620d70d78ee63045f3493812c206b988cbbe76c28918a7364fdbd",
+    },
+    TestVector {
+        name:   "leaf(b)",
+        input:  &["00", "62"],
+        output: "e99905ac9f9583a5737a07d20a7129343f486f5f549b42c05192046188ef5f66",
+    },
+    TestVector {
+        name:   "node(leaf(a), leaf(b))",
+        input:  &[
+            "01",
+            "9722201502e620d70d78ee63045f3493812c206b988cbbe76c28918a7364fdbd",
+            "e99905ac9f9583a5737a07d20a7129343f486f5f549b42c05192046188ef5f66",
+        ],
+        output: "00d25e3ecfd5a8430c58b5562d4a00f53ce3e76001e3683df8496c541fecb9da",
+    },
+    TestVector {
+        name:   "null",
+        input:  &["02"],
+        output: "f2ee15ea639b73fa3db9b34a245bdfa015c260c598b211bf05a1ecc4b3e3b4f2",
+    },
+];
+
+/// Vectors for `keccak256_160::Keccak160`.
+pub const KECCAK160: &[TestVector] = &[
+    TestVector {
+        name:   "empty",
+        input:  &[""],
+        output: "c5d2460186f7233c927e7db2dcc703c0e500b653",
+    },
+    TestVector {
+        name:   "abc",
+        input:  &["616263"],
+        output: "4e03657aea45a94fc7d47ba826c8d667c0d1e6e3",
+    },
+    TestVector {
+        name:   "leaf(a)",
+        input:  &["00", "61"],
+        output: "9722201502e620d70d78ee63045f3493812c206b",
+    },
+    TestVector {
+        name:   "leaf(b)",
+        input:  &["00", "62"],
+        output: "e99905ac9f9583a5737a07d20a7129343f486f5f",
+    },
+    TestVector {
+        name:   "node(leaf(a), leaf(b))",
+        input:  &[
+            "01",
+            "9722201502e620d70d78ee63045f3493812c206b",
+            "e99905ac9f9583a5737a07d20a7129343f486f5f",
+        ],
+        output: "b0ec766973c10e9db260b419894379956de116b9",
+    },
+    TestVector {
+        name:   "null",
+        input:  &["02"],
+        output: "f2ee15ea639b73fa3db9b34a245bdfa015c260c5",
+    },
+];
+
+/// Vectors for `keccak512::Keccak512`.
+pub const KECCAK512: &[TestVector] = &[
+    TestVector {
+        name:   "empty",
+        input:  &[""],
+        output: "0eab42de4c3ceb9235fc91acffe746b29c29a8c366b7c60e4e67c466f36a4304c00fa9caf9d87976ba469bcbe06713b435f091ef2769fb160cdab33d3670680e",
+    },
+    TestVector {
+        name:   "abc",
+        input:  &["616263"],
+        output: "18587dc2ea106b9a1563e32b3312421ca164c7f1f07bc922a9c83d77cea3a1e5d0c69910739025372dc14ac9642629379540c17e2a65b19d77aa511a9d00bb96",
+    },
+    TestVector {
+        name:   "leaf(a)",
+        input:  &["00", "61"],
+        output: "2cbfec2c86a5fe988b70888b845627fa19844a6f6ba128e17cb94994412f7f6c6c6ba133332286c03e801896219c4ee6ef238706c550625a9ceabf3917ffeac4",
+    },
+    TestVector {
+        name:   "leaf(b)",
+        input:  &["00", "62"],
+        output: "18f41aeee493fa537f31fb03ca66345d54578061ccef7aab2745c49abc447efb7f919208ed78f95f22a5563f435a2b417295b8f370938da8a316949c151fd1e4",
+    },
+    TestVector {
+        name:   "node(leaf(a), leaf(b))",
+        input:  &["01", "18f41aeee493fa537f31fb03ca66345d54578061ccef7aab2745c49abc447efb7f919208ed78f95f22a5563f435a2b417295b8f370938da8a316949c151fd1e4", "2cbfec2c86a5fe988b70888b845627fa19844a6f6ba128e17cb94994412f7f6c6c6ba133332286c03e801896219c4ee6ef238706c550625a9ceabf3917ffeac4"],
+        output: "8fc8c141f57890b3d3f285a9aa7e4b4328ca3e9f15130fbd6e74e2c274c02d410475f1cc81336af5e1e68188b1deb3a2aef5c8cc41bbe2ed45b1ad009ab4800b",
+    },
+    TestVector {
+        name:   "null",
+        input:  &["02"],
+        output: "098b0d38aa278d44d5adef2e77985e3f29bbcaa9f1c9ee92e2186a84b3de299b0b92b3df3ac24d6921799fa06daa3b607bbc0e8dff32b17875cab1b70019dbc0",
+    },
+];
+
+/// Vectors for `sha256::Sha256`.
+pub const SHA256: &[TestVector] = &[
+    TestVector {
+        name:   "empty",
+        input:  &[""],
+        output: "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+    },
+    TestVector {
+        name:   "abc",
+        input:  &["616263"],
+        output: "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+    },
+    TestVector {
+        name:   "leaf(a)",
+        input:  &["00", "61"],
+        output: "022a6979e6dab7aa5ae4c3e5e45f7e977112a7e63593820dbec1ec738a24f93c",
+    },
+    TestVector {
+        name:   "leaf(b)",
+        input:  &["00", "62"],
+        output: "57eb35615d47f34ec714cacdf5fd74608a5e8e102724e80b24b287c0c27b6a31",
+    },
+    TestVector {
+        name:   "node(leaf(a), leaf(b))",
+        input:  &[
+            "01",
+            "022a6979e6dab7aa5ae4c3e5e45f7e977112a7e63593820dbec1ec738a24f93c",
+            "57eb35615d47f34ec714cacdf5fd74608a5e8e102724e80b24b287c0c27b6a31",
+        ],
+        output: "b137985ff484fb600db93107c77b0365c80d78f5b429ded0fd97361d077999eb",
+    },
+    TestVector {
+        name:   "null",
+        input:  &["02"],
+        output: "dbc1b4c900ffe48d575b5da5c638040125f65db0fe3e24494b76ea986457d986",
+    },
+];
+
+/// Vectors for `sha3_256::Sha3_256`.
+pub const SHA3_256: &[TestVector] = &[
+    TestVector {
+        name:   "empty",
+        input:  &[""],
+        output: "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a",
+    },
+    TestVector {
+        name:   "abc",
+        input:  &["616263"],
+        output: "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532",
+    },
+    TestVector {
+        name:   "leaf(a)",
+        input:  &["00", "61"],
+        output: "d4a31b6bbfc0f8229bcb66ba85fd3cf1fe50c5da2f4cc69edbdf1e313258aaba",
+    },
+    TestVector {
+        name:   "leaf(b)",
+        input:  &["00", "62"],
+        output: "a84d04ed0773f2f7e8d1b02c58a796ae30695a211a9cf492793e7d1627dab180",
+    },
+    TestVector {
+        name:   "node(leaf(a), leaf(b))",
+        input:  &[
+            "01",
+            "a84d04ed0773f2f7e8d1b02c58a796ae30695a211a9cf492793e7d1627dab180",
+            "d4a31b6bbfc0f8229bcb66ba85fd3cf1fe50c5da2f4cc69edbdf1e313258aaba",
+        ],
+        output: "e588b51f863965c35b7bbe832c603b198f67ea4a56183895503746d159238c2a",
+    },
+    TestVector {
+        name:   "null",
+        input:  &["02"],
+        output: "0a1e2736777f80a62beb2df72b649878481c0ca10194b832b5136befbae54017",
+    },
+];
+
+/// Vectors for `blake3::Blake3`.
+pub const BLAKE3: &[TestVector] = &[
+    TestVector {
+        name:   "empty",
+        input:  &[""],
+        output: "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262",
+    },
+    TestVector {
+        name:   "abc",
+        input:  &["616263"],
+        output: "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85",
+    },
+    TestVector {
+        name:   "leaf(a)",
+        input:  &["00", "61"],
+        output: "1ff621ee3430890e869728995a6cee4f2b0b61271bfc19b0092b06d778750ae8",
+    },
+    TestVector {
+        name:   "leaf(b)",
+        input:  &["00", "62"],
+        output: "3acbabc85b6b9ceff22334abe02e3752f93875f0c2fcdc7ef48ded6117df4170",
+    },
+    TestVector {
+        name:   "node(leaf(a), leaf(b))",
+        input:  &[
+            "01",
+            "1ff621ee3430890e869728995a6cee4f2b0b61271bfc19b0092b06d778750ae8",
+            "3acbabc85b6b9ceff22334abe02e3752f93875f0c2fcdc7ef48ded6117df4170",
+        ],
+        output: "6564e87d8619ea09c801c567c641d47fe817ae3b2cf80685cde2eb6557247eca",
+    },
+    TestVector {
+        name:   "null",
+        input:  &["02"],
+        output: "ab13bedf42e84bae0f7c62c7dd6a8ada571e8829bed6ea558217f0361b5e25d0",
+    },
+];
+
+/// Vectors for `poseidon::Poseidon`.
+pub const POSEIDON: &[TestVector] = &[
+    TestVector {
+        name:   "empty",
+        input:  &[""],
+        output: "2a09a9fd93c590c26b91effbb2499f07e8f7aa12e2b4940a3aed2411cb65e11c",
+    },
+    TestVector {
+        name:   "abc",
+        input:  &["616263"],
+        output: "06aeed70a622ae7251cfe9b9a1d46fa8ecb33fe3eae998c33779fafd5bd8f571",
+    },
+    TestVector {
+        name:   "leaf(a)",
+        input:  &["00", "61"],
+        output: "27456135441a7172ee6b181906967d0f9fe48b265167b39107720f3e59cee296",
+    },
+    TestVector {
+        name:   "leaf(b)",
+        input:  &["00", "62"],
+        output: "0a671a78b11290e7fb6b2a16df4c00639771302fe54feb6e98676402c24c6b2d",
+    },
+    TestVector {
+        name:   "node(leaf(a), leaf(b))",
+        input:  &[
+            "01",
+            "0a671a78b11290e7fb6b2a16df4c00639771302fe54feb6e98676402c24c6b2d",
+            "27456135441a7172ee6b181906967d0f9fe48b265167b39107720f3e59cee296",
+        ],
+        output: "238240fd58807eaae2a1a40409c665c8a1cae172bb3042653ae8e19cf2412f75",
+    },
+    TestVector {
+        name:   "null",
+        input:  &["02"],
+        output: "115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a",
+    },
+];
+
+/// Vectors for `prime::PrimeHasher`.
+pub const PRIME: &[TestVector] = &[
+    TestVector {
+        name:   "empty",
+        input:  &[""],
+        output: "0000000000000000000000009fbe92e3",
+    },
+    TestVector {
+        name:   "abc",
+        input:  &["616263"],
+        output: "000000000000000000000000e6ae2535",
+    },
+    TestVector {
+        name:   "leaf(a)",
+        input:  &["00", "61"],
+        output: "000000000000000000000000bfec59d9",
+    },
+    TestVector {
+        name:   "leaf(b)",
+        input:  &["00", "62"],
+        output: "000000000000000000000000088af107",
+    },
+    TestVector {
+        name:   "node(leaf(a), leaf(b))",
+        input:  &[
+            "01",
+            "000000000000000000000000088af107",
+            "000000000000000000000000bfec59d9",
+        ],
+        output: "000000000000000000000000ebdd7cdf",
+    },
+    TestVector {
+        name:   "null",
+        input:  &["02"],
+        output: "0000000000000000000000002dfc722b",
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "blake3")]
+    use crate::hashers::blake3::Blake3;
+    #[cfg(feature = "poseidon")]
+    use crate::hashers::poseidon::Poseidon;
+    use {
+        super::*,
+        crate::{
+            accumulators::merkle::hash::{
+                hash_leaf,
+                hash_node,
+                hash_null,
+            },
+            hashers::{
+                keccak256::Keccak256,
+                keccak256_160::Keccak160,
+                keccak512::Keccak512,
+                prime::PrimeHasher,
+                sha256::Sha256,
+                sha3_256::Sha3_256,
+                Hasher,
+            },
+        },
+    };
+
+    fn check<H: Hasher>(vectors: &[TestVector]) {
+        for vector in vectors {
+            let input: Vec<Vec<u8>> = vector
+                .input
+                .iter()
+                .map(|part| hex::decode(part).unwrap())
+                .collect();
+            assert_eq!(
+                hex::encode(H::hashv(&input)),
+                vector.output,
+                "{}",
+                vector.name
+            );
+        }
+
+        // The prefixed vectors are what the tree hashing primitives produce.
+        let a = hash_leaf::<H>(b"a");
+        let b = hash_leaf::<H>(b"b");
+        assert_eq!(vectors[2].output, hex::encode(a));
+        assert_eq!(vectors[3].output, hex::encode(b));
+        assert_eq!(vectors[4].output, hex::encode(hash_node::<H>(&a, &b)));
+        assert_eq!(vectors[5].output, hex::encode(hash_null::<H>()));
+    }
+
+    #[test]
+    fn test_vectors() {
+        check::<Keccak256>(KECCAK256);
+        check::<Keccak160>(KECCAK160);
+        check::<Keccak512>(KECCAK512);
+        check::<Sha256>(SHA256);
+        check::<Sha3_256>(SHA3_256);
+        check::<PrimeHasher>(PRIME);
+        #[cfg(feature = "blake3")]
+        check::<Blake3>(BLAKE3);
+        #[cfg(feature = "poseidon")]
+        check::<Poseidon>(POSEIDON);
+    }
+}
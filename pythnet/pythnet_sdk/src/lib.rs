@@ -1,6 +1,25 @@
+//! Without the default `std` feature only the `alloc` based verification core is available:
+//! hashing, Merkle paths and roots, along with the wire formats with the `wire` feature. Building
+//! accumulators requires `std`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+// Without `std` the wire formats take `format!` and `vec!` from `alloc`.
+#[cfg_attr(all(feature = "wire", not(feature = "std")), macro_use)]
+extern crate alloc;
+
 pub mod accumulators;
 pub mod hashers;
+#[cfg(feature = "std")]
+pub mod messages;
+#[cfg(feature = "std")]
 pub mod payload;
+pub mod staleness;
+#[cfg(any(test, feature = "proptest"))]
+pub mod test_utils;
+#[cfg(feature = "wire")]
+pub mod wire;
+#[cfg(feature = "std")]
 pub mod wormhole;
 
 pub(crate) type Pubkey = [u8; 32];
@@ -6,7 +6,11 @@
 
 use {
     borsh::BorshSerialize,
-    serde::Serialize,
+    serde::{
+        Deserialize,
+        Serialize,
+    },
+    std::borrow::Cow,
     wormhole_sdk::Vaa,
 };
 
@@ -29,7 +33,20 @@ pub struct AccumulatorProof<'a> {
 // requiring a new module to be defined. So for example, new accounts can be added
 // to the end of `AccumulatorAccount` without moving to a `v1`.
 pub mod v1 {
-    use super::*;
+    use {
+        super::*,
+        crate::{
+            accumulators::merkle::{
+                MerklePath,
+                PathTooLong,
+                MAX_PATH_LENGTH,
+            },
+            hashers::{
+                keccak256::Keccak256,
+                Hasher,
+            },
+        },
+    };
 
     // A hash of some data.
     pub type Hash = [u8; 32];
@@ -51,10 +68,62 @@ pub mod v1 {
         digest:     Hash,
     }
 
-    #[derive(Serialize)]
-    pub struct MerkleProof<'a> {
-        proof: &'a [Hash],
-        data:  &'a [u8],
+    /// A proof of `data` against a tree hashed with `H`.
+    ///
+    /// Each hash of the proof takes the full digest width of `H`, so trees built with a truncated
+    /// hasher such as `Keccak160` produce proofs that are smaller by the same ratio.
+    ///
+    /// Proofs built for a payload borrow the path and data they embed, while deserialized proofs
+    /// own them. The path is bounded by `MAX_PATH_LENGTH` while it is deserialized, like any
+    /// other MerklePath.
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(bound = "")]
+    pub struct MerkleProof<'a, H: Hasher = Keccak256> {
+        proof: Cow<'a, MerklePath<H>>,
+        data:  Cow<'a, [u8]>,
+    }
+
+    impl<'a, H: Hasher> MerkleProof<'a, H> {
+        /// Embed a proof generated by a MerkleAccumulator for `data`. Paths from an accumulator
+        /// are never longer than `MAX_PATH_LENGTH`, use `try_from` to embed any other path.
+        pub fn new(proof: &'a MerklePath<H>, data: &'a [u8]) -> Self {
+            Self {
+                proof: Cow::Borrowed(proof),
+                data:  Cow::Borrowed(data),
+            }
+        }
+
+        pub fn path(&self) -> &MerklePath<H> {
+            &self.proof
+        }
+
+        pub fn data(&self) -> &[u8] {
+            &self.data
+        }
+    }
+
+    // Both directions share the length limit of a deserialized MerklePath, so a proof that could
+    // not be read back from a payload can never be embedded in one.
+    impl<'a, H: Hasher> TryFrom<(&'a MerklePath<H>, &'a [u8])> for MerkleProof<'a, H> {
+        type Error = PathTooLong;
+
+        fn try_from((proof, data): (&'a MerklePath<H>, &'a [u8])) -> Result<Self, Self::Error> {
+            match proof.hashes().len() {
+                len if len > MAX_PATH_LENGTH => Err(PathTooLong { len }),
+                _ => Ok(Self::new(proof, data)),
+            }
+        }
+    }
+
+    impl<'a, H: Hasher> TryFrom<&MerkleProof<'a, H>> for MerklePath<H> {
+        type Error = PathTooLong;
+
+        fn try_from(proof: &MerkleProof<'a, H>) -> Result<Self, Self::Error> {
+            match proof.proof.hashes().len() {
+                len if len > MAX_PATH_LENGTH => Err(PathTooLong { len }),
+                _ => Ok((*proof.proof).clone()),
+            }
+        }
     }
 
     #[derive(Serialize)]
@@ -0,0 +1,113 @@
+//! Checks for whether a published value is recent enough to act on.
+//!
+//! On-chain programs and off-chain services read the current time from different places, so the
+//! check is written against a small `Clock` trait. Both then share the same rules for what counts
+//! as stale, including the edge cases around clock skew and overflow.
+
+/// A source of the current Unix timestamp, in seconds.
+pub trait Clock {
+    fn unix_timestamp(&self) -> i64;
+}
+
+/// A fixed timestamp, for callers that already read the time or for tests.
+impl Clock for i64 {
+    fn unix_timestamp(&self) -> i64 {
+        *self
+    }
+}
+
+#[cfg(target_os = "solana")]
+impl Clock for solana_program::clock::Clock {
+    fn unix_timestamp(&self) -> i64 {
+        self.unix_timestamp
+    }
+}
+
+/// The host's wall clock.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn unix_timestamp(&self) -> i64 {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default();
+        i64::try_from(now.as_secs()).unwrap_or(i64::MAX)
+    }
+}
+
+/// Accepts values published at most `max_age` seconds before the clock's current time.
+///
+/// A `publish_time` ahead of the clock is treated as fresh, as it usually means the local clock
+/// lags the publisher's rather than that the value is old.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StalenessCheck {
+    pub max_age: u64,
+}
+
+impl StalenessCheck {
+    pub fn new(max_age: u64) -> Self {
+        Self { max_age }
+    }
+
+    /// Seconds elapsed between `publish_time` and the clock's current time, zero if it lies in
+    /// the future.
+    pub fn age(publish_time: i64, clock: &impl Clock) -> u64 {
+        let now = clock.unix_timestamp();
+        if now <= publish_time {
+            return 0;
+        }
+        now.abs_diff(publish_time)
+    }
+
+    pub fn is_fresh(&self, publish_time: i64, clock: &impl Clock) -> bool {
+        Self::age(publish_time, clock) <= self.max_age
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_age() {
+        assert_eq!(StalenessCheck::age(100, &160), 60);
+        assert_eq!(StalenessCheck::age(100, &100), 0);
+        assert_eq!(StalenessCheck::age(100, &40), 0);
+
+        // Ages spanning the whole range of i64 don't overflow.
+        assert_eq!(StalenessCheck::age(i64::MIN, &i64::MAX), u64::MAX);
+        assert_eq!(StalenessCheck::age(i64::MAX, &i64::MIN), 0);
+    }
+
+    #[test]
+    fn test_is_fresh() {
+        let check = StalenessCheck::new(60);
+        assert!(check.is_fresh(100, &100));
+        assert!(check.is_fresh(100, &160));
+        assert!(!check.is_fresh(100, &161));
+
+        // Published ahead of the local clock.
+        assert!(check.is_fresh(200, &100));
+
+        // A zero max age only accepts values published this second.
+        let check = StalenessCheck::new(0);
+        assert!(check.is_fresh(100, &100));
+        assert!(!check.is_fresh(100, &101));
+
+        let check = StalenessCheck::new(u64::MAX);
+        assert!(check.is_fresh(i64::MIN, &i64::MAX));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_system_clock() {
+        let check = StalenessCheck::new(60);
+        let now = SystemClock.unix_timestamp();
+        assert!(now > 0);
+        assert!(check.is_fresh(now, &SystemClock));
+        assert!(!check.is_fresh(now - 3600, &SystemClock));
+    }
+}
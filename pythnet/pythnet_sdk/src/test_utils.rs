@@ -0,0 +1,282 @@
+//! Proptest strategies for the types in this crate.
+//!
+//! These are the strategies this crate fuzzes its own accumulators with, exported behind the
+//! `proptest` feature, or its older alias `test-utils`, so that downstream verifiers can be tested
+//! against the same inputs.
+
+use {
+    crate::{
+        accumulators::{
+            merkle::{
+                MerkleAccumulator,
+                MerklePath,
+                MAX_PATH_LENGTH,
+            },
+            Accumulator,
+        },
+        hashers::keccak256::Keccak256,
+        messages::{
+            FeedId,
+            FeedMetadataMessage,
+            FundingRateMessage,
+            MarketStatus,
+            MarketStatusMessage,
+            Message,
+            PriceFeedMessage,
+            PublisherStakeCap,
+            PublisherStakeCapsMessage,
+            TwapMessage,
+            PRICE_EXPONENT_RANGE,
+        },
+    },
+    proptest::prelude::*,
+    std::collections::BTreeSet,
+};
+
+/// A MerkleAccumulator along with the distinct items it was built from.
+#[derive(Debug)]
+pub struct MerkleAccumulatorDataWrapper {
+    pub accumulator: MerkleAccumulator,
+    pub data:        BTreeSet<Vec<u8>>,
+}
+
+impl Arbitrary for MerkleAccumulatorDataWrapper {
+    type Parameters = usize;
+
+    fn arbitrary_with(size: Self::Parameters) -> Self::Strategy {
+        let size = size.saturating_add(1);
+        prop::collection::vec(
+            prop::collection::vec(any::<u8>(), 1..=10),
+            size..=size.saturating_add(100),
+        )
+        .prop_map(|v| {
+            let data: BTreeSet<Vec<u8>> = v.into_iter().collect();
+            let accumulator =
+                MerkleAccumulator::<Keccak256>::from_set(data.iter().map(|i| i.as_ref())).unwrap();
+            MerkleAccumulatorDataWrapper { accumulator, data }
+        })
+        .boxed()
+    }
+
+    type Strategy = BoxedStrategy<Self>;
+}
+
+// Paths are never longer than `MAX_PATH_LENGTH`, anything longer is rejected by deserialization
+// and verification before its hashes are even looked at. The parameter raises the minimum length.
+impl Arbitrary for MerklePath<Keccak256> {
+    type Parameters = usize;
+
+    fn arbitrary_with(size: Self::Parameters) -> Self::Strategy {
+        let size = size.saturating_add(1).min(MAX_PATH_LENGTH);
+        prop::collection::vec(
+            prop::collection::vec(any::<u8>(), 32),
+            size..=MAX_PATH_LENGTH,
+        )
+        .prop_map(|v| {
+            let v = v.into_iter().map(|i| i.try_into().unwrap()).collect();
+            MerklePath::new(v)
+        })
+        .boxed()
+    }
+
+    type Strategy = BoxedStrategy<Self>;
+}
+
+// Messages draw their feed ids from a small set so that generated batches contain several
+// messages for the same feed, as real slots do.
+fn feed_id() -> impl Strategy<Value = FeedId> {
+    (0..16u8).prop_map(|i| FeedId([i; 32]))
+}
+
+// Generated prices pass `PriceFeedMessage::try_new`, as the ones published by Pythnet do.
+impl Arbitrary for PriceFeedMessage {
+    type Parameters = ();
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (
+            feed_id(),
+            (any::<i64>(), 0..=i64::MAX as u64),
+            PRICE_EXPONENT_RANGE,
+            (any::<i64>(), 0..3600i64),
+            (any::<i64>(), 0..=i64::MAX as u64),
+        )
+            .prop_map(
+                |(
+                    feed_id,
+                    (price, conf),
+                    exponent,
+                    (publish_time, window),
+                    (ema_price, ema_conf),
+                )| {
+                    PriceFeedMessage::try_new(
+                        feed_id,
+                        price,
+                        conf,
+                        exponent,
+                        publish_time,
+                        publish_time.saturating_sub(window),
+                        ema_price,
+                        ema_conf,
+                    )
+                    .unwrap()
+                },
+            )
+            .boxed()
+    }
+
+    type Strategy = BoxedStrategy<Self>;
+}
+
+impl Arbitrary for TwapMessage {
+    type Parameters = ();
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (
+            feed_id(),
+            any::<i128>(),
+            any::<u128>(),
+            any::<u64>(),
+            any::<u64>(),
+            any::<i64>(),
+            0..3600i64,
+        )
+            .prop_map(
+                |(
+                    feed_id,
+                    cumulative_price,
+                    cumulative_conf,
+                    num_down_slots,
+                    publish_slot,
+                    publish_time,
+                    window,
+                )| TwapMessage {
+                    feed_id,
+                    cumulative_price,
+                    cumulative_conf,
+                    num_down_slots,
+                    publish_slot,
+                    publish_time,
+                    prev_publish_time: publish_time.saturating_sub(window),
+                },
+            )
+            .boxed()
+    }
+
+    type Strategy = BoxedStrategy<Self>;
+}
+
+impl Arbitrary for PublisherStakeCapsMessage {
+    type Parameters = ();
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (
+            any::<i64>(),
+            prop::collection::vec((any::<[u8; 32]>(), any::<u64>()), 0..64),
+        )
+            .prop_map(|(publish_time, caps)| PublisherStakeCapsMessage {
+                publish_time,
+                caps: caps
+                    .into_iter()
+                    .map(|(publisher, cap)| PublisherStakeCap { publisher, cap })
+                    .collect(),
+            })
+            .boxed()
+    }
+
+    type Strategy = BoxedStrategy<Self>;
+}
+
+impl Arbitrary for MarketStatusMessage {
+    type Parameters = ();
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (
+            feed_id(),
+            prop_oneof![
+                Just(MarketStatus::Unknown),
+                Just(MarketStatus::Open),
+                Just(MarketStatus::Halted),
+                Just(MarketStatus::Auction),
+            ],
+            any::<i64>(),
+        )
+            .prop_map(|(feed_id, status, publish_time)| MarketStatusMessage {
+                feed_id,
+                status,
+                publish_time,
+            })
+            .boxed()
+    }
+
+    type Strategy = BoxedStrategy<Self>;
+}
+
+impl Arbitrary for FundingRateMessage {
+    type Parameters = ();
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (
+            feed_id(),
+            any::<i64>(),
+            -12..0i32,
+            prop_oneof![Just(3600u64), Just(8 * 3600u64)],
+            any::<i64>(),
+        )
+            .prop_map(
+                |(feed_id, rate, rate_expo, interval, publish_time)| FundingRateMessage {
+                    feed_id,
+                    rate,
+                    rate_expo,
+                    interval,
+                    publish_time,
+                },
+            )
+            .boxed()
+    }
+
+    type Strategy = BoxedStrategy<Self>;
+}
+
+impl Arbitrary for FeedMetadataMessage {
+    type Parameters = ();
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (
+            feed_id(),
+            "[A-Za-z]{1,8}\\.[A-Z0-9]{1,8}/[A-Z]{3}",
+            prop_oneof![Just("Crypto"), Just("Equity"), Just("FX"), Just("Metal")],
+            "[A-Z]{3}",
+            any::<i64>(),
+        )
+            .prop_map(
+                |(feed_id, symbol, asset_type, quote_currency, publish_time)| FeedMetadataMessage {
+                    feed_id,
+                    symbol,
+                    asset_type: asset_type.to_string(),
+                    quote_currency,
+                    publish_time,
+                },
+            )
+            .boxed()
+    }
+
+    type Strategy = BoxedStrategy<Self>;
+}
+
+impl Arbitrary for Message {
+    type Parameters = ();
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        prop_oneof![
+            4 => any::<PriceFeedMessage>().prop_map(Message::PriceFeedMessage),
+            4 => any::<TwapMessage>().prop_map(Message::TwapMessage),
+            1 => any::<PublisherStakeCapsMessage>().prop_map(Message::PublisherStakeCapsMessage),
+            1 => any::<MarketStatusMessage>().prop_map(Message::MarketStatusMessage),
+            2 => any::<FundingRateMessage>().prop_map(Message::FundingRateMessage),
+            1 => any::<FeedMetadataMessage>().prop_map(Message::FeedMetadataMessage),
+        ]
+        .boxed()
+    }
+
+    type Strategy = BoxedStrategy<Self>;
+}
@@ -0,0 +1,2424 @@
+//! Definition of the Wire Formats.
+//!
+//! The types in this module are encoded byte for byte the way target chain contracts parse them,
+//! so unlike the Borsh derives used elsewhere all integers are big-endian. As with `payload` the
+//! formats must stay backwards compatible and are versioned for breaking changes.
+//!
+//! Only `alloc` is required: errors and writers come from `borsh::maybestd::io`, which is
+//! `std::io` when the `std` feature is enabled. Compression needs `std`.
+
+use borsh::maybestd::io;
+
+/// Machine-readable descriptions of the wire formats.
+///
+/// Every wire type describes its byte layout as a `Schema`. Serialized with serde, for example to
+/// JSON, the schemas are a source of truth that implementations in other languages can be
+/// generated from or checked against.
+pub mod schema {
+    use {
+        alloc::{
+            string::String,
+            vec::Vec,
+        },
+        serde::Serialize,
+    };
+
+    /// The byte layout of a wire type.
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+    pub struct Schema {
+        pub name:   &'static str,
+        pub fields: Vec<Field>,
+    }
+
+    impl Schema {
+        /// Encoded size in bytes, with a repeated field holding no elements. This is the total
+        /// size of fixed size types and the minimum size of the others.
+        pub fn size(&self) -> usize {
+            self.fields
+                .iter()
+                .filter(|field| field.repeat.is_none())
+                .map(|field| field.size)
+                .sum()
+        }
+    }
+
+    /// A field of a wire type, stored at `offset` bytes from the start of the encoding.
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+    pub struct Field {
+        pub name:     &'static str,
+        pub offset:   usize,
+        pub size:     usize,
+        pub encoding: Encoding,
+        /// Hex encoding of the only value the field may hold, for magic numbers and tags.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub constant: Option<String>,
+        /// Set if the field holds any number of `size` byte elements instead of exactly one.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub repeat:   Option<Repeat>,
+    }
+
+    /// How many elements a repeated field holds. Only the last field of a type can be repeated,
+    /// so every field still starts at a fixed offset.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum Repeat {
+        /// As many as the value of the named count field, which precedes it.
+        Count(&'static str),
+        /// As many as fit in the rest of the encoding.
+        Trailing,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum Encoding {
+        /// Opaque bytes.
+        Bytes,
+        U8,
+        U16Be,
+        U32Be,
+        U64Be,
+        /// A digest of the hasher the type is instantiated with.
+        Hash,
+        /// A 2 byte big-endian emitter chain id followed by a 32 byte emitter address.
+        DataSource,
+        /// A 4 byte big-endian length followed by that many bytes. `size` only covers the length,
+        /// so repeated frames are read one at a time.
+        Frame,
+    }
+
+    /// Lay out `fields` one after another, given as name, size, encoding and optional constant.
+    pub(crate) fn fields(fields: &[(&'static str, usize, Encoding, Option<&[u8]>)]) -> Vec<Field> {
+        let mut offset = 0;
+        fields
+            .iter()
+            .map(|&(name, size, encoding, constant)| {
+                let field = Field {
+                    name,
+                    offset,
+                    size,
+                    encoding,
+                    constant: constant.map(hex::encode),
+                    repeat: None,
+                };
+                offset += size;
+                field
+            })
+            .collect()
+    }
+
+    /// Lay out `fields` as `fields` does, with the last one repeated as given by `repeat`.
+    pub(crate) fn repeated_fields(
+        fields: &[(&'static str, usize, Encoding, Option<&[u8]>)],
+        repeat: Repeat,
+    ) -> Vec<Field> {
+        let mut fields = self::fields(fields);
+        if let Some(last) = fields.last_mut() {
+            last.repeat = Some(repeat);
+        }
+        fields
+    }
+}
+
+/// Encoded length of a wire type, computed without serializing it.
+///
+/// Lets relayers check transaction size limits and servers allocate exact buffers before
+/// encoding. Always equal to the length of the Borsh encoding.
+pub trait SerializedSize {
+    fn serialized_size(&self) -> usize;
+}
+
+/// Deserialize a `T` that must take up the whole of `bytes`.
+///
+/// `BorshDeserialize::deserialize` stops at the end of the value and leaves the rest to the caller,
+/// so a payload with bytes appended would still be accepted. Verifiers parsing untrusted payloads
+/// should use this instead, so that every payload has exactly one accepted encoding.
+pub fn from_slice_strict<T: borsh::BorshDeserialize>(bytes: &[u8]) -> io::Result<T> {
+    let mut buf = bytes;
+    let value = T::deserialize(&mut buf)?;
+    if !buf.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "{} trailing bytes at offset {}",
+                buf.len(),
+                bytes.len() - buf.len()
+            ),
+        ));
+    }
+    Ok(value)
+}
+
+/// Reads `N` bytes of `field` at `offset`, advancing it.
+pub(crate) fn read<const N: usize>(
+    buf: &[u8],
+    offset: &mut usize,
+    field: &str,
+) -> io::Result<[u8; N]> {
+    let bytes = buf
+        .get(*offset..*offset + N)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Not enough bytes for {field} at offset {offset}: expected {N} but got {}",
+                    buf.len().saturating_sub(*offset)
+                ),
+            )
+        })?;
+    *offset += N;
+    Ok(bytes)
+}
+
+/// Integers tagged with the byte order they are encoded in.
+///
+/// Wire structs use these for every integer field instead of primitives, so the byte order of a
+/// field is part of its type rather than something each hand-written impl has to get right.
+pub mod endian {
+    use {
+        super::SerializedSize,
+        borsh::{
+            maybestd::io::{
+                self,
+                Error,
+                ErrorKind::InvalidData,
+                Write,
+            },
+            BorshDeserialize,
+            BorshSerialize,
+        },
+    };
+
+    macro_rules! endian_int {
+        ($name:ident, $int:ty, $to_bytes:ident, $from_bytes:ident) => {
+            #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+            #[repr(transparent)]
+            pub struct $name(pub $int);
+
+            impl $name {
+                pub fn get(self) -> $int {
+                    self.0
+                }
+
+                /// The integer encoded in `bytes`.
+                pub fn from_bytes(bytes: [u8; core::mem::size_of::<$int>()]) -> Self {
+                    Self(<$int>::$from_bytes(bytes))
+                }
+            }
+
+            impl From<$int> for $name {
+                fn from(value: $int) -> Self {
+                    Self(value)
+                }
+            }
+
+            impl From<$name> for $int {
+                fn from(value: $name) -> Self {
+                    value.0
+                }
+            }
+
+            impl SerializedSize for $name {
+                fn serialized_size(&self) -> usize {
+                    core::mem::size_of::<$int>()
+                }
+            }
+
+            impl BorshSerialize for $name {
+                fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+                    writer.write_all(&self.0.$to_bytes())
+                }
+            }
+
+            impl BorshDeserialize for $name {
+                fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+                    const SIZE: usize = core::mem::size_of::<$int>();
+                    if buf.len() < SIZE {
+                        return Err(Error::new(InvalidData, "Not enough bytes"));
+                    }
+
+                    let (bytes, rest) = buf.split_at(SIZE);
+                    *buf = rest;
+                    let mut value = [0u8; SIZE];
+                    value.copy_from_slice(bytes);
+                    Ok(Self::from_bytes(value))
+                }
+            }
+        };
+    }
+
+    endian_int!(BeU16, u16, to_be_bytes, from_be_bytes);
+    endian_int!(BeU32, u32, to_be_bytes, from_be_bytes);
+    endian_int!(BeU64, u64, to_be_bytes, from_be_bytes);
+    endian_int!(LeU16, u16, to_le_bytes, from_le_bytes);
+    endian_int!(LeU32, u32, to_le_bytes, from_le_bytes);
+    endian_int!(LeU64, u64, to_le_bytes, from_le_bytes);
+}
+
+pub mod v1 {
+    #[cfg(feature = "std")]
+    use crate::accumulators::merkle::MerkleAccumulator;
+    use {
+        super::{
+            endian::BeU32,
+            schema::{
+                self,
+                Encoding,
+                Repeat,
+                Schema,
+            },
+            SerializedSize,
+        },
+        crate::{
+            accumulators::merkle::{
+                AbsenceProof,
+                AggregatedProof,
+                MerkleMultiProof,
+                MerklePath,
+            },
+            hashers::{
+                keccak256::Keccak256,
+                Hasher,
+            },
+        },
+        alloc::{
+            borrow::Cow,
+            vec::Vec,
+        },
+        borsh::{
+            maybestd::io::{
+                self,
+                Error,
+                ErrorKind::InvalidData,
+                Write,
+            },
+            BorshDeserialize,
+            BorshSerialize,
+        },
+        core::ops::RangeInclusive,
+    };
+
+    /// Magic number of the Wormhole message payloads committing to an accumulator ("AUWV").
+    pub const ACCUMULATOR_UPDATE_WORMHOLE_VERIFICATION_MAGIC: [u8; 4] = *b"AUWV";
+
+    /// Update type of a payload committing to the root of a MerkleAccumulator.
+    pub const WORMHOLE_MERKLE_UPDATE_TYPE: u8 = 0;
+
+    /// Update type of a payload committing to the roots of several MerkleAccumulators.
+    pub const WORMHOLE_MULTI_MERKLE_UPDATE_TYPE: u8 = 1;
+
+    /// The Wormhole message payload committing to the root of a MerkleAccumulator.
+    ///
+    /// Layout:
+    ///
+    /// ```text
+    /// 4 bytes:  magic number
+    /// 1 byte:   update type
+    /// 4 bytes:  storage id
+    /// N bytes:  root hash
+    /// ```
+    #[derive(Clone, Debug, Default, PartialEq, Eq)]
+    pub struct WormholeMerkleRoot<H: Hasher = Keccak256> {
+        pub storage_id: BeU32,
+        pub root:       H::Hash,
+    }
+
+    impl<H: Hasher> WormholeMerkleRoot<H> {
+        #[cfg(feature = "std")]
+        pub fn from_accumulator(accumulator: &MerkleAccumulator<H>, storage_id: u32) -> Self {
+            Self {
+                storage_id: storage_id.into(),
+                root:       accumulator.root,
+            }
+        }
+
+        pub fn schema() -> Schema {
+            Schema {
+                name:   "WormholeMerkleRoot",
+                fields: schema::fields(&[
+                    (
+                        "magic",
+                        4,
+                        Encoding::Bytes,
+                        Some(&ACCUMULATOR_UPDATE_WORMHOLE_VERIFICATION_MAGIC),
+                    ),
+                    (
+                        "update_type",
+                        1,
+                        Encoding::U8,
+                        Some(&[WORMHOLE_MERKLE_UPDATE_TYPE]),
+                    ),
+                    ("storage_id", 4, Encoding::U32Be, None),
+                    ("root", H::hash_size(), Encoding::Hash, None),
+                ]),
+            }
+        }
+    }
+
+    /// Schemas of every type in this version of the wire format, with the default hasher,
+    /// followed by one schema per governance action.
+    pub fn schemas() -> Vec<Schema> {
+        let mut schemas = vec![
+            WormholeMerkleRoot::<Keccak256>::schema(),
+            WormholeMerkleRoots::<Keccak256>::schema(),
+            CompressedAccumulatorUpdateData::schema(),
+            UpdateDataStream::schema(),
+            Proof::<Keccak256>::schema(),
+        ];
+        schemas.extend(super::governance::GovernanceInstruction::schemas());
+        schemas
+    }
+
+    impl<H: Hasher> SerializedSize for WormholeMerkleRoot<H> {
+        fn serialized_size(&self) -> usize {
+            9 + H::hash_size()
+        }
+    }
+
+    impl<H: Hasher> BorshSerialize for WormholeMerkleRoot<H> {
+        fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+            writer.write_all(&ACCUMULATOR_UPDATE_WORMHOLE_VERIFICATION_MAGIC)?;
+            writer.write_all(&[WORMHOLE_MERKLE_UPDATE_TYPE])?;
+            self.storage_id.serialize(writer)?;
+            writer.write_all(self.root.as_ref())
+        }
+    }
+
+    // Errors name the field that failed to parse and its byte offset in the payload, so that a
+    // corrupted payload can be diagnosed from logs alone.
+    impl<H: Hasher> BorshDeserialize for WormholeMerkleRoot<H> {
+        fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+            if buf.len() < 9 {
+                return Err(Error::new(
+                    InvalidData,
+                    format!(
+                        "Not enough bytes for header at offset 0: expected 9 but got {}",
+                        buf.len()
+                    ),
+                ));
+            }
+
+            let magic: &[u8] = &buf[0..4];
+            if magic != ACCUMULATOR_UPDATE_WORMHOLE_VERIFICATION_MAGIC {
+                return Err(Error::new(
+                    InvalidData,
+                    format!(
+                        "Magic mismatch at offset 0. Expected {ACCUMULATOR_UPDATE_WORMHOLE_VERIFICATION_MAGIC:?} but got {magic:?}"
+                    ),
+                ));
+            }
+
+            let update_type = buf[4];
+            if update_type != WORMHOLE_MERKLE_UPDATE_TYPE {
+                return Err(Error::new(
+                    InvalidData,
+                    format!("Unsupported update type {update_type} at offset 4"),
+                ));
+            }
+
+            let storage_id = BeU32::deserialize(&mut &buf[5..9])?;
+            let root = buf
+                .get(9..9 + H::hash_size())
+                .and_then(|root| H::Hash::try_from(root).ok())
+                .ok_or_else(|| {
+                    Error::new(
+                        InvalidData,
+                        format!(
+                            "Not enough bytes for root at offset 9: expected {} but got {}",
+                            H::hash_size(),
+                            buf.len() - 9
+                        ),
+                    )
+                })?;
+            *buf = &buf[9 + H::hash_size()..];
+            Ok(Self { storage_id, root })
+        }
+    }
+
+    /// The Wormhole message payload committing to the roots of several MerkleAccumulators at once,
+    /// for example prices and governance, so that a single VAA covers all of them.
+    ///
+    /// Roots are identified by their position, which each storage id assigns a meaning to.
+    ///
+    /// Layout:
+    ///
+    /// ```text
+    /// 4 bytes:  magic number
+    /// 1 byte:   update type
+    /// 4 bytes:  storage id
+    /// 1 byte:   number of roots
+    /// N bytes:  root hashes
+    /// ```
+    #[derive(Clone, Debug, Default, PartialEq, Eq)]
+    pub struct WormholeMerkleRoots<H: Hasher = Keccak256> {
+        pub storage_id: BeU32,
+        pub roots:      Vec<H::Hash>,
+    }
+
+    impl<H: Hasher> WormholeMerkleRoots<H> {
+        #[cfg(feature = "std")]
+        pub fn from_accumulators(accumulators: &[&MerkleAccumulator<H>], storage_id: u32) -> Self {
+            Self {
+                storage_id: storage_id.into(),
+                roots:      accumulators
+                    .iter()
+                    .map(|accumulator| accumulator.root)
+                    .collect(),
+            }
+        }
+
+        pub fn schema() -> Schema {
+            Schema {
+                name:   "WormholeMerkleRoots",
+                fields: schema::repeated_fields(
+                    &[
+                        (
+                            "magic",
+                            4,
+                            Encoding::Bytes,
+                            Some(&ACCUMULATOR_UPDATE_WORMHOLE_VERIFICATION_MAGIC),
+                        ),
+                        (
+                            "update_type",
+                            1,
+                            Encoding::U8,
+                            Some(&[WORMHOLE_MULTI_MERKLE_UPDATE_TYPE]),
+                        ),
+                        ("storage_id", 4, Encoding::U32Be, None),
+                        ("count", 1, Encoding::U8, None),
+                        ("roots", H::hash_size(), Encoding::Hash, None),
+                    ],
+                    Repeat::Count("count"),
+                ),
+            }
+        }
+    }
+
+    impl<H: Hasher> From<WormholeMerkleRoot<H>> for WormholeMerkleRoots<H> {
+        fn from(root: WormholeMerkleRoot<H>) -> Self {
+            Self {
+                storage_id: root.storage_id,
+                roots:      vec![root.root],
+            }
+        }
+    }
+
+    impl<H: Hasher> SerializedSize for WormholeMerkleRoots<H> {
+        fn serialized_size(&self) -> usize {
+            10 + self.roots.len() * H::hash_size()
+        }
+    }
+
+    impl<H: Hasher> BorshSerialize for WormholeMerkleRoots<H> {
+        fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+            let count = u8::try_from(self.roots.len())
+                .map_err(|_| Error::new(InvalidData, "Too many roots for a single payload"))?;
+            writer.write_all(&ACCUMULATOR_UPDATE_WORMHOLE_VERIFICATION_MAGIC)?;
+            writer.write_all(&[WORMHOLE_MULTI_MERKLE_UPDATE_TYPE])?;
+            self.storage_id.serialize(writer)?;
+            writer.write_all(&[count])?;
+            self.roots
+                .iter()
+                .try_for_each(|root| writer.write_all(root.as_ref()))
+        }
+    }
+
+    impl<H: Hasher> BorshDeserialize for WormholeMerkleRoots<H> {
+        fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+            if buf.len() < 10 {
+                return Err(Error::new(
+                    InvalidData,
+                    format!(
+                        "Not enough bytes for header at offset 0: expected 10 but got {}",
+                        buf.len()
+                    ),
+                ));
+            }
+
+            let magic: &[u8] = &buf[0..4];
+            if magic != ACCUMULATOR_UPDATE_WORMHOLE_VERIFICATION_MAGIC {
+                return Err(Error::new(
+                    InvalidData,
+                    format!(
+                        "Magic mismatch at offset 0. Expected {ACCUMULATOR_UPDATE_WORMHOLE_VERIFICATION_MAGIC:?} but got {magic:?}"
+                    ),
+                ));
+            }
+
+            let update_type = buf[4];
+            if update_type != WORMHOLE_MULTI_MERKLE_UPDATE_TYPE {
+                return Err(Error::new(
+                    InvalidData,
+                    format!("Unsupported update type {update_type} at offset 4"),
+                ));
+            }
+
+            let storage_id = BeU32::deserialize(&mut &buf[5..9])?;
+            let count = buf[9] as usize;
+            let roots = (0..count)
+                .map(|i| {
+                    let offset = 10 + i * H::hash_size();
+                    buf.get(offset..offset + H::hash_size())
+                        .and_then(|root| H::Hash::try_from(root).ok())
+                        .ok_or_else(|| {
+                            Error::new(
+                                InvalidData,
+                                format!(
+                                    "Not enough bytes for root {i} at offset {offset}: expected {} but got {}",
+                                    H::hash_size(),
+                                    buf.len().saturating_sub(offset)
+                                ),
+                            )
+                        })
+                })
+                .collect::<io::Result<Vec<_>>>()?;
+            *buf = &buf[10 + count * H::hash_size()..];
+            Ok(Self { storage_id, roots })
+        }
+    }
+
+    /// Any Wormhole message payload committing to accumulator roots, for consumers that accept
+    /// both the single and the multi root payloads.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum WormholePayload<H: Hasher = Keccak256> {
+        MerkleRoot(WormholeMerkleRoot<H>),
+        MerkleRoots(WormholeMerkleRoots<H>),
+    }
+
+    impl<H: Hasher> WormholePayload<H> {
+        pub fn storage_id(&self) -> u32 {
+            match self {
+                Self::MerkleRoot(root) => root.storage_id.get(),
+                Self::MerkleRoots(roots) => roots.storage_id.get(),
+            }
+        }
+
+        /// The committed roots, in payload order. A single root payload has exactly one.
+        pub fn roots(&self) -> &[H::Hash] {
+            match self {
+                Self::MerkleRoot(root) => core::slice::from_ref(&root.root),
+                Self::MerkleRoots(roots) => &roots.roots,
+            }
+        }
+    }
+
+    impl<H: Hasher> SerializedSize for WormholePayload<H> {
+        fn serialized_size(&self) -> usize {
+            match self {
+                Self::MerkleRoot(root) => root.serialized_size(),
+                Self::MerkleRoots(roots) => roots.serialized_size(),
+            }
+        }
+    }
+
+    impl<H: Hasher> BorshSerialize for WormholePayload<H> {
+        fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+            match self {
+                Self::MerkleRoot(root) => root.serialize(writer),
+                Self::MerkleRoots(roots) => roots.serialize(writer),
+            }
+        }
+    }
+
+    impl<H: Hasher> BorshDeserialize for WormholePayload<H> {
+        fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+            // Both payloads start with the same magic number, only the update type differs. The
+            // payload deserializers report malformed headers.
+            match buf.get(4) {
+                Some(&WORMHOLE_MULTI_MERKLE_UPDATE_TYPE) => {
+                    WormholeMerkleRoots::deserialize(buf).map(Self::MerkleRoots)
+                }
+                _ => WormholeMerkleRoot::deserialize(buf).map(Self::MerkleRoot),
+            }
+        }
+    }
+
+    /// Magic number of compressed accumulator update payloads ("AUCD").
+    pub const COMPRESSED_ACCUMULATOR_UPDATE_MAGIC: [u8; 4] = *b"AUCD";
+
+    /// Largest update `CompressedAccumulatorUpdateData::decompress` will produce, so that a small
+    /// malicious payload cannot expand into an arbitrarily large allocation.
+    pub const MAX_DECOMPRESSED_UPDATE_SIZE: usize = 4 * 1024 * 1024;
+
+    /// Compression applied to a `CompressedAccumulatorUpdateData`.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    #[repr(u8)]
+    pub enum UpdateCompression {
+        None = 0,
+        Zstd = 1,
+    }
+
+    /// A serialized accumulator update, compressed for transport to bandwidth-constrained
+    /// clients. Clients decompress it before submitting the update on-chain, contracts only ever
+    /// see raw updates.
+    ///
+    /// Layout:
+    ///
+    /// ```text
+    /// 4 bytes:  magic number
+    /// 1 byte:   compression, 0 for none and 1 for zstd
+    /// N bytes:  compressed update, up to the end of the payload
+    /// ```
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct CompressedAccumulatorUpdateData {
+        pub compression: UpdateCompression,
+        pub data:        Vec<u8>,
+    }
+
+    impl CompressedAccumulatorUpdateData {
+        /// Compress a serialized update with Zstandard at the given level, see
+        /// `zstd::compression_level_range`.
+        #[cfg(feature = "compression")]
+        pub fn compress(update: &[u8], level: i32) -> io::Result<Self> {
+            Ok(Self {
+                compression: UpdateCompression::Zstd,
+                data:        zstd::bulk::compress(update, level)?,
+            })
+        }
+
+        pub fn decompress(&self) -> io::Result<Vec<u8>> {
+            match self.compression {
+                UpdateCompression::None => Ok(self.data.clone()),
+                #[cfg(feature = "compression")]
+                UpdateCompression::Zstd => {
+                    use std::io::Read;
+
+                    // Read one byte past the limit so that oversized updates are detected without
+                    // ever buffering more than that.
+                    let mut update = Vec::new();
+                    zstd::stream::Decoder::new(&self.data[..])?
+                        .take(MAX_DECOMPRESSED_UPDATE_SIZE as u64 + 1)
+                        .read_to_end(&mut update)?;
+                    if update.len() > MAX_DECOMPRESSED_UPDATE_SIZE {
+                        return Err(Error::new(
+                            InvalidData,
+                            format!(
+                                "Decompressed update exceeds {MAX_DECOMPRESSED_UPDATE_SIZE} bytes"
+                            ),
+                        ));
+                    }
+                    Ok(update)
+                }
+                #[cfg(not(feature = "compression"))]
+                UpdateCompression::Zstd => Err(Error::new(
+                    InvalidData,
+                    "Update is compressed but the `compression` feature is disabled",
+                )),
+            }
+        }
+
+        /// Whether `bytes` start like a compressed update rather than a raw one.
+        pub fn is_compressed(bytes: &[u8]) -> bool {
+            bytes.starts_with(&COMPRESSED_ACCUMULATOR_UPDATE_MAGIC)
+        }
+
+        /// The raw update held in `bytes`, which may or may not be compressed. Raw updates are
+        /// returned without copying.
+        pub fn decompress_update(bytes: &[u8]) -> io::Result<Cow<'_, [u8]>> {
+            if !Self::is_compressed(bytes) {
+                return Ok(Cow::Borrowed(bytes));
+            }
+
+            super::from_slice_strict::<Self>(bytes)?
+                .decompress()
+                .map(Cow::Owned)
+        }
+
+        pub fn schema() -> Schema {
+            Schema {
+                name:   "CompressedAccumulatorUpdateData",
+                fields: schema::repeated_fields(
+                    &[
+                        (
+                            "magic",
+                            4,
+                            Encoding::Bytes,
+                            Some(&COMPRESSED_ACCUMULATOR_UPDATE_MAGIC),
+                        ),
+                        ("compression", 1, Encoding::U8, None),
+                        ("data", 1, Encoding::Bytes, None),
+                    ],
+                    Repeat::Trailing,
+                ),
+            }
+        }
+    }
+
+    impl SerializedSize for CompressedAccumulatorUpdateData {
+        fn serialized_size(&self) -> usize {
+            5 + self.data.len()
+        }
+    }
+
+    impl BorshSerialize for CompressedAccumulatorUpdateData {
+        fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+            writer.write_all(&COMPRESSED_ACCUMULATOR_UPDATE_MAGIC)?;
+            writer.write_all(&[self.compression as u8])?;
+            writer.write_all(&self.data)
+        }
+    }
+
+    impl BorshDeserialize for CompressedAccumulatorUpdateData {
+        fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+            if buf.len() < 5 {
+                return Err(Error::new(
+                    InvalidData,
+                    format!(
+                        "Not enough bytes for header at offset 0: expected 5 but got {}",
+                        buf.len()
+                    ),
+                ));
+            }
+
+            let magic: &[u8] = &buf[0..4];
+            if magic != COMPRESSED_ACCUMULATOR_UPDATE_MAGIC {
+                return Err(Error::new(
+                    InvalidData,
+                    format!(
+                        "Magic mismatch at offset 0. Expected {COMPRESSED_ACCUMULATOR_UPDATE_MAGIC:?} but got {magic:?}"
+                    ),
+                ));
+            }
+
+            let compression = match buf[4] {
+                0 => UpdateCompression::None,
+                1 => UpdateCompression::Zstd,
+                compression => {
+                    return Err(Error::new(
+                        InvalidData,
+                        format!("Unsupported compression {compression} at offset 4"),
+                    ))
+                }
+            };
+
+            let data = buf[5..].to_vec();
+            *buf = &[];
+            Ok(Self { compression, data })
+        }
+    }
+
+    /// Magic number of a blob holding several framed accumulator updates ("AUFS").
+    pub const FRAMED_UPDATES_MAGIC: [u8; 4] = *b"AUFS";
+
+    /// Pack several serialized accumulator updates into one blob, so that clients can split them
+    /// apart again with `UpdateDataStream`.
+    ///
+    /// Layout:
+    ///
+    /// ```text
+    /// 4 bytes:  magic number
+    /// repeated:
+    ///   4 bytes:  length of the update
+    ///   N bytes:  update
+    /// ```
+    pub fn frame_updates(updates: &[impl AsRef<[u8]>]) -> io::Result<Vec<u8>> {
+        let mut framed = FRAMED_UPDATES_MAGIC.to_vec();
+        for update in updates {
+            let update = update.as_ref();
+            let len = u32::try_from(update.len())
+                .map_err(|_| Error::new(InvalidData, "Update too large to frame"))?;
+            BeU32(len).serialize(&mut framed)?;
+            framed.extend_from_slice(update);
+        }
+        Ok(framed)
+    }
+
+    /// Parses the updates packed by `frame_updates` one at a time, borrowing each from the blob.
+    ///
+    /// A malformed frame is returned as an error once, after which the stream ends.
+    #[derive(Clone, Debug)]
+    pub struct UpdateDataStream<'a> {
+        data:   &'a [u8],
+        offset: usize,
+    }
+
+    impl<'a> UpdateDataStream<'a> {
+        pub fn new(data: &'a [u8]) -> io::Result<Self> {
+            if !data.starts_with(&FRAMED_UPDATES_MAGIC) {
+                return Err(Error::new(
+                    InvalidData,
+                    format!("Magic mismatch at offset 0. Expected {FRAMED_UPDATES_MAGIC:?}"),
+                ));
+            }
+
+            Ok(Self { data, offset: 4 })
+        }
+
+        /// Schema of the blobs produced by `frame_updates`.
+        pub fn schema() -> Schema {
+            Schema {
+                name:   "FramedUpdates",
+                fields: schema::repeated_fields(
+                    &[
+                        ("magic", 4, Encoding::Bytes, Some(&FRAMED_UPDATES_MAGIC)),
+                        ("updates", 4, Encoding::Frame, None),
+                    ],
+                    Repeat::Trailing,
+                ),
+            }
+        }
+
+        pub fn next_update(&mut self) -> Option<io::Result<&'a [u8]>> {
+            if self.offset == self.data.len() {
+                return None;
+            }
+
+            let mut rest = &self.data[self.offset..];
+            let update = BeU32::deserialize(&mut rest)
+                .ok()
+                .and_then(|len| rest.get(..len.get() as usize));
+            let offset = self.offset;
+            Some(match update {
+                Some(update) => {
+                    self.offset += 4 + update.len();
+                    Ok(update)
+                }
+                None => {
+                    self.offset = self.data.len();
+                    Err(Error::new(
+                        InvalidData,
+                        format!("Truncated update frame at offset {offset}"),
+                    ))
+                }
+            })
+        }
+    }
+
+    impl<'a> Iterator for UpdateDataStream<'a> {
+        type Item = io::Result<&'a [u8]>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            self.next_update()
+        }
+    }
+
+    /// Tags of proof types defined in this crate.
+    pub const CORE_PROOF_TAGS: RangeInclusive<u8> = 0..=63;
+
+    /// Tags of proof types being trialled, which may change or be withdrawn.
+    pub const EXPERIMENTAL_PROOF_TAGS: RangeInclusive<u8> = 64..=127;
+
+    /// Tags left to third parties experimenting with their own proof types, never assigned here.
+    pub const VENDOR_PROOF_TAGS: RangeInclusive<u8> = 128..=255;
+
+    /// Tag of `Proof::MerklePath`.
+    pub const MERKLE_PATH_PROOF: u8 = 0;
+
+    /// Tag of `Proof::MerkleMultiProof`.
+    pub const MERKLE_MULTI_PROOF: u8 = 1;
+
+    /// Tag of `Proof::AggregatedProof`.
+    pub const AGGREGATED_PROOF: u8 = 2;
+
+    /// Tag of `Proof::AbsenceProof`.
+    pub const ABSENCE_PROOF: u8 = 3;
+
+    /// A proof of any type, tagged with the type so that consumers can dispatch on it.
+    ///
+    /// Proofs are length-prefixed so that a tag the parser doesn't know is skipped over and kept as
+    /// `Unknown`, and encodes back to the same bytes. Third parties can carry proofs of their own
+    /// under a vendor tag through code built against this crate without changing it.
+    ///
+    /// Layout:
+    ///
+    /// ```text
+    /// 1 byte:   tag
+    /// 4 bytes:  length of the proof
+    /// N bytes:  proof, Borsh encoded for the known types
+    /// ```
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum Proof<H: Hasher = Keccak256> {
+        MerklePath(MerklePath<H>),
+        MerkleMultiProof(MerkleMultiProof<H>),
+        AggregatedProof(AggregatedProof<H>),
+        AbsenceProof(AbsenceProof<H>),
+        /// A proof with an unassigned tag, kept as the encoded bytes.
+        Unknown {
+            tag:   u8,
+            bytes: Vec<u8>,
+        },
+    }
+
+    impl<H: Hasher> Proof<H> {
+        pub fn tag(&self) -> u8 {
+            match self {
+                Self::MerklePath(_) => MERKLE_PATH_PROOF,
+                Self::MerkleMultiProof(_) => MERKLE_MULTI_PROOF,
+                Self::AggregatedProof(_) => AGGREGATED_PROOF,
+                Self::AbsenceProof(_) => ABSENCE_PROOF,
+                Self::Unknown { tag, .. } => *tag,
+            }
+        }
+
+        /// Schema of proofs of every type, which only differ in the tag and the proof bytes.
+        pub fn schema() -> Schema {
+            Schema {
+                name:   "Proof",
+                fields: schema::repeated_fields(
+                    &[
+                        ("tag", 1, Encoding::U8, None),
+                        ("length", 4, Encoding::U32Be, None),
+                        ("proof", 1, Encoding::Bytes, None),
+                    ],
+                    Repeat::Count("length"),
+                ),
+            }
+        }
+    }
+
+    impl<H: Hasher> SerializedSize for Proof<H> {
+        fn serialized_size(&self) -> usize {
+            5 + match self {
+                Self::MerklePath(proof) => proof.serialized_size(),
+                Self::MerkleMultiProof(proof) => proof.serialized_size(),
+                Self::AggregatedProof(proof) => proof.serialized_size(),
+                Self::AbsenceProof(proof) => proof.serialized_size(),
+                Self::Unknown { bytes, .. } => bytes.len(),
+            }
+        }
+    }
+
+    impl<H: Hasher> BorshSerialize for Proof<H>
+    where
+        H::Hash: BorshSerialize,
+    {
+        fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+            // Encoding a known tag as unknown would not parse back to the same proof.
+            if let Self::Unknown { tag, .. } = self {
+                if *tag <= ABSENCE_PROOF {
+                    return Err(Error::new(
+                        InvalidData,
+                        format!("Proof tag {tag} is assigned to a known proof type"),
+                    ));
+                }
+            }
+
+            // The proof is written straight after its length, which is known up front.
+            let len = u32::try_from(self.serialized_size() - 5)
+                .map_err(|_| Error::new(InvalidData, "Proof too large to encode"))?;
+            writer.write_all(&[self.tag()])?;
+            BeU32(len).serialize(writer)?;
+            match self {
+                Self::MerklePath(proof) => proof.serialize(writer),
+                Self::MerkleMultiProof(proof) => proof.serialize(writer),
+                Self::AggregatedProof(proof) => proof.serialize(writer),
+                Self::AbsenceProof(proof) => proof.serialize(writer),
+                Self::Unknown { bytes, .. } => writer.write_all(bytes),
+            }
+        }
+    }
+
+    impl<H: Hasher> BorshDeserialize for Proof<H>
+    where
+        H::Hash: BorshDeserialize,
+    {
+        fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+            let offset = &mut 0;
+            let [tag] = super::read::<1>(buf, offset, "proof tag")?;
+            let len = BeU32::from_bytes(super::read(buf, offset, "proof length")?).get() as usize;
+            // A length close to `u32::MAX` would wrap on 32-bit targets.
+            let end = len.checked_add(5).ok_or_else(|| {
+                Error::new(
+                    InvalidData,
+                    format!("Proof length {len} at offset 1 overflows usize"),
+                )
+            })?;
+            let mut bytes = buf.get(5..end).ok_or_else(|| {
+                Error::new(
+                    InvalidData,
+                    format!(
+                        "Not enough bytes for proof at offset 5: expected {len} but got {}",
+                        buf.len() - 5
+                    ),
+                )
+            })?;
+            *buf = &buf[end..];
+
+            let proof = match tag {
+                MERKLE_PATH_PROOF => Self::MerklePath(BorshDeserialize::deserialize(&mut bytes)?),
+                MERKLE_MULTI_PROOF => {
+                    Self::MerkleMultiProof(BorshDeserialize::deserialize(&mut bytes)?)
+                }
+                AGGREGATED_PROOF => {
+                    Self::AggregatedProof(BorshDeserialize::deserialize(&mut bytes)?)
+                }
+                ABSENCE_PROOF => Self::AbsenceProof(BorshDeserialize::deserialize(&mut bytes)?),
+                tag => {
+                    return Ok(Self::Unknown {
+                        tag,
+                        bytes: bytes.to_vec(),
+                    })
+                }
+            };
+            if !bytes.is_empty() {
+                return Err(Error::new(
+                    InvalidData,
+                    format!("{} trailing bytes after proof with tag {tag}", bytes.len()),
+                ));
+            }
+            Ok(proof)
+        }
+    }
+}
+
+/// Governance instructions, carried as Wormhole message payloads from the governance emitter to
+/// every target chain contract.
+pub mod governance {
+    use {
+        super::{
+            endian::{
+                BeU16,
+                BeU32,
+                BeU64,
+            },
+            read,
+            schema::{
+                self,
+                Encoding,
+                Repeat,
+                Schema,
+            },
+            SerializedSize,
+        },
+        alloc::vec::Vec,
+        borsh::{
+            maybestd::io::{
+                self,
+                Error,
+                ErrorKind::InvalidData,
+                Write,
+            },
+            BorshDeserialize,
+            BorshSerialize,
+        },
+    };
+
+    /// Magic number of governance instructions ("PTGM").
+    pub const GOVERNANCE_MAGIC: [u8; 4] = *b"PTGM";
+
+    /// Module of the instructions executed by target chain contracts.
+    pub const TARGET_CHAIN_MODULE: u8 = 1;
+
+    /// Target chain id of instructions meant for every chain.
+    pub const ALL_CHAINS: u16 = 0;
+
+    /// A Wormhole emitter whose price updates a contract accepts.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct DataSource {
+        pub emitter_chain:   BeU16,
+        pub emitter_address: [u8; 32],
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum GovernanceAction {
+        /// Upgrade the contract. The payload identifies the new code and is specific to the
+        /// target chain, for example a code id on CosmWasm chains.
+        UpgradeContract {
+            payload: Vec<u8>,
+        },
+        /// Accept a new governance emitter, proven by a VAA it signed requesting the transfer.
+        AuthorizeGovernanceDataSourceTransfer {
+            claim_vaa: Vec<u8>,
+        },
+        SetDataSources {
+            data_sources: Vec<DataSource>,
+        },
+        /// Set the update fee to `value * 10^expo`.
+        SetFee {
+            value: BeU64,
+            expo:  BeU64,
+        },
+        SetValidPeriod {
+            valid_seconds: BeU64,
+        },
+        /// Signed by the new governance emitter and embedded in
+        /// `AuthorizeGovernanceDataSourceTransfer`. The index must increase with every transfer
+        /// so that old requests cannot be replayed.
+        RequestGovernanceDataSourceTransfer {
+            governance_data_source_index: BeU32,
+        },
+    }
+
+    impl GovernanceAction {
+        pub fn action_id(&self) -> u8 {
+            match self {
+                Self::UpgradeContract { .. } => 0,
+                Self::AuthorizeGovernanceDataSourceTransfer { .. } => 1,
+                Self::SetDataSources { .. } => 2,
+                Self::SetFee { .. } => 3,
+                Self::SetValidPeriod { .. } => 4,
+                Self::RequestGovernanceDataSourceTransfer { .. } => 5,
+            }
+        }
+    }
+
+    /// A governance instruction for target chain contracts.
+    ///
+    /// Layout:
+    ///
+    /// ```text
+    /// 4 bytes:  magic number
+    /// 1 byte:   module
+    /// 1 byte:   action
+    /// 2 bytes:  target chain id, 0 for all chains
+    /// N bytes:  action payload
+    /// ```
+    ///
+    /// The action payloads are:
+    ///
+    /// ```text
+    /// UpgradeContract:                        chain specific, up to the end of the payload
+    /// AuthorizeGovernanceDataSourceTransfer:  claim VAA, up to the end of the payload
+    /// SetDataSources:                         1 byte count, then 2 byte chain and 32 byte address each
+    /// SetFee:                                 8 bytes value, 8 bytes exponent
+    /// SetValidPeriod:                         8 bytes seconds
+    /// RequestGovernanceDataSourceTransfer:    4 bytes index
+    /// ```
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct GovernanceInstruction {
+        pub target_chain_id: BeU16,
+        pub action:          GovernanceAction,
+    }
+
+    impl GovernanceInstruction {
+        /// Whether the instruction applies to the chain with the given Wormhole id.
+        pub fn targets(&self, chain_id: u16) -> bool {
+            let target_chain_id = self.target_chain_id.get();
+            target_chain_id == ALL_CHAINS || target_chain_id == chain_id
+        }
+
+        /// Schemas of every action, each describing a whole instruction carrying that action.
+        pub fn schemas() -> Vec<Schema> {
+            vec![
+                action_schema(
+                    "GovernanceInstruction::UpgradeContract",
+                    &[0],
+                    &[("payload", 1, Encoding::Bytes, None)],
+                    Some(Repeat::Trailing),
+                ),
+                action_schema(
+                    "GovernanceInstruction::AuthorizeGovernanceDataSourceTransfer",
+                    &[1],
+                    &[("claim_vaa", 1, Encoding::Bytes, None)],
+                    Some(Repeat::Trailing),
+                ),
+                action_schema(
+                    "GovernanceInstruction::SetDataSources",
+                    &[2],
+                    &[
+                        ("count", 1, Encoding::U8, None),
+                        ("data_sources", 34, Encoding::DataSource, None),
+                    ],
+                    Some(Repeat::Count("count")),
+                ),
+                action_schema(
+                    "GovernanceInstruction::SetFee",
+                    &[3],
+                    &[
+                        ("value", 8, Encoding::U64Be, None),
+                        ("expo", 8, Encoding::U64Be, None),
+                    ],
+                    None,
+                ),
+                action_schema(
+                    "GovernanceInstruction::SetValidPeriod",
+                    &[4],
+                    &[("valid_seconds", 8, Encoding::U64Be, None)],
+                    None,
+                ),
+                action_schema(
+                    "GovernanceInstruction::RequestGovernanceDataSourceTransfer",
+                    &[5],
+                    &[("governance_data_source_index", 4, Encoding::U32Be, None)],
+                    None,
+                ),
+            ]
+        }
+    }
+
+    /// The instruction header with the given action id, followed by the action payload `fields`.
+    fn action_schema(
+        name: &'static str,
+        action_id: &'static [u8],
+        fields: &[(&'static str, usize, Encoding, Option<&[u8]>)],
+        repeat: Option<Repeat>,
+    ) -> Schema {
+        let mut header: Vec<(&'static str, usize, Encoding, Option<&[u8]>)> = vec![
+            ("magic", 4, Encoding::Bytes, Some(&GOVERNANCE_MAGIC)),
+            ("module", 1, Encoding::U8, Some(&[TARGET_CHAIN_MODULE])),
+            ("action", 1, Encoding::U8, Some(action_id)),
+            ("target_chain_id", 2, Encoding::U16Be, None),
+        ];
+        header.extend_from_slice(fields);
+        Schema {
+            name,
+            fields: match repeat {
+                Some(repeat) => schema::repeated_fields(&header, repeat),
+                None => schema::fields(&header),
+            },
+        }
+    }
+
+    impl SerializedSize for GovernanceInstruction {
+        fn serialized_size(&self) -> usize {
+            8 + match &self.action {
+                GovernanceAction::UpgradeContract { payload } => payload.len(),
+                GovernanceAction::AuthorizeGovernanceDataSourceTransfer { claim_vaa } => {
+                    claim_vaa.len()
+                }
+                GovernanceAction::SetDataSources { data_sources } => 1 + 34 * data_sources.len(),
+                GovernanceAction::SetFee { .. } => 16,
+                GovernanceAction::SetValidPeriod { .. } => 8,
+                GovernanceAction::RequestGovernanceDataSourceTransfer { .. } => 4,
+            }
+        }
+    }
+
+    impl BorshSerialize for GovernanceInstruction {
+        fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+            writer.write_all(&GOVERNANCE_MAGIC)?;
+            writer.write_all(&[TARGET_CHAIN_MODULE, self.action.action_id()])?;
+            self.target_chain_id.serialize(writer)?;
+            match &self.action {
+                GovernanceAction::UpgradeContract { payload } => writer.write_all(payload),
+                GovernanceAction::AuthorizeGovernanceDataSourceTransfer { claim_vaa } => {
+                    writer.write_all(claim_vaa)
+                }
+                GovernanceAction::SetDataSources { data_sources } => {
+                    let count = u8::try_from(data_sources.len())
+                        .map_err(|_| Error::new(InvalidData, "Too many data sources"))?;
+                    writer.write_all(&[count])?;
+                    data_sources.iter().try_for_each(|source| {
+                        source.emitter_chain.serialize(writer)?;
+                        writer.write_all(&source.emitter_address)
+                    })
+                }
+                GovernanceAction::SetFee { value, expo } => {
+                    value.serialize(writer)?;
+                    expo.serialize(writer)
+                }
+                GovernanceAction::SetValidPeriod { valid_seconds } => {
+                    valid_seconds.serialize(writer)
+                }
+                GovernanceAction::RequestGovernanceDataSourceTransfer {
+                    governance_data_source_index,
+                } => governance_data_source_index.serialize(writer),
+            }
+        }
+    }
+
+    // Like the accumulator payloads, errors name the field that failed to parse and its offset.
+    impl BorshDeserialize for GovernanceInstruction {
+        fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+            let offset = &mut 0;
+            let magic = read::<4>(buf, offset, "magic")?;
+            if magic != GOVERNANCE_MAGIC {
+                return Err(Error::new(
+                    InvalidData,
+                    format!("Magic mismatch at offset 0. Expected {GOVERNANCE_MAGIC:?} but got {magic:?}"),
+                ));
+            }
+
+            let [module] = read::<1>(buf, offset, "module")?;
+            if module != TARGET_CHAIN_MODULE {
+                return Err(Error::new(
+                    InvalidData,
+                    format!("Unsupported module {module} at offset 4"),
+                ));
+            }
+
+            let [action] = read::<1>(buf, offset, "action")?;
+            let target_chain_id = BeU16::from_bytes(read(buf, offset, "target chain id")?);
+            let action = match action {
+                0 => GovernanceAction::UpgradeContract {
+                    payload: buf[*offset..].to_vec(),
+                },
+                1 => GovernanceAction::AuthorizeGovernanceDataSourceTransfer {
+                    claim_vaa: buf[*offset..].to_vec(),
+                },
+                2 => {
+                    let [count] = read::<1>(buf, offset, "data source count")?;
+                    let data_sources = (0..count)
+                        .map(|_| {
+                            Ok(DataSource {
+                                emitter_chain:   BeU16::from_bytes(read(
+                                    buf,
+                                    offset,
+                                    "emitter chain",
+                                )?),
+                                emitter_address: read(buf, offset, "emitter address")?,
+                            })
+                        })
+                        .collect::<io::Result<_>>()?;
+                    GovernanceAction::SetDataSources { data_sources }
+                }
+                3 => GovernanceAction::SetFee {
+                    value: BeU64::from_bytes(read(buf, offset, "fee value")?),
+                    expo:  BeU64::from_bytes(read(buf, offset, "fee exponent")?),
+                },
+                4 => GovernanceAction::SetValidPeriod {
+                    valid_seconds: BeU64::from_bytes(read(buf, offset, "valid period")?),
+                },
+                5 => GovernanceAction::RequestGovernanceDataSourceTransfer {
+                    governance_data_source_index: BeU32::from_bytes(read(
+                        buf,
+                        offset,
+                        "governance data source index",
+                    )?),
+                },
+                action => {
+                    return Err(Error::new(
+                        InvalidData,
+                        format!("Unsupported action {action} at offset 5"),
+                    ))
+                }
+            };
+
+            // The first two actions extend to the end of the payload.
+            *buf = match action {
+                GovernanceAction::UpgradeContract { .. }
+                | GovernanceAction::AuthorizeGovernanceDataSourceTransfer { .. } => &[],
+                _ => &buf[*offset..],
+            };
+            Ok(Self {
+                target_chain_id,
+                action,
+            })
+        }
+    }
+}
+
+/// Canonical payloads of every wire type, with their decoded fields.
+///
+/// Implementations of the wire formats in other languages can run these vectors as their test
+/// suite: every `VALID` payload must decode to its fields and encode back to the same bytes, and
+/// every `INVALID` payload must be rejected. Payloads and byte fields are hex encoded, integers
+/// are decimal, lists are comma separated and governance actions are named by their variant.
+/// `check` and `check_invalid` are the reference harness.
+pub mod conformance {
+    use {
+        super::{
+            from_slice_strict,
+            governance::{
+                GovernanceAction,
+                GovernanceInstruction,
+            },
+            v1::{
+                CompressedAccumulatorUpdateData,
+                WormholeMerkleRoot,
+                WormholeMerkleRoots,
+            },
+        },
+        crate::hashers::keccak256::Keccak256,
+        alloc::{
+            string::{
+                String,
+                ToString,
+            },
+            vec::Vec,
+        },
+        borsh::{
+            maybestd::io::Result,
+            BorshSerialize,
+        },
+    };
+
+    /// The wire type a payload is decoded as.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Kind {
+        WormholeMerkleRoot,
+        WormholeMerkleRoots,
+        CompressedAccumulatorUpdateData,
+        GovernanceInstruction,
+    }
+
+    /// A payload that decodes to `fields`, in the order they appear on the wire.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct ConformanceVector {
+        pub name:    &'static str,
+        pub kind:    Kind,
+        pub payload: &'static str,
+        pub fields:  &'static [(&'static str, &'static str)],
+    }
+
+    /// A payload that must fail to decode.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct InvalidVector {
+        pub name:    &'static str,
+        pub kind:    Kind,
+        pub payload: &'static str,
+    }
+
+    pub const VALID: &[ConformanceVector] = &[
+        ConformanceVector {
+            name:    "wormhole merkle root",
+            kind:    Kind::WormholeMerkleRoot,
+            payload: "4155575600000000071111111111111111111111111111111111111111111111111111111111111111",
+            fields:  &[
+                ("storage_id", "7"),
+                ("root", "1111111111111111111111111111111111111111111111111111111111111111"),
+            ],
+        },
+        ConformanceVector {
+            name:    "wormhole merkle roots",
+            kind:    Kind::WormholeMerkleRoots,
+            payload: "4155575601000000070211111111111111111111111111111111111111111111111111111111111111112222222222222222222222222222222222222222222222222222222222222222",
+            fields:  &[
+                ("storage_id", "7"),
+                ("roots", "1111111111111111111111111111111111111111111111111111111111111111,2222222222222222222222222222222222222222222222222222222222222222"),
+            ],
+        },
+        ConformanceVector {
+            name:    "wormhole merkle roots, empty",
+            kind:    Kind::WormholeMerkleRoots,
+            payload: "41555756010000000700",
+            fields:  &[("storage_id", "7"), ("roots", "")],
+        },
+        ConformanceVector {
+            name:    "uncompressed update",
+            kind:    Kind::CompressedAccumulatorUpdateData,
+            payload: "4155434400504e4155",
+            fields:  &[("compression", "0"), ("data", "504e4155")],
+        },
+        ConformanceVector {
+            name:    "upgrade contract",
+            kind:    Kind::GovernanceInstruction,
+            payload: "5054474d010000030000000000000007",
+            fields:  &[
+                ("target_chain_id", "3"),
+                ("action", "UpgradeContract"),
+                ("payload", "0000000000000007"),
+            ],
+        },
+        ConformanceVector {
+            name:    "authorize governance data source transfer",
+            kind:    Kind::GovernanceInstruction,
+            payload: "5054474d01010000010203",
+            fields:  &[
+                ("target_chain_id", "0"),
+                ("action", "AuthorizeGovernanceDataSourceTransfer"),
+                ("claim_vaa", "010203"),
+            ],
+        },
+        ConformanceVector {
+            name:    "set data sources",
+            kind:    Kind::GovernanceInstruction,
+            payload: "5054474d0102000001001a0101010101010101010101010101010101010101010101010101010101010101",
+            fields:  &[
+                ("target_chain_id", "0"),
+                ("action", "SetDataSources"),
+                ("data_sources", "26:0101010101010101010101010101010101010101010101010101010101010101"),
+            ],
+        },
+        ConformanceVector {
+            name:    "set fee",
+            kind:    Kind::GovernanceInstruction,
+            payload: "5054474d0103000200000000000000010000000000000003",
+            fields:  &[
+                ("target_chain_id", "2"),
+                ("action", "SetFee"),
+                ("value", "1"),
+                ("expo", "3"),
+            ],
+        },
+        ConformanceVector {
+            name:    "set valid period",
+            kind:    Kind::GovernanceInstruction,
+            payload: "5054474d01040000000000000000003c",
+            fields:  &[
+                ("target_chain_id", "0"),
+                ("action", "SetValidPeriod"),
+                ("valid_seconds", "60"),
+            ],
+        },
+        ConformanceVector {
+            name:    "request governance data source transfer",
+            kind:    Kind::GovernanceInstruction,
+            payload: "5054474d0105000000000001",
+            fields:  &[
+                ("target_chain_id", "0"),
+                ("action", "RequestGovernanceDataSourceTransfer"),
+                ("governance_data_source_index", "1"),
+            ],
+        },
+    ];
+
+    pub const INVALID: &[InvalidVector] = &[
+        InvalidVector {
+            name:    "wormhole merkle root, bad magic",
+            kind:    Kind::WormholeMerkleRoot,
+            payload: "4155575800000000071111111111111111111111111111111111111111111111111111111111111111",
+        },
+        InvalidVector {
+            name:    "wormhole merkle root, multi root update type",
+            kind:    Kind::WormholeMerkleRoot,
+            payload: "4155575601000000071111111111111111111111111111111111111111111111111111111111111111",
+        },
+        InvalidVector {
+            name:    "wormhole merkle root, truncated root",
+            kind:    Kind::WormholeMerkleRoot,
+            payload: "415557560000000007111111",
+        },
+        InvalidVector {
+            name:    "wormhole merkle root, trailing byte",
+            kind:    Kind::WormholeMerkleRoot,
+            payload: "415557560000000007111111111111111111111111111111111111111111111111111111111111111100",
+        },
+        InvalidVector {
+            name:    "wormhole merkle roots, missing root",
+            kind:    Kind::WormholeMerkleRoots,
+            payload: "415557560100000007021111111111111111111111111111111111111111111111111111111111111111",
+        },
+        InvalidVector {
+            name:    "compressed update, unknown compression",
+            kind:    Kind::CompressedAccumulatorUpdateData,
+            payload: "4155434409504e4155",
+        },
+        InvalidVector {
+            name:    "governance, executor module",
+            kind:    Kind::GovernanceInstruction,
+            payload: "5054474d000000000000000000000007",
+        },
+        InvalidVector {
+            name:    "governance, unknown action",
+            kind:    Kind::GovernanceInstruction,
+            payload: "5054474d01090000",
+        },
+        InvalidVector {
+            name:    "governance, truncated fee",
+            kind:    Kind::GovernanceInstruction,
+            payload: "5054474d01030002000000000000000100000000",
+        },
+    ];
+
+    /// Decoded fields as names and formatted values.
+    type Fields = Vec<(&'static str, String)>;
+
+    /// Decode `bytes` as `kind`, returning its fields and its encoding.
+    fn decode(kind: Kind, bytes: &[u8]) -> Result<(Fields, Vec<u8>)> {
+        let join = |values: Vec<String>| values.join(",");
+        Ok(match kind {
+            Kind::WormholeMerkleRoot => {
+                let root = from_slice_strict::<WormholeMerkleRoot<Keccak256>>(bytes)?;
+                let fields = vec![
+                    ("storage_id", root.storage_id.get().to_string()),
+                    ("root", hex::encode(root.root)),
+                ];
+                (fields, root.try_to_vec()?)
+            }
+            Kind::WormholeMerkleRoots => {
+                let roots = from_slice_strict::<WormholeMerkleRoots<Keccak256>>(bytes)?;
+                let fields = vec![
+                    ("storage_id", roots.storage_id.get().to_string()),
+                    ("roots", join(roots.roots.iter().map(hex::encode).collect())),
+                ];
+                (fields, roots.try_to_vec()?)
+            }
+            Kind::CompressedAccumulatorUpdateData => {
+                let update = from_slice_strict::<CompressedAccumulatorUpdateData>(bytes)?;
+                let fields = vec![
+                    ("compression", (update.compression as u8).to_string()),
+                    ("data", hex::encode(&update.data)),
+                ];
+                (fields, update.try_to_vec()?)
+            }
+            Kind::GovernanceInstruction => {
+                let instruction = from_slice_strict::<GovernanceInstruction>(bytes)?;
+                let mut fields = vec![(
+                    "target_chain_id",
+                    instruction.target_chain_id.get().to_string(),
+                )];
+                fields.extend(match &instruction.action {
+                    GovernanceAction::UpgradeContract { payload } => vec![
+                        ("action", "UpgradeContract".to_string()),
+                        ("payload", hex::encode(payload)),
+                    ],
+                    GovernanceAction::AuthorizeGovernanceDataSourceTransfer { claim_vaa } => vec![
+                        (
+                            "action",
+                            "AuthorizeGovernanceDataSourceTransfer".to_string(),
+                        ),
+                        ("claim_vaa", hex::encode(claim_vaa)),
+                    ],
+                    GovernanceAction::SetDataSources { data_sources } => vec![
+                        ("action", "SetDataSources".to_string()),
+                        (
+                            "data_sources",
+                            join(
+                                data_sources
+                                    .iter()
+                                    .map(|source| {
+                                        format!(
+                                            "{}:{}",
+                                            source.emitter_chain.get(),
+                                            hex::encode(source.emitter_address)
+                                        )
+                                    })
+                                    .collect(),
+                            ),
+                        ),
+                    ],
+                    GovernanceAction::SetFee { value, expo } => vec![
+                        ("action", "SetFee".to_string()),
+                        ("value", value.get().to_string()),
+                        ("expo", expo.get().to_string()),
+                    ],
+                    GovernanceAction::SetValidPeriod { valid_seconds } => vec![
+                        ("action", "SetValidPeriod".to_string()),
+                        ("valid_seconds", valid_seconds.get().to_string()),
+                    ],
+                    GovernanceAction::RequestGovernanceDataSourceTransfer {
+                        governance_data_source_index,
+                    } => vec![
+                        ("action", "RequestGovernanceDataSourceTransfer".to_string()),
+                        (
+                            "governance_data_source_index",
+                            governance_data_source_index.get().to_string(),
+                        ),
+                    ],
+                });
+                (fields, instruction.try_to_vec()?)
+            }
+        })
+    }
+
+    /// Check that `vector` decodes to its fields and encodes back to its payload.
+    pub fn check(vector: &ConformanceVector) -> core::result::Result<(), String> {
+        let payload = hex::decode(vector.payload).map_err(|e| e.to_string())?;
+        let (fields, encoded) = decode(vector.kind, &payload).map_err(|e| e.to_string())?;
+        let expected: Vec<(&str, String)> = vector
+            .fields
+            .iter()
+            .map(|&(name, value)| (name, value.to_string()))
+            .collect();
+        if fields != expected {
+            return Err(format!("Decoded {fields:?} but expected {expected:?}"));
+        }
+        if encoded != payload {
+            return Err(format!("Encoded back to {}", hex::encode(encoded)));
+        }
+        Ok(())
+    }
+
+    /// Check that `vector` fails to decode.
+    pub fn check_invalid(vector: &InvalidVector) -> core::result::Result<(), String> {
+        let payload = hex::decode(vector.payload).map_err(|e| e.to_string())?;
+        match decode(vector.kind, &payload) {
+            Ok((fields, _)) => Err(format!("Decoded {fields:?}")),
+            Err(_) => Ok(()),
+        }
+    }
+}
+
+/// Round-trip helpers and proptest strategies for the wire types.
+///
+/// Exported behind the `proptest` feature so that crates defining their own wire types can test
+/// them the same way this crate does.
+#[cfg(any(test, feature = "proptest"))]
+pub mod testing {
+    use {
+        super::v1::*,
+        crate::hashers::keccak256::Keccak256,
+        borsh::{
+            BorshDeserialize,
+            BorshSerialize,
+        },
+        core::fmt::Debug,
+        proptest::prelude::*,
+    };
+
+    /// Assert that `value` decodes back to itself and that the decoded value encodes to the same
+    /// bytes, returning the encoding.
+    pub fn assert_round_trip<T>(value: &T) -> Vec<u8>
+    where
+        T: BorshSerialize + BorshDeserialize + PartialEq + Debug,
+    {
+        let bytes = value.try_to_vec().unwrap();
+        let decoded = T::try_from_slice(&bytes).unwrap();
+        assert_eq!(&decoded, value);
+        assert_eq!(decoded.try_to_vec().unwrap(), bytes);
+        bytes
+    }
+
+    impl Arbitrary for WormholeMerkleRoot<Keccak256> {
+        type Parameters = ();
+
+        fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+            (any::<u32>(), any::<[u8; 32]>())
+                .prop_map(|(storage_id, root)| WormholeMerkleRoot {
+                    storage_id: storage_id.into(),
+                    root,
+                })
+                .boxed()
+        }
+
+        type Strategy = BoxedStrategy<Self>;
+    }
+
+    impl Arbitrary for WormholeMerkleRoots<Keccak256> {
+        type Parameters = ();
+
+        fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+            (
+                any::<u32>(),
+                prop::collection::vec(any::<[u8; 32]>(), 0..=u8::MAX as usize),
+            )
+                .prop_map(|(storage_id, roots)| WormholeMerkleRoots {
+                    storage_id: storage_id.into(),
+                    roots,
+                })
+                .boxed()
+        }
+
+        type Strategy = BoxedStrategy<Self>;
+    }
+
+    impl Arbitrary for WormholePayload<Keccak256> {
+        type Parameters = ();
+
+        fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+            prop_oneof![
+                any::<WormholeMerkleRoot<Keccak256>>().prop_map(WormholePayload::MerkleRoot),
+                any::<WormholeMerkleRoots<Keccak256>>().prop_map(WormholePayload::MerkleRoots),
+            ]
+            .boxed()
+        }
+
+        type Strategy = BoxedStrategy<Self>;
+    }
+
+    impl Arbitrary for CompressedAccumulatorUpdateData {
+        type Parameters = ();
+
+        fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+            (
+                prop_oneof![Just(UpdateCompression::None), Just(UpdateCompression::Zstd)],
+                prop::collection::vec(any::<u8>(), 0..1024),
+            )
+                .prop_map(|(compression, data)| CompressedAccumulatorUpdateData {
+                    compression,
+                    data,
+                })
+                .boxed()
+        }
+
+        type Strategy = BoxedStrategy<Self>;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{
+            from_slice_strict,
+            testing::assert_round_trip,
+            v1::*,
+            SerializedSize,
+        },
+        crate::{
+            accumulators::{
+                merkle::MerkleAccumulator,
+                Accumulator,
+            },
+            hashers::keccak256::Keccak256,
+        },
+        borsh::{
+            BorshDeserialize,
+            BorshSerialize,
+        },
+        proptest::prelude::*,
+    };
+
+    #[test]
+    fn test_wormhole_merkle_root() {
+        let items: Vec<&[u8]> = vec![b"a", b"b", b"c"];
+        let accumulator = MerkleAccumulator::<Keccak256>::from_set(items.into_iter()).unwrap();
+        let root = WormholeMerkleRoot::from_accumulator(&accumulator, 7);
+
+        let bytes = root.try_to_vec().unwrap();
+        assert_eq!(&bytes[..4], b"AUWV");
+        assert_eq!(bytes[4], WORMHOLE_MERKLE_UPDATE_TYPE);
+        assert_eq!(&bytes[5..9], &7u32.to_be_bytes());
+        assert_eq!(&bytes[9..], &accumulator.root);
+        #[allow(deprecated)]
+        let legacy = accumulator.serialize(7);
+        assert_eq!(bytes, legacy);
+
+        assert_eq!(WormholeMerkleRoot::try_from_slice(&bytes).unwrap(), root);
+
+        // Errors point at the field that failed to parse.
+        let error = |bytes: &[u8]| {
+            WormholeMerkleRoot::<Keccak256>::try_from_slice(bytes)
+                .unwrap_err()
+                .to_string()
+        };
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = b'X';
+        assert!(error(&bad_magic).starts_with("Magic mismatch at offset 0"));
+
+        let mut bad_update_type = bytes.clone();
+        bad_update_type[4] = 1;
+        assert_eq!(
+            error(&bad_update_type),
+            "Unsupported update type 1 at offset 4"
+        );
+
+        assert_eq!(
+            error(&bytes[..20]),
+            "Not enough bytes for root at offset 9: expected 32 but got 11"
+        );
+        assert_eq!(
+            error(&bytes[..5]),
+            "Not enough bytes for header at offset 0: expected 9 but got 5"
+        );
+        assert!(
+            WormholeMerkleRoot::<Keccak256>::try_from_slice(&[bytes, vec![0]].concat()).is_err()
+        );
+    }
+
+    #[test]
+    fn test_wormhole_merkle_roots() {
+        let prices: Vec<&[u8]> = vec![b"a", b"b", b"c"];
+        let prices = MerkleAccumulator::<Keccak256>::from_set(prices.into_iter()).unwrap();
+        let governance: Vec<&[u8]> = vec![b"d"];
+        let governance = MerkleAccumulator::<Keccak256>::from_set(governance.into_iter()).unwrap();
+        let roots = WormholeMerkleRoots::from_accumulators(&[&prices, &governance], 7);
+
+        let bytes = roots.try_to_vec().unwrap();
+        assert_eq!(bytes.len(), 10 + 2 * 32);
+        assert_eq!(&bytes[..4], b"AUWV");
+        assert_eq!(bytes[4], WORMHOLE_MULTI_MERKLE_UPDATE_TYPE);
+        assert_eq!(&bytes[5..9], &7u32.to_be_bytes());
+        assert_eq!(bytes[9], 2);
+        assert_eq!(&bytes[10..42], &prices.root);
+        assert_eq!(&bytes[42..], &governance.root);
+        assert_eq!(WormholeMerkleRoots::try_from_slice(&bytes).unwrap(), roots);
+
+        assert_eq!(
+            WormholeMerkleRoots::<Keccak256>::try_from_slice(&bytes[..60])
+                .unwrap_err()
+                .to_string(),
+            "Not enough bytes for root 1 at offset 42: expected 32 but got 18"
+        );
+
+        // Single and multi root payloads both parse as a `WormholePayload`.
+        let payload = WormholePayload::<Keccak256>::try_from_slice(&bytes).unwrap();
+        assert_eq!(payload, WormholePayload::MerkleRoots(roots));
+        assert_eq!(payload.roots(), &[prices.root, governance.root]);
+
+        let root = WormholeMerkleRoot::from_accumulator(&prices, 7);
+        let payload =
+            WormholePayload::<Keccak256>::try_from_slice(&root.try_to_vec().unwrap()).unwrap();
+        assert_eq!(payload, WormholePayload::MerkleRoot(root.clone()));
+        assert_eq!(payload.storage_id(), 7);
+        assert_eq!(payload.roots(), &[prices.root]);
+        assert_eq!(WormholeMerkleRoots::from(root).roots, vec![prices.root]);
+
+        let mut unknown = bytes;
+        unknown[4] = 2;
+        assert!(WormholePayload::<Keccak256>::try_from_slice(&unknown).is_err());
+    }
+
+    #[test]
+    fn test_compressed_accumulator_update_data() {
+        let update = [b"PNAU".to_vec(), vec![7; 256]].concat();
+
+        // Raw updates pass through untouched.
+        assert!(!CompressedAccumulatorUpdateData::is_compressed(&update));
+        assert!(matches!(
+            CompressedAccumulatorUpdateData::decompress_update(&update).unwrap(),
+            std::borrow::Cow::Borrowed(_)
+        ));
+
+        let uncompressed = CompressedAccumulatorUpdateData {
+            compression: UpdateCompression::None,
+            data:        update.clone(),
+        };
+        let bytes = uncompressed.try_to_vec().unwrap();
+        assert_eq!(&bytes[..5], b"AUCD\0");
+        assert_eq!(
+            CompressedAccumulatorUpdateData::try_from_slice(&bytes).unwrap(),
+            uncompressed
+        );
+        assert_eq!(
+            CompressedAccumulatorUpdateData::decompress_update(&bytes).unwrap(),
+            &update[..]
+        );
+
+        #[cfg(feature = "compression")]
+        {
+            let compressed = CompressedAccumulatorUpdateData::compress(&update, 3).unwrap();
+            let bytes = compressed.try_to_vec().unwrap();
+            assert_eq!(bytes[4], 1);
+            assert!(bytes.len() < update.len());
+            assert_eq!(
+                CompressedAccumulatorUpdateData::decompress_update(&bytes).unwrap(),
+                &update[..]
+            );
+
+            let bomb = vec![0; MAX_DECOMPRESSED_UPDATE_SIZE + 1];
+            let bomb = CompressedAccumulatorUpdateData::compress(&bomb, 3).unwrap();
+            assert!(bomb.decompress().is_err());
+        }
+
+        let mut unknown = bytes;
+        unknown[4] = 9;
+        assert_eq!(
+            CompressedAccumulatorUpdateData::decompress_update(&unknown)
+                .unwrap_err()
+                .to_string(),
+            "Unsupported compression 9 at offset 4"
+        );
+    }
+
+    #[test]
+    fn test_update_data_stream() {
+        let updates: [&[u8]; 3] = [b"first", b"", b"third"];
+        let framed = frame_updates(&updates).unwrap();
+        assert_eq!(&framed[..8], b"AUFS\0\0\0\x05");
+
+        let mut stream = UpdateDataStream::new(&framed).unwrap();
+        for update in updates {
+            assert_eq!(stream.next_update().unwrap().unwrap(), update);
+        }
+        assert!(stream.next_update().is_none());
+
+        let parsed: Vec<&[u8]> = UpdateDataStream::new(&framed)
+            .unwrap()
+            .collect::<std::io::Result<_>>()
+            .unwrap();
+        assert_eq!(parsed, updates);
+        assert_eq!(
+            UpdateDataStream::new(&frame_updates(&[] as &[&[u8]]).unwrap())
+                .unwrap()
+                .count(),
+            0
+        );
+
+        // A truncated frame is reported once, with the offset of its length prefix.
+        let mut stream = UpdateDataStream::new(&framed[..framed.len() - 1]).unwrap();
+        assert_eq!(stream.next_update().unwrap().unwrap(), b"first");
+        assert_eq!(stream.next_update().unwrap().unwrap(), b"");
+        assert_eq!(
+            stream.next_update().unwrap().unwrap_err().to_string(),
+            "Truncated update frame at offset 17"
+        );
+        assert!(stream.next_update().is_none());
+
+        assert!(UpdateDataStream::new(b"first").is_err());
+    }
+
+    #[test]
+    fn test_proof() {
+        use crate::accumulators::merkle::{
+            AggregatedProof,
+            SortedMerkleAccumulator,
+        };
+
+        let items: Vec<&[u8]> = vec![b"a", b"c", b"e"];
+        let accumulator = MerkleAccumulator::<Keccak256>::new(&items).unwrap();
+        let sorted = SortedMerkleAccumulator::<Keccak256>::new(&items).unwrap();
+        let multi = accumulator.prove_many(&[b"a", b"e"]).unwrap();
+
+        for proof in [
+            Proof::MerklePath(accumulator.prove(b"c").unwrap()),
+            Proof::MerkleMultiProof(multi.clone()),
+            Proof::AggregatedProof(AggregatedProof::new(vec![multi])),
+            Proof::AbsenceProof(sorted.prove_absence(b"d").unwrap()),
+            Proof::AbsenceProof(sorted.prove_absence(b"0").unwrap()),
+            Proof::AggregatedProof(AggregatedProof::default()),
+        ] {
+            let bytes = assert_round_trip(&proof);
+            assert_eq!(bytes.len(), proof.serialized_size());
+            assert_eq!(bytes[0], proof.tag());
+            assert!(CORE_PROOF_TAGS.contains(&proof.tag()));
+            assert_eq!(&bytes[1..5], &(bytes.len() as u32 - 5).to_be_bytes());
+            for len in 0..bytes.len() {
+                assert!(Proof::<Keccak256>::try_from_slice(&bytes[..len]).is_err());
+            }
+        }
+
+        // Unassigned tags are preserved byte for byte, whatever their range.
+        for tag in [
+            4,
+            *EXPERIMENTAL_PROOF_TAGS.start(),
+            *VENDOR_PROOF_TAGS.end(),
+        ] {
+            let bytes = [&[tag, 0, 0, 0, 3][..], &[1, 2, 3]].concat();
+            let proof = Proof::<Keccak256>::try_from_slice(&bytes).unwrap();
+            assert_eq!(
+                proof,
+                Proof::Unknown {
+                    tag,
+                    bytes: vec![1, 2, 3],
+                }
+            );
+            assert_eq!(proof.try_to_vec().unwrap(), bytes);
+            assert_eq!(proof.serialized_size(), bytes.len());
+        }
+
+        // A known proof must fill its length exactly, and known tags can't be sent as unknown.
+        let mut padded = Proof::MerklePath(accumulator.prove(b"a").unwrap())
+            .try_to_vec()
+            .unwrap();
+        padded[4] += 1;
+        padded.push(0);
+        assert!(Proof::<Keccak256>::try_from_slice(&padded).is_err());
+        assert!(Proof::<Keccak256>::Unknown {
+            tag:   MERKLE_PATH_PROOF,
+            bytes: vec![],
+        }
+        .try_to_vec()
+        .is_err());
+    }
+
+    // A relayer built against this version must forward proofs of types added later, mixed with
+    // the ones it knows, without changing a byte.
+    #[test]
+    fn test_forward_unknown_proof() {
+        let items: Vec<&[u8]> = vec![b"a", b"b"];
+        let accumulator = MerkleAccumulator::<Keccak256>::new(&items).unwrap();
+        let known = Proof::MerklePath(accumulator.prove(b"a").unwrap());
+        let newer = [&[4, 0, 0, 0, 2][..], &[7, 7]].concat();
+        let vendor = vec![200, 0, 0, 0, 0];
+        let bytes = [
+            known.try_to_vec().unwrap(),
+            newer,
+            vendor.clone(),
+            known.try_to_vec().unwrap(),
+        ]
+        .concat();
+
+        let mut buf = &bytes[..];
+        let mut proofs = vec![];
+        while !buf.is_empty() {
+            proofs.push(Proof::<Keccak256>::deserialize(&mut buf).unwrap());
+        }
+        assert_eq!(
+            proofs,
+            [
+                known.clone(),
+                Proof::Unknown {
+                    tag:   4,
+                    bytes: vec![7, 7],
+                },
+                Proof::Unknown {
+                    tag:   200,
+                    bytes: vec![],
+                },
+                known,
+            ]
+        );
+        let forwarded: Vec<u8> = proofs
+            .iter()
+            .flat_map(|proof| proof.try_to_vec().unwrap())
+            .collect();
+        assert_eq!(forwarded, bytes);
+
+        // An unknown proof still has to be complete to be forwarded.
+        assert!(Proof::<Keccak256>::try_from_slice(&[4, 0, 0, 0, 2, 7]).is_err());
+        assert!(Proof::<Keccak256>::try_from_slice(&vendor[..4]).is_err());
+        // The largest length is rejected rather than wrapping, whatever the pointer width.
+        assert!(Proof::<Keccak256>::try_from_slice(&[4, 0xff, 0xff, 0xff, 0xff]).is_err());
+    }
+
+    #[test]
+    fn test_governance_instruction() {
+        use super::governance::*;
+
+        let set_fee = GovernanceInstruction {
+            target_chain_id: 2.into(),
+            action:          GovernanceAction::SetFee {
+                value: 1.into(),
+                expo:  3.into(),
+            },
+        };
+        let bytes = assert_round_trip(&set_fee);
+        assert_eq!(bytes.len(), set_fee.serialized_size());
+        assert_eq!(
+            hex::encode(&bytes),
+            "5054474d0103000200000000000000010000000000000003"
+        );
+        assert!(set_fee.targets(2));
+        assert!(!set_fee.targets(3));
+
+        for action in [
+            GovernanceAction::UpgradeContract {
+                payload: vec![0, 0, 0, 0, 0, 0, 0, 7],
+            },
+            GovernanceAction::AuthorizeGovernanceDataSourceTransfer {
+                claim_vaa: vec![1; 100],
+            },
+            GovernanceAction::SetDataSources {
+                data_sources: vec![],
+            },
+            GovernanceAction::SetDataSources {
+                data_sources: vec![
+                    DataSource {
+                        emitter_chain:   26.into(),
+                        emitter_address: [1; 32],
+                    },
+                    DataSource {
+                        emitter_chain:   1.into(),
+                        emitter_address: [2; 32],
+                    },
+                ],
+            },
+            GovernanceAction::SetValidPeriod {
+                valid_seconds: 60.into(),
+            },
+            GovernanceAction::RequestGovernanceDataSourceTransfer {
+                governance_data_source_index: 1.into(),
+            },
+        ] {
+            let instruction = GovernanceInstruction {
+                target_chain_id: ALL_CHAINS.into(),
+                action,
+            };
+            assert!(instruction.targets(2));
+            let bytes = assert_round_trip(&instruction);
+            assert_eq!(bytes.len(), instruction.serialized_size());
+            assert_eq!(bytes[5], instruction.action.action_id());
+        }
+
+        let error = |bytes: &[u8]| {
+            GovernanceInstruction::try_from_slice(bytes)
+                .unwrap_err()
+                .to_string()
+        };
+
+        let mut bad_module = bytes.clone();
+        bad_module[4] = 0;
+        assert_eq!(error(&bad_module), "Unsupported module 0 at offset 4");
+
+        let mut bad_action = bytes.clone();
+        bad_action[5] = 9;
+        assert_eq!(error(&bad_action), "Unsupported action 9 at offset 5");
+
+        assert_eq!(
+            error(&bytes[..20]),
+            "Not enough bytes for fee exponent at offset 16: expected 8 but got 4"
+        );
+        assert!(error(b"PTGX\x01\x03\x00\x02").starts_with("Magic mismatch at offset 0"));
+        assert!(GovernanceInstruction::try_from_slice(&[bytes, vec![0]].concat()).is_err());
+    }
+
+    #[test]
+    fn test_from_slice_strict() {
+        let root = WormholeMerkleRoot::<Keccak256> {
+            storage_id: 7.into(),
+            root:       [1; 32],
+        };
+        let bytes = root.try_to_vec().unwrap();
+        assert_eq!(
+            from_slice_strict::<WormholeMerkleRoot<Keccak256>>(&bytes).unwrap(),
+            root
+        );
+
+        // Deserializing on its own stops at the end of the root, strict parsing rejects the rest.
+        let padded = [bytes, vec![0, 0]].concat();
+        assert_eq!(
+            WormholeMerkleRoot::<Keccak256>::deserialize(&mut &padded[..]).unwrap(),
+            root
+        );
+        assert_eq!(
+            from_slice_strict::<WormholeMerkleRoot<Keccak256>>(&padded)
+                .unwrap_err()
+                .to_string(),
+            "2 trailing bytes at offset 41"
+        );
+
+        let proof = [&[200, 0, 0, 0, 1][..], &[7, 7]].concat();
+        assert!(Proof::<Keccak256>::deserialize(&mut &proof[..]).is_ok());
+        assert!(from_slice_strict::<Proof<Keccak256>>(&proof).is_err());
+        assert!(from_slice_strict::<Proof<Keccak256>>(&proof[..6]).is_ok());
+    }
+
+    #[test]
+    fn test_endian() {
+        use super::endian::*;
+
+        assert_eq!(BeU32(0x01020304).try_to_vec().unwrap(), [1, 2, 3, 4]);
+        assert_eq!(LeU32(0x01020304).try_to_vec().unwrap(), [4, 3, 2, 1]);
+        assert_eq!(BeU16::try_from_slice(&[1, 2]).unwrap().get(), 0x0102);
+        assert_eq!(LeU16::try_from_slice(&[1, 2]).unwrap().get(), 0x0201);
+        assert_eq!(assert_round_trip(&BeU64(7)), [0, 0, 0, 0, 0, 0, 0, 7]);
+        assert_eq!(BeU16(7).serialized_size(), 2);
+        assert_eq!(LeU32(7).serialized_size(), 4);
+        assert_eq!(BeU64(7).serialized_size(), 8);
+        assert_eq!(assert_round_trip(&LeU64(7)), [7, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(u64::from(BeU64::from(7)), 7);
+        assert!(BeU64::try_from_slice(&[0; 7]).is_err());
+    }
+
+    #[test]
+    fn test_conformance() {
+        use super::conformance::*;
+
+        for vector in VALID {
+            assert_eq!(check(vector), Ok(()), "{}", vector.name);
+        }
+        for vector in INVALID {
+            assert_eq!(check_invalid(vector), Ok(()), "{}", vector.name);
+        }
+    }
+
+    #[test]
+    fn test_schema() {
+        use super::governance::*;
+
+        let root = WormholeMerkleRoot::<Keccak256>::default();
+        let schema = WormholeMerkleRoot::<Keccak256>::schema();
+        assert_eq!(schema.size(), root.try_to_vec().unwrap().len());
+        let names: Vec<_> = schemas().iter().map(|schema| schema.name).collect();
+        assert_eq!(
+            names,
+            [
+                "WormholeMerkleRoot",
+                "WormholeMerkleRoots",
+                "CompressedAccumulatorUpdateData",
+                "FramedUpdates",
+                "Proof",
+                "GovernanceInstruction::UpgradeContract",
+                "GovernanceInstruction::AuthorizeGovernanceDataSourceTransfer",
+                "GovernanceInstruction::SetDataSources",
+                "GovernanceInstruction::SetFee",
+                "GovernanceInstruction::SetValidPeriod",
+                "GovernanceInstruction::RequestGovernanceDataSourceTransfer",
+            ]
+        );
+
+        assert_eq!(
+            serde_json::to_value(&schema).unwrap(),
+            serde_json::json!({
+                "name": "WormholeMerkleRoot",
+                "fields": [
+                    {"name": "magic", "offset": 0, "size": 4, "encoding": "bytes", "constant": "41555756"},
+                    {"name": "update_type", "offset": 4, "size": 1, "encoding": "u8", "constant": "00"},
+                    {"name": "storage_id", "offset": 5, "size": 4, "encoding": "u32_be"},
+                    {"name": "root", "offset": 9, "size": 32, "encoding": "hash"},
+                ],
+            })
+        );
+
+        // Repeated fields add `size` bytes per element to the minimum size.
+        let roots = WormholeMerkleRoots::<Keccak256> {
+            storage_id: 7.into(),
+            roots:      vec![[1; 32], [2; 32]],
+        };
+        let schema = WormholeMerkleRoots::<Keccak256>::schema();
+        assert_eq!(schema.size() + 2 * 32, roots.try_to_vec().unwrap().len());
+        assert_eq!(
+            serde_json::to_value(&schema).unwrap()["fields"],
+            serde_json::json!([
+                {"name": "magic", "offset": 0, "size": 4, "encoding": "bytes", "constant": "41555756"},
+                {"name": "update_type", "offset": 4, "size": 1, "encoding": "u8", "constant": "01"},
+                {"name": "storage_id", "offset": 5, "size": 4, "encoding": "u32_be"},
+                {"name": "count", "offset": 9, "size": 1, "encoding": "u8"},
+                {"name": "roots", "offset": 10, "size": 32, "encoding": "hash", "repeat": {"count": "count"}},
+            ])
+        );
+
+        let update = CompressedAccumulatorUpdateData {
+            compression: UpdateCompression::None,
+            data:        vec![1, 2, 3],
+        };
+        let schema = CompressedAccumulatorUpdateData::schema();
+        assert_eq!(schema.size() + 3, update.try_to_vec().unwrap().len());
+        assert_eq!(
+            serde_json::to_value(&schema).unwrap()["fields"][2],
+            serde_json::json!({"name": "data", "offset": 5, "size": 1, "encoding": "bytes", "repeat": "trailing"})
+        );
+
+        let framed = frame_updates(&[b"first".as_ref(), b""]).unwrap();
+        let schema = UpdateDataStream::schema();
+        assert_eq!(schema.size() + 2 * 4 + 5, framed.len());
+        assert_eq!(schema.fields[1].encoding, super::schema::Encoding::Frame);
+
+        let proof = Proof::<Keccak256>::Unknown {
+            tag:   *VENDOR_PROOF_TAGS.end(),
+            bytes: vec![1, 2, 3],
+        };
+        assert_eq!(
+            Proof::<Keccak256>::schema().size() + 3,
+            proof.try_to_vec().unwrap().len()
+        );
+
+        // Every governance action is described with its full instruction header and payload.
+        let data_source = DataSource {
+            emitter_chain:   26.into(),
+            emitter_address: [1; 32],
+        };
+        let instructions = [
+            (
+                GovernanceAction::UpgradeContract {
+                    payload: vec![7; 3],
+                },
+                3,
+            ),
+            (
+                GovernanceAction::AuthorizeGovernanceDataSourceTransfer {
+                    claim_vaa: vec![1; 3],
+                },
+                3,
+            ),
+            (
+                GovernanceAction::SetDataSources {
+                    data_sources: vec![data_source; 3],
+                },
+                3 * 34,
+            ),
+            (
+                GovernanceAction::SetFee {
+                    value: 1.into(),
+                    expo:  3.into(),
+                },
+                0,
+            ),
+            (
+                GovernanceAction::SetValidPeriod {
+                    valid_seconds: 60.into(),
+                },
+                0,
+            ),
+            (
+                GovernanceAction::RequestGovernanceDataSourceTransfer {
+                    governance_data_source_index: 1.into(),
+                },
+                0,
+            ),
+        ];
+        let schemas = GovernanceInstruction::schemas();
+        assert_eq!(schemas.len(), instructions.len());
+        for (schema, (action, repeated)) in schemas.iter().zip(instructions) {
+            let bytes = GovernanceInstruction {
+                target_chain_id: 2.into(),
+                action,
+            }
+            .try_to_vec()
+            .unwrap();
+            assert_eq!(schema.size() + repeated, bytes.len(), "{}", schema.name);
+            assert_eq!(schema.fields[2].constant, Some(hex::encode(&bytes[5..6])));
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_round_trip(
+            root in any::<WormholeMerkleRoot<Keccak256>>(),
+            payload in any::<WormholePayload<Keccak256>>(),
+            update in any::<CompressedAccumulatorUpdateData>(),
+        ) {
+            let header = match payload {
+                WormholePayload::MerkleRoot(_) => 9,
+                WormholePayload::MerkleRoots(_) => 10,
+            };
+            let bytes = assert_round_trip(&payload);
+            assert_eq!(bytes.len(), header + 32 * payload.roots().len());
+            assert_eq!(bytes.len(), payload.serialized_size());
+            assert_eq!(assert_round_trip(&root).len(), root.serialized_size());
+            assert_eq!(assert_round_trip(&update).len(), update.serialized_size());
+        }
+    }
+}